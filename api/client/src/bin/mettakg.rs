@@ -0,0 +1,393 @@
+use chrono::Utc;
+use metta_kg_client::profile::Profile;
+use metta_kg_client::{CSVParseDirection, ExportFormat, Mm2Input, Mm2InputMulti, Token};
+use pico_args::Arguments;
+use rocket::serde::json::from_str;
+use rocket::tokio::runtime::Runtime;
+use std::fs;
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+
+const USAGE: &str = "\
+Calls the MeTTa-KG API.
+
+Usage:
+  mettakg [--profile <name>] <command>
+
+Commands:
+  upload <namespace> <path>...            .metta files, or directories of them
+  transform <namespace> <file>            {\"patterns\": [...], \"templates\": [...]}
+  export <namespace> [--pattern <atom>] [--template <atom>]
+         [--format metta|json|csv|raw] [--output <file>]
+  tokens list
+  tokens current
+  tokens create --namespace <namespace> [--description <text>] [--read]
+                [--write] [--share-read] [--share-write]
+  tokens refresh <id>
+  tokens delete <id>...                   also deletes the tokens derived from them
+  translate csv <file> [--direction row|column|cell-unlabeled|cell-labeled]
+                [--delimiter <delimiter>]
+  translate nt|jsonld|n3 <file>
+
+A file of `-` is read from stdin. Without `--output`, exports and translations
+are written to stdout.
+
+The API and the token are read from the profile, `METTA_KG_PROFILE` or
+`default`, of ~/.config/mettakg/config.toml, or the file named by
+`METTA_KG_CONFIG`. `METTA_KG_URL` and `METTA_KG_TOKEN` take precedence.
+";
+
+fn main() -> ExitCode {
+    let mut args = Arguments::from_env();
+
+    if args.contains(["-h", "--help"]) {
+        print!("{USAGE}");
+        return ExitCode::SUCCESS;
+    }
+
+    match run(args) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("{e}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+enum Command {
+    Upload {
+        namespace: String,
+        paths: Vec<PathBuf>,
+    },
+    Transform {
+        namespace: String,
+        file: String,
+    },
+    Export {
+        namespace: String,
+        input: Mm2Input,
+        format: ExportFormat,
+        output: Option<String>,
+    },
+    ListTokens,
+    CurrentToken,
+    CreateToken(Token),
+    RefreshToken(i32),
+    DeleteTokens(Vec<i32>),
+    TranslateCsv {
+        file: String,
+        direction: CSVParseDirection,
+        delimiter: String,
+    },
+    Translate {
+        format: String,
+        file: String,
+    },
+}
+
+/// The command given by `args`, if any
+fn parse(args: &mut Arguments) -> Result<Option<Command>, pico_args::Error> {
+    let command = args.subcommand()?;
+
+    let command = match command.as_deref() {
+        Some("upload") => Command::Upload {
+            namespace: args.free_from_str()?,
+            paths: free(args)?,
+        },
+        Some("transform") => Command::Transform {
+            namespace: args.free_from_str()?,
+            file: args.free_from_str()?,
+        },
+        Some("export") => Command::Export {
+            input: Mm2Input {
+                pattern: args
+                    .opt_value_from_str("--pattern")?
+                    .unwrap_or_else(|| "$x".to_string()),
+                template: args
+                    .opt_value_from_str("--template")?
+                    .unwrap_or_else(|| "$x".to_string()),
+            },
+            format: args
+                .opt_value_from_fn("--format", export_format)?
+                .unwrap_or_default(),
+            output: args.opt_value_from_str("--output")?,
+            namespace: args.free_from_str()?,
+        },
+        Some("tokens") => match args.subcommand()?.as_deref() {
+            Some("list") => Command::ListTokens,
+            Some("current") => Command::CurrentToken,
+            Some("create") => Command::CreateToken(Token {
+                id: 0,
+                code: String::new(),
+                description: args
+                    .opt_value_from_str("--description")?
+                    .unwrap_or_default(),
+                namespace: args.value_from_str("--namespace")?,
+                creation_timestamp: Utc::now().naive_utc(),
+                permission_read: args.contains("--read"),
+                permission_write: args.contains("--write"),
+                permission_share_share: false,
+                permission_share_read: args.contains("--share-read"),
+                permission_share_write: args.contains("--share-write"),
+                parent: None,
+                rate_limit_read: None,
+                rate_limit_write: None,
+                rate_limit_translate: None,
+                concurrency_limit_read: None,
+                concurrency_limit_write: None,
+                concurrency_limit_translate: None,
+            }),
+            Some("refresh") => Command::RefreshToken(args.free_from_str()?),
+            Some("delete") => Command::DeleteTokens(free(args)?),
+            _ => return Ok(None),
+        },
+        Some("translate") => match args.subcommand()?.as_deref() {
+            Some("csv") => Command::TranslateCsv {
+                direction: args
+                    .opt_value_from_fn("--direction", csv_direction)?
+                    .unwrap_or(CSVParseDirection::Row),
+                delimiter: args
+                    .opt_value_from_str("--delimiter")?
+                    .unwrap_or_else(|| ",".to_string()),
+                file: args.free_from_str()?,
+            },
+            Some(format @ ("nt" | "jsonld" | "n3")) => Command::Translate {
+                format: format.to_string(),
+                file: args.free_from_str()?,
+            },
+            _ => return Ok(None),
+        },
+        _ => return Ok(None),
+    };
+
+    Ok(Some(command))
+}
+
+/// The remaining free arguments, at least one
+fn free<T: std::str::FromStr>(args: &mut Arguments) -> Result<Vec<T>, pico_args::Error>
+where
+    T::Err: std::fmt::Display,
+{
+    let mut values = vec![args.free_from_str()?];
+    while let Some(value) = args.opt_free_from_str()? {
+        values.push(value);
+    }
+
+    Ok(values)
+}
+
+fn export_format(format: &str) -> Result<ExportFormat, String> {
+    match format {
+        "metta" => Ok(ExportFormat::Metta),
+        "json" => Ok(ExportFormat::Json),
+        "csv" => Ok(ExportFormat::Csv),
+        "raw" => Ok(ExportFormat::Raw),
+        _ => Err("expected metta, json, csv or raw".to_string()),
+    }
+}
+
+fn csv_direction(direction: &str) -> Result<CSVParseDirection, String> {
+    match direction {
+        "row" => Ok(CSVParseDirection::Row),
+        "column" => Ok(CSVParseDirection::Column),
+        "cell-unlabeled" => Ok(CSVParseDirection::CellUnlabeled),
+        "cell-labeled" => Ok(CSVParseDirection::CellLabeled),
+        _ => Err("expected row, column, cell-unlabeled or cell-labeled".to_string()),
+    }
+}
+
+fn run(mut args: Arguments) -> Result<(), String> {
+    let profile: Option<String> = args
+        .opt_value_from_str("--profile")
+        .map_err(|e| format!("{e}\n\n{USAGE}"))?;
+
+    let command = match parse(&mut args) {
+        Ok(Some(command)) => command,
+        Ok(None) => return Err(USAGE.to_string()),
+        Err(e) => return Err(format!("{e}\n\n{USAGE}")),
+    };
+
+    let unused = args.finish();
+    if !unused.is_empty() {
+        return Err(format!("unexpected arguments {unused:?}\n\n{USAGE}"));
+    }
+
+    let profile = Profile::load(profile.as_deref())?;
+    let client = profile.client();
+    let runtime = Runtime::new().map_err(|e| e.to_string())?;
+
+    runtime.block_on(async {
+        match command {
+            Command::Upload { namespace, paths } => {
+                let mut files = vec![];
+                for path in &paths {
+                    files
+                        .extend(metta_files(path).map_err(|e| format!("{}: {e}", path.display()))?);
+                }
+                if files.is_empty() {
+                    return Err("No .metta files to upload".to_string());
+                }
+
+                for file in files {
+                    let atoms = read(&file.to_string_lossy())?;
+                    client
+                        .upload(&namespace, &String::from_utf8_lossy(&atoms))
+                        .await
+                        .map_err(|e| format!("{}: {e}", file.display()))?;
+                    println!("Uploaded {}", file.display());
+                }
+            }
+            Command::Transform { namespace, file } => {
+                let json = String::from_utf8_lossy(&read(&file)?).into_owned();
+                let input: Mm2InputMulti = from_str(&json).map_err(|e| format!("{file}: {e}"))?;
+
+                client
+                    .transform(&namespace, &input)
+                    .await
+                    .map_err(|e| e.to_string())?;
+                println!("Transformed {namespace}");
+            }
+            Command::Export {
+                namespace,
+                input,
+                format,
+                output,
+            } => {
+                let data = client
+                    .export_as(&namespace, &input, format)
+                    .await
+                    .map_err(|e| e.to_string())?;
+                write(output, &data)?;
+            }
+            Command::ListTokens => {
+                for token in client.tokens().await.map_err(|e| e.to_string())? {
+                    println!("{}", describe(&token));
+                }
+            }
+            Command::CurrentToken => {
+                let token = client.current_token().await.map_err(|e| e.to_string())?;
+                println!("{}", describe(&token));
+            }
+            Command::CreateToken(new_token) => {
+                let token = client
+                    .create_token(&new_token)
+                    .await
+                    .map_err(|e| e.to_string())?;
+                println!("{}", describe(&token));
+                println!("{}", token.code);
+            }
+            Command::RefreshToken(id) => {
+                let token = client.refresh_token(id).await.map_err(|e| e.to_string())?;
+                println!("{}", token.code);
+            }
+            Command::DeleteTokens(ids) => {
+                let deleted = client
+                    .delete_tokens(&ids)
+                    .await
+                    .map_err(|e| e.to_string())?;
+                println!("Deleted {deleted} tokens");
+            }
+            Command::TranslateCsv {
+                file,
+                direction,
+                delimiter,
+            } => {
+                let metta = client
+                    .translate_csv(read(&file)?, direction, &delimiter)
+                    .await
+                    .map_err(|e| e.to_string())?;
+                write(None, &metta)?;
+            }
+            Command::Translate { format, file } => {
+                let file = read(&file)?;
+                let metta = match format.as_str() {
+                    "nt" => client.translate_nt(file).await,
+                    "jsonld" => client.translate_jsonld(file).await,
+                    _ => client.translate_n3(file).await,
+                }
+                .map_err(|e| e.to_string())?;
+                write(None, &metta)?;
+            }
+        }
+
+        Ok(())
+    })
+}
+
+/// `path` if it is a file, or the `.metta` files under it, in order
+fn metta_files(path: &Path) -> io::Result<Vec<PathBuf>> {
+    if !path.is_dir() {
+        return Ok(vec![path.to_path_buf()]);
+    }
+
+    let mut files = vec![];
+    let mut entries: Vec<PathBuf> = fs::read_dir(path)?
+        .map(|entry| entry.map(|entry| entry.path()))
+        .collect::<io::Result<_>>()?;
+    entries.sort();
+
+    for entry in entries {
+        if entry.is_dir() {
+            files.extend(metta_files(&entry)?);
+        } else if entry
+            .extension()
+            .is_some_and(|extension| extension == "metta")
+        {
+            files.push(entry);
+        }
+    }
+
+    Ok(files)
+}
+
+/// The content of `file`, or stdin for `-`
+fn read(file: &str) -> Result<Vec<u8>, String> {
+    let mut content = vec![];
+
+    if file == "-" {
+        io::stdin()
+            .read_to_end(&mut content)
+            .map_err(|e| format!("stdin: {e}"))?;
+    } else {
+        content = fs::read(file).map_err(|e| format!("{file}: {e}"))?;
+    }
+
+    Ok(content)
+}
+
+/// Writes `data` to `output`, or to stdout without one
+fn write(output: Option<String>, data: &str) -> Result<(), String> {
+    match output {
+        Some(file) => fs::write(&file, data).map_err(|e| format!("{file}: {e}")),
+        None => {
+            println!("{data}");
+            Ok(())
+        }
+    }
+}
+
+/// `id  parent  namespace  permissions  description`, separated by tabs
+fn describe(token: &Token) -> String {
+    let permissions: Vec<&str> = [
+        (token.permission_read, "read"),
+        (token.permission_write, "write"),
+        (token.permission_share_read, "share-read"),
+        (token.permission_share_write, "share-write"),
+        (token.permission_share_share, "share-share"),
+    ]
+    .into_iter()
+    .filter_map(|(granted, name)| granted.then_some(name))
+    .collect();
+
+    format!(
+        "{}\t{}\t{}\t{}\t{}",
+        token.id,
+        token
+            .parent
+            .map_or_else(|| "-".to_string(), |parent| parent.to_string()),
+        token.namespace,
+        permissions.join(","),
+        token.description,
+    )
+}
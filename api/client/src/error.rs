@@ -0,0 +1,86 @@
+use api::errors::ErrorBody;
+use rocket::serde::json::from_slice;
+use std::fmt;
+
+use crate::transport::HttpResponse;
+
+/// The ways a call to the API can fail
+#[derive(Debug)]
+pub enum Error {
+    /// the request is invalid
+    BadRequest(ErrorBody),
+    /// the token is missing or invalid, or lacks a permission
+    Unauthorized(ErrorBody),
+    NotFound(ErrorBody),
+    /// the request exceeds the upload limit or a quota
+    PayloadTooLarge(ErrorBody),
+    /// the token made too many requests, retry after the given seconds
+    RateLimited {
+        retry_after: Option<u64>,
+        error: ErrorBody,
+    },
+    /// MORK is unavailable
+    Unavailable(ErrorBody),
+    /// any other error response
+    Api {
+        status: u16,
+        error: ErrorBody,
+    },
+    /// the request could not be sent, or the response could not be read
+    Transport(String),
+    /// the response is not the expected one
+    Decode(String),
+}
+
+impl Error {
+    /// The error of an error response
+    pub fn from_response(response: HttpResponse) -> Self {
+        let error = from_slice::<ErrorBody>(&response.body).unwrap_or_else(|_| ErrorBody {
+            code: format!("http_{}", response.status),
+            message: String::from_utf8_lossy(&response.body).into_owned(),
+            details: None,
+        });
+
+        match response.status {
+            400 | 422 => Error::BadRequest(error),
+            401 | 403 => Error::Unauthorized(error),
+            404 => Error::NotFound(error),
+            413 => Error::PayloadTooLarge(error),
+            429 => Error::RateLimited {
+                retry_after: response.retry_after,
+                error,
+            },
+            503 => Error::Unavailable(error),
+            status => Error::Api { status, error },
+        }
+    }
+
+    /// The body of the error response, if the API answered
+    pub fn body(&self) -> Option<&ErrorBody> {
+        match self {
+            Error::BadRequest(error)
+            | Error::Unauthorized(error)
+            | Error::NotFound(error)
+            | Error::PayloadTooLarge(error)
+            | Error::RateLimited { error, .. }
+            | Error::Unavailable(error)
+            | Error::Api { error, .. } => Some(error),
+            Error::Transport(_) | Error::Decode(_) => None,
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Transport(message) => write!(f, "failed to reach the API: {message}"),
+            Error::Decode(message) => write!(f, "unexpected response: {message}"),
+            error => {
+                let body = error.body().expect("API errors have a body");
+                write!(f, "{}: {}", body.code, body.message)
+            }
+        }
+    }
+}
+
+impl std::error::Error for Error {}
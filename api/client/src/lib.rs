@@ -0,0 +1,324 @@
+mod error;
+pub mod profile;
+pub mod transport;
+
+pub use api::errors::ErrorBody;
+pub use api::model::{Job, Token};
+pub use api::mork_api::ExportFormat;
+pub use api::routes::spaces::{ClearPreview, ExploreInput, Mm2Input, Mm2InputMulti};
+pub use api::routes::translations::CSVParseDirection;
+pub use error::Error;
+
+use rocket::http::{ContentType, Method};
+use rocket::serde::de::DeserializeOwned;
+use rocket::serde::json::{from_slice, to_string};
+use rocket::serde::Serialize;
+use urlencoding::encode;
+
+use transport::{HttpRequest, HttpResponse, HttpTransport, Transport};
+
+/// The body of a request, with its content type
+type Body = Option<(ContentType, Vec<u8>)>;
+
+/// A typed client of the MeTTa-KG API, sending requests to its `/v1` routes.
+/// Namespaces are written as in the API, e.g. `/space/subspace/`.
+pub struct MettaKgClient<T = HttpTransport> {
+    transport: T,
+    token: Option<String>,
+}
+
+impl MettaKgClient {
+    /// A client of the API at `base_url`, e.g. `http://localhost:8000`
+    pub fn new(base_url: impl Into<String>) -> Self {
+        MettaKgClient::with_transport(HttpTransport::new(base_url))
+    }
+}
+
+impl<T: Transport> MettaKgClient<T> {
+    pub fn with_transport(transport: T) -> Self {
+        MettaKgClient {
+            transport,
+            token: None,
+        }
+    }
+
+    /// Sends `token` with every request
+    pub fn with_token(mut self, token: impl Into<String>) -> Self {
+        self.token = Some(token.into());
+        self
+    }
+
+    async fn send(&self, method: Method, path: String, body: Body) -> Result<HttpResponse, Error> {
+        let response = self
+            .transport
+            .send(HttpRequest {
+                method,
+                path,
+                token: self.token.clone(),
+                body,
+            })
+            .await?;
+
+        if (200..300).contains(&response.status) {
+            Ok(response)
+        } else {
+            Err(Error::from_response(response))
+        }
+    }
+
+    async fn call<R: DeserializeOwned>(
+        &self,
+        method: Method,
+        path: String,
+        body: Body,
+    ) -> Result<R, Error> {
+        let response = self.send(method, path, body).await?;
+        from_slice(&response.body).map_err(|e| Error::Decode(e.to_string()))
+    }
+
+    pub async fn tokens(&self) -> Result<Vec<Token>, Error> {
+        self.call(Method::Get, "/v1/tokens".to_string(), None).await
+    }
+
+    /// The token sent with the requests
+    pub async fn current_token(&self) -> Result<Token, Error> {
+        self.call(Method::Get, "/v1/token".to_string(), None).await
+    }
+
+    /// Derives a token from the current one. The id, code, parent and creation
+    /// timestamp of `token` are ignored.
+    pub async fn create_token(&self, token: &Token) -> Result<Token, Error> {
+        self.call(Method::Post, "/v1/tokens".to_string(), json(token)?)
+            .await
+    }
+
+    /// Gives a token a new code
+    pub async fn refresh_token(&self, id: i32) -> Result<Token, Error> {
+        self.call(Method::Post, format!("/v1/tokens/{id}"), None)
+            .await
+    }
+
+    pub async fn delete_token(&self, id: i32) -> Result<(), Error> {
+        self.send(Method::Delete, format!("/v1/tokens/{id}"), None)
+            .await
+            .map(|_| ())
+    }
+
+    /// Deletes tokens derived from the current one, returning how many were
+    /// deleted
+    pub async fn delete_tokens(&self, ids: &[i32]) -> Result<i32, Error> {
+        self.call(Method::Delete, "/v1/tokens".to_string(), json(&ids)?)
+            .await
+    }
+
+    /// The atoms of the space
+    pub async fn read(&self, namespace: &str) -> Result<String, Error> {
+        self.call(Method::Get, format!("/v1/spaces/{}", path(namespace)), None)
+            .await
+    }
+
+    /// Adds MeTTa atoms to the space, returning the response of MORK
+    pub async fn upload(&self, namespace: &str, atoms: &str) -> Result<String, Error> {
+        self.call(Method::Post, upload_path(namespace, false), text(atoms))
+            .await
+    }
+
+    pub async fn upload_in_background(&self, namespace: &str, atoms: &str) -> Result<Job, Error> {
+        self.call(Method::Post, upload_path(namespace, true), text(atoms))
+            .await
+    }
+
+    /// Imports the resource at `uri` into the space
+    pub async fn import(&self, namespace: &str, uri: &str) -> Result<(), Error> {
+        self.call::<bool>(Method::Post, import_path(namespace, uri, false), None)
+            .await
+            .map(|_| ())
+    }
+
+    pub async fn import_in_background(&self, namespace: &str, uri: &str) -> Result<Job, Error> {
+        self.call(Method::Post, import_path(namespace, uri, true), None)
+            .await
+    }
+
+    pub async fn transform(&self, namespace: &str, input: &Mm2InputMulti) -> Result<(), Error> {
+        self.call::<bool>(Method::Post, transform_path(namespace, false), json(input)?)
+            .await
+            .map(|_| ())
+    }
+
+    pub async fn transform_in_background(
+        &self,
+        namespace: &str,
+        input: &Mm2InputMulti,
+    ) -> Result<Job, Error> {
+        self.call(Method::Post, transform_path(namespace, true), json(input)?)
+            .await
+    }
+
+    /// The atoms matching the pattern of `input`, and the token to continue
+    /// exploring
+    pub async fn explore(&self, namespace: &str, input: &ExploreInput) -> Result<String, Error> {
+        self.call(
+            Method::Post,
+            format!("/v1/spaces/explore/{}", path(namespace)),
+            json(input)?,
+        )
+        .await
+    }
+
+    /// The atoms matching the pattern of `input`, rewritten with its template
+    pub async fn export(&self, namespace: &str, input: &Mm2Input) -> Result<String, Error> {
+        self.export_as(namespace, input, ExportFormat::Metta).await
+    }
+
+    /// Exports as `export` does, in the given format
+    pub async fn export_as(
+        &self,
+        namespace: &str,
+        input: &Mm2Input,
+        format: ExportFormat,
+    ) -> Result<String, Error> {
+        let format = match format {
+            ExportFormat::Metta => "Metta",
+            ExportFormat::Json => "Json",
+            ExportFormat::Csv => "Csv",
+            ExportFormat::Raw => "Raw",
+        };
+
+        self.call(
+            Method::Post,
+            format!("/v1/spaces/export/{}?format={format}", path(namespace)),
+            json(input)?,
+        )
+        .await
+    }
+
+    /// Removes the atoms matching `expr` from the space
+    pub async fn clear(&self, namespace: &str, expr: &str) -> Result<(), Error> {
+        self.call::<bool>(Method::Post, clear_path(namespace, expr, ""), None)
+            .await
+            .map(|_| ())
+    }
+
+    pub async fn clear_in_background(&self, namespace: &str, expr: &str) -> Result<Job, Error> {
+        self.call(
+            Method::Post,
+            clear_path(namespace, expr, "&background=true"),
+            None,
+        )
+        .await
+    }
+
+    /// The atoms `clear` would remove, without removing them
+    pub async fn clear_preview(&self, namespace: &str, expr: &str) -> Result<ClearPreview, Error> {
+        self.call(
+            Method::Post,
+            clear_path(namespace, expr, "&dry_run=true"),
+            None,
+        )
+        .await
+    }
+
+    /// Translates a CSV file to MeTTa
+    pub async fn translate_csv(
+        &self,
+        file: impl Into<Vec<u8>>,
+        direction: CSVParseDirection,
+        delimiter: &str,
+    ) -> Result<String, Error> {
+        let direction = match direction {
+            CSVParseDirection::Row => "Row",
+            CSVParseDirection::Column => "Column",
+            CSVParseDirection::CellUnlabeled => "CellUnlabeled",
+            CSVParseDirection::CellLabeled => "CellLabeled",
+        };
+
+        self.call(
+            Method::Post,
+            format!(
+                "/v1/translations/csv?direction={direction}&delimiter={}",
+                encode(delimiter)
+            ),
+            Some((ContentType::CSV, file.into())),
+        )
+        .await
+    }
+
+    /// Translates an N-Triples file to MeTTa
+    pub async fn translate_nt(&self, file: impl Into<Vec<u8>>) -> Result<String, Error> {
+        self.translate("nt", ContentType::new("application", "n-triples"), file)
+            .await
+    }
+
+    /// Translates a JSON-LD file to MeTTa
+    pub async fn translate_jsonld(&self, file: impl Into<Vec<u8>>) -> Result<String, Error> {
+        self.translate("jsonld", ContentType::new("application", "ld+json"), file)
+            .await
+    }
+
+    /// Translates a Notation3 file to MeTTa
+    pub async fn translate_n3(&self, file: impl Into<Vec<u8>>) -> Result<String, Error> {
+        self.translate("n3", ContentType::new("text", "n3"), file)
+            .await
+    }
+
+    async fn translate(
+        &self,
+        format: &str,
+        content_type: ContentType,
+        file: impl Into<Vec<u8>>,
+    ) -> Result<String, Error> {
+        // these translations take no parameters, but the API expects a dummy one
+        self.call(
+            Method::Post,
+            format!("/v1/translations/{format}?dummy="),
+            Some((content_type, file.into())),
+        )
+        .await
+    }
+}
+
+/// `/space/subspace/` as a path, `space/subspace`
+fn path(namespace: &str) -> &str {
+    namespace.trim_matches('/')
+}
+
+fn upload_path(namespace: &str, background: bool) -> String {
+    format!(
+        "/v1/spaces/upload/{}?background={background}",
+        path(namespace)
+    )
+}
+
+fn import_path(namespace: &str, uri: &str, background: bool) -> String {
+    format!(
+        "/v1/spaces/import/{}?uri={}&background={background}",
+        path(namespace),
+        encode(uri)
+    )
+}
+
+fn transform_path(namespace: &str, background: bool) -> String {
+    format!(
+        "/v1/spaces/transform/{}?background={background}",
+        path(namespace)
+    )
+}
+
+fn clear_path(namespace: &str, expr: &str, options: &str) -> String {
+    format!(
+        "/v1/spaces/clear/{}?expr={}{options}",
+        path(namespace),
+        encode(expr)
+    )
+}
+
+fn json(body: &impl Serialize) -> Result<Body, Error> {
+    to_string(body)
+        .map(|json| Some((ContentType::JSON, json.into_bytes())))
+        .map_err(|e| Error::Decode(e.to_string()))
+}
+
+fn text(body: &str) -> Body {
+    Some((ContentType::Plain, body.as_bytes().to_vec()))
+}
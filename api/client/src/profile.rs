@@ -0,0 +1,74 @@
+use rocket::figment::providers::{Env, Format, Serialized, Toml};
+use rocket::figment::{self, Figment};
+use rocket::serde::{Deserialize, Serialize};
+use std::env;
+use std::path::PathBuf;
+
+use crate::MettaKgClient;
+
+/// Where the API is and the token to send it, as read by the `mettakg` CLI.
+///
+/// Profiles are tables of a TOML file, `~/.config/mettakg/config.toml` unless
+/// `METTA_KG_CONFIG` names another one, e.g.
+///
+/// ```toml
+/// [default]
+/// url = "http://localhost:8000"
+///
+/// [ci]
+/// url = "https://metta-kg.example.com"
+/// token = "..."
+/// ```
+///
+/// `METTA_KG_URL` and `METTA_KG_TOKEN` take precedence over every profile.
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq)]
+#[serde(crate = "rocket::serde", default)]
+pub struct Profile {
+    pub url: String,
+    pub token: Option<String>,
+}
+
+impl Default for Profile {
+    fn default() -> Self {
+        Profile {
+            url: "http://localhost:8000".to_string(),
+            token: None,
+        }
+    }
+}
+
+impl Profile {
+    /// The profile named `name`, or the one named by `METTA_KG_PROFILE`, or
+    /// `default`
+    pub fn load(name: Option<&str>) -> Result<Profile, String> {
+        let name = match name {
+            Some(name) => figment::Profile::new(name),
+            None => figment::Profile::from_env_or("METTA_KG_PROFILE", "default"),
+        };
+
+        Figment::from(Serialized::defaults(Profile::default()))
+            .merge(Toml::file(config_file()).nested())
+            .merge(Env::prefixed("METTA_KG_").only(&["url", "token"]).global())
+            .select(name)
+            .extract()
+            .map_err(|e| e.to_string())
+    }
+
+    /// A client of the API, with the token of the profile
+    pub fn client(&self) -> MettaKgClient {
+        let client = MettaKgClient::new(&self.url);
+
+        match &self.token {
+            Some(token) => client.with_token(token),
+            None => client,
+        }
+    }
+}
+
+fn config_file() -> PathBuf {
+    match env::var_os("METTA_KG_CONFIG") {
+        Some(file) => PathBuf::from(file),
+        None => PathBuf::from(env::var_os("HOME").unwrap_or_default())
+            .join(".config/mettakg/config.toml"),
+    }
+}
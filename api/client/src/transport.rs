@@ -0,0 +1,132 @@
+use rocket::http::{ContentType, Header, Method};
+use rocket::local::asynchronous::Client as LocalClient;
+use rocket::{async_trait, Build, Rocket};
+
+use crate::Error;
+
+/// A request to the API
+pub struct HttpRequest {
+    pub method: Method,
+    /// the path with its query, e.g. `/v1/spaces/test/`
+    pub path: String,
+    pub token: Option<String>,
+    /// the content type and the body
+    pub body: Option<(ContentType, Vec<u8>)>,
+}
+
+/// A response of the API
+pub struct HttpResponse {
+    pub status: u16,
+    /// the `Retry-After` header of throttled requests, in seconds
+    pub retry_after: Option<u64>,
+    pub body: Vec<u8>,
+}
+
+/// Sends requests to the API
+#[async_trait]
+pub trait Transport: Send + Sync {
+    async fn send(&self, request: HttpRequest) -> Result<HttpResponse, Error>;
+}
+
+/// Sends requests over HTTP to the API at a base URL
+pub struct HttpTransport {
+    base_url: String,
+    client: reqwest::Client,
+}
+
+impl HttpTransport {
+    pub fn new(base_url: impl Into<String>) -> Self {
+        HttpTransport {
+            base_url: base_url.into().trim_end_matches('/').to_string(),
+            client: reqwest::Client::new(),
+        }
+    }
+}
+
+#[async_trait]
+impl Transport for HttpTransport {
+    async fn send(&self, request: HttpRequest) -> Result<HttpResponse, Error> {
+        let method = reqwest::Method::from_bytes(request.method.as_str().as_bytes())
+            .map_err(|e| Error::Transport(e.to_string()))?;
+
+        let mut http_request = self
+            .client
+            .request(method, format!("{}{}", self.base_url, request.path));
+
+        if let Some(token) = request.token {
+            http_request = http_request.header("authorization", token);
+        }
+        if let Some((content_type, body)) = request.body {
+            http_request = http_request
+                .header("content-type", content_type.to_string())
+                .body(body);
+        }
+
+        let response = http_request
+            .send()
+            .await
+            .map_err(|e| Error::Transport(e.to_string()))?;
+
+        let status = response.status().as_u16();
+        let retry_after = response
+            .headers()
+            .get("retry-after")
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.parse().ok());
+        let body = response
+            .bytes()
+            .await
+            .map_err(|e| Error::Transport(e.to_string()))?;
+
+        Ok(HttpResponse {
+            status,
+            retry_after,
+            body: body.to_vec(),
+        })
+    }
+}
+
+/// Dispatches requests to a Rocket instance in the same process, without a
+/// network, e.g. in tests
+pub struct LocalTransport {
+    client: LocalClient,
+}
+
+impl LocalTransport {
+    pub async fn new(rocket: Rocket<Build>) -> Result<Self, Error> {
+        LocalClient::untracked(rocket)
+            .await
+            .map(|client| LocalTransport { client })
+            .map_err(|e| Error::Transport(e.to_string()))
+    }
+}
+
+#[async_trait]
+impl Transport for LocalTransport {
+    async fn send(&self, request: HttpRequest) -> Result<HttpResponse, Error> {
+        let mut local_request = self.client.req(request.method, request.path);
+
+        if let Some(token) = request.token {
+            local_request.add_header(Header::new("authorization", token));
+        }
+        if let Some((content_type, body)) = request.body {
+            local_request.add_header(content_type);
+            local_request.set_body(body);
+        }
+
+        let response = local_request.dispatch().await;
+
+        let status = response.status().code;
+        let retry_after = response
+            .headers()
+            .get_one("Retry-After")
+            .and_then(|value| value.parse().ok());
+        let body = response.into_bytes().await.unwrap_or_default();
+
+        Ok(HttpResponse {
+            status,
+            retry_after,
+            body,
+        })
+    }
+}
@@ -0,0 +1,5 @@
+mod common;
+mod test_cli;
+mod test_spaces;
+mod test_tokens;
+mod test_translations;
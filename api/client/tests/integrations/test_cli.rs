@@ -0,0 +1,163 @@
+use httpmock::prelude::*;
+use httpmock::Regex;
+use serial_test::serial;
+use std::env;
+use std::fs;
+use std::net::TcpListener;
+use std::process::{Command, Output};
+
+use crate::integrations::common;
+
+/// Runs `mettakg` with `args`, against the API at `url` with `token`
+async fn mettakg(url: &str, token: Option<&str>, args: &[&str]) -> Output {
+    let mut command = Command::new(env!("CARGO_BIN_EXE_mettakg"));
+    command
+        .args(args)
+        .env("METTA_KG_URL", url)
+        .env("METTA_KG_CONFIG", env::temp_dir().join("mettakg-none.toml"))
+        .env_remove("METTA_KG_TOKEN")
+        .env_remove("METTA_KG_PROFILE");
+    if let Some(token) = token {
+        command.env("METTA_KG_TOKEN", token);
+    }
+
+    tokio::task::spawn_blocking(move || command.output().expect("mettakg runs"))
+        .await
+        .unwrap()
+}
+
+fn stdout(output: &Output) -> String {
+    String::from_utf8_lossy(&output.stdout).into_owned()
+}
+
+/// Launches the API on a free port, returning its URL
+async fn launch() -> String {
+    let port = TcpListener::bind("127.0.0.1:0")
+        .unwrap()
+        .local_addr()
+        .unwrap()
+        .port();
+    env::set_var("ROCKET_PORT", port.to_string());
+
+    tokio::spawn(api::rocket().launch());
+
+    let url = format!("http://127.0.0.1:{port}");
+    for _ in 0..50 {
+        if reqwest::get(format!("{url}/healthz")).await.is_ok() {
+            return url;
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+    }
+    panic!("the API did not start");
+}
+
+#[tokio::test]
+#[serial]
+async fn test_cli() {
+    if !common::is_database_running() {
+        eprintln!("Warning: Database not running, skipping test");
+        return;
+    }
+    let server = MockServer::start();
+    common::setup(&server.base_url());
+
+    let upload = server.mock(|when, then| {
+        when.method(POST)
+            .path_matches(Regex::new(r"/upload/.*").unwrap());
+        then.status(200).body("Upload successful");
+    });
+    let export = server.mock(|when, then| {
+        when.method(GET)
+            .path_matches(Regex::new(r"/export/.*").unwrap())
+            .query_param("format", "json");
+        then.status(200).body(r#"[["test", "data"]]"#);
+    });
+
+    let url = launch().await;
+    let root = api::admin::root_token().expect("root token").code;
+
+    // the .metta files of a directory, and of its subdirectories
+    let dir = env::temp_dir().join("mettakg-upload");
+    fs::create_dir_all(dir.join("nested")).unwrap();
+    fs::write(dir.join("a.metta"), "(a 1)").unwrap();
+    fs::write(dir.join("nested/b.metta"), "(b 2)").unwrap();
+    fs::write(dir.join("notes.txt"), "not metta").unwrap();
+
+    let output = mettakg(
+        &url,
+        Some(&root),
+        &["upload", "/cli/", dir.to_str().unwrap()],
+    )
+    .await;
+    assert!(output.status.success(), "{output:?}");
+    assert_eq!(stdout(&output).lines().count(), 2);
+    upload.assert_hits(2);
+
+    let exported = dir.join("export.json");
+    let output = mettakg(
+        &url,
+        Some(&root),
+        &[
+            "export",
+            "/cli/",
+            "--format",
+            "json",
+            "--output",
+            exported.to_str().unwrap(),
+        ],
+    )
+    .await;
+    assert!(output.status.success(), "{output:?}");
+    assert_eq!(
+        fs::read_to_string(&exported).unwrap(),
+        r#"[["test", "data"]]"#
+    );
+    export.assert_hits(1);
+
+    let output = mettakg(
+        &url,
+        Some(&root),
+        &["tokens", "create", "--namespace", "/cli/", "--read"],
+    )
+    .await;
+    assert!(output.status.success(), "{output:?}");
+    let created = stdout(&output);
+    assert!(created.contains("/cli/\tread"), "{created}");
+
+    // the token of a profile
+    let code = created.lines().last().unwrap();
+    let config = dir.join("config.toml");
+    fs::write(
+        &config,
+        format!("[ci]\nurl = \"{url}\"\ntoken = \"{code}\"\n"),
+    )
+    .unwrap();
+
+    let output = tokio::task::spawn_blocking(move || {
+        Command::new(env!("CARGO_BIN_EXE_mettakg"))
+            .args(["--profile", "ci", "tokens", "current"])
+            .env("METTA_KG_CONFIG", config)
+            .env_remove("METTA_KG_URL")
+            .env_remove("METTA_KG_TOKEN")
+            .output()
+            .expect("mettakg runs")
+    })
+    .await
+    .unwrap();
+    assert!(output.status.success(), "{output:?}");
+    assert!(stdout(&output).contains("/cli/\tread"));
+
+    // without a token, the error of the API is reported
+    let output = mettakg(&url, None, &["tokens", "list"]).await;
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("missing_token"));
+
+    // invalid arguments are reported with the usage
+    let output = mettakg(&url, Some(&root), &["export", "/cli/", "--format", "xml"]).await;
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("Usage:"));
+
+    fs::remove_dir_all(&dir).unwrap();
+    env::remove_var("ROCKET_PORT");
+    common::teardown_database();
+}
@@ -0,0 +1,150 @@
+use httpmock::prelude::*;
+use httpmock::Regex;
+use metta_kg_client::{Error, ExploreInput, Mm2Input, Mm2InputMulti};
+use serial_test::serial;
+
+use crate::integrations::common;
+
+#[tokio::test]
+#[serial]
+async fn test_spaces() {
+    if !common::is_database_running() {
+        eprintln!("Warning: Database not running, skipping test");
+        return;
+    }
+    let server = MockServer::start();
+    common::setup(&server.base_url());
+
+    let upload = server.mock(|when, then| {
+        when.method(POST)
+            .path_matches(Regex::new(r"/upload/.*").unwrap());
+        then.status(200).body("Upload successful");
+    });
+    let import = server.mock(|when, then| {
+        when.method(GET)
+            .path_matches(Regex::new(r"/import/.*").unwrap());
+        then.status(200).body("Import successful");
+    });
+    let transform = server.mock(|when, then| {
+        when.method(POST).path("/transform");
+        then.status(200).body("Transform successful");
+    });
+    let export = server.mock(|when, then| {
+        when.method(GET)
+            .path_matches(Regex::new(r"/export/.*").unwrap());
+        then.status(200).body("(test data)");
+    });
+    let explore = server.mock(|when, then| {
+        when.method(GET)
+            .path_matches(Regex::new(r"/explore/.*").unwrap());
+        then.status(200).body("(explore result)");
+    });
+    let clear = server.mock(|when, then| {
+        when.method(GET)
+            .path_matches(Regex::new(r"/clear/.*").unwrap());
+        then.status(200).body("Clear successful");
+    });
+
+    let client = common::root_client().await;
+
+    assert_eq!(
+        client.upload("/test/space/", "(test atom)").await.unwrap(),
+        "Upload successful"
+    );
+    upload.assert_hits(1);
+
+    client
+        .import("/test/space/", "https://example.com/data.metta?x=1&y=2")
+        .await
+        .expect("imported");
+    import.assert_hits(1);
+
+    client
+        .transform(
+            "/test/space/",
+            &Mm2InputMulti {
+                patterns: vec!["(test $x)".to_string()],
+                templates: vec!["(done $x)".to_string()],
+            },
+        )
+        .await
+        .expect("transformed");
+    transform.assert_hits(1);
+
+    assert_eq!(client.read("/test/space/").await.unwrap(), "(test data)");
+    assert_eq!(
+        client
+            .export(
+                "/test/space/",
+                &Mm2Input {
+                    pattern: "$x".to_string(),
+                    template: "$x".to_string(),
+                },
+            )
+            .await
+            .unwrap(),
+        "(test data)"
+    );
+    export.assert_hits(2);
+
+    assert_eq!(
+        client
+            .explore(
+                "/test/space/",
+                &ExploreInput {
+                    pattern: "$x".to_string(),
+                    token: String::new(),
+                },
+            )
+            .await
+            .unwrap(),
+        "(explore result)"
+    );
+    explore.assert_hits(1);
+
+    let preview = client
+        .clear_preview("/test/space/", "(test $x)")
+        .await
+        .expect("preview");
+    assert_eq!(preview.total, 1);
+    clear.assert_hits(0);
+
+    client
+        .clear("/test/space/", "(test $x)")
+        .await
+        .expect("cleared");
+    clear.assert_hits(1);
+
+    common::teardown_database();
+}
+
+#[tokio::test]
+#[serial]
+async fn test_space_errors() {
+    if !common::is_database_running() {
+        eprintln!("Warning: Database not running, skipping test");
+        return;
+    }
+    common::setup("http://127.0.0.1:1");
+
+    let client = common::root_client().await;
+
+    // MORK can not be reached
+    match client.read("/test/").await {
+        Err(Error::Unavailable(error)) => assert_eq!(error.code, "service_unavailable"),
+        other => panic!("unexpected {:?}", other),
+    }
+
+    match client.import("/test/", "not a uri").await {
+        Err(Error::BadRequest(_)) => {}
+        other => panic!("unexpected {:?}", other),
+    }
+
+    // the root token can not write to the reserved space of snapshots
+    match client.upload("/_snapshots/", "(test atom)").await {
+        Err(Error::Unauthorized(_)) => {}
+        other => panic!("unexpected {:?}", other),
+    }
+
+    common::teardown_database();
+}
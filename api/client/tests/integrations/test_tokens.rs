@@ -0,0 +1,88 @@
+use chrono::Utc;
+use metta_kg_client::{Error, Token};
+use serial_test::serial;
+
+use crate::integrations::common;
+
+fn new_token(namespace: &str) -> Token {
+    Token {
+        id: 0,
+        code: String::new(),
+        description: "From the client".to_string(),
+        namespace: namespace.to_string(),
+        creation_timestamp: Utc::now().naive_utc(),
+        permission_read: true,
+        permission_write: false,
+        permission_share_share: false,
+        permission_share_read: false,
+        permission_share_write: false,
+        parent: None,
+        rate_limit_read: None,
+        rate_limit_write: None,
+        rate_limit_translate: None,
+        concurrency_limit_read: None,
+        concurrency_limit_write: None,
+        concurrency_limit_translate: None,
+    }
+}
+
+#[tokio::test]
+#[serial]
+async fn test_tokens() {
+    if !common::is_database_running() {
+        eprintln!("Warning: Database not running, skipping test");
+        return;
+    }
+    common::setup("http://127.0.0.1:1");
+
+    let client = common::root_client().await;
+
+    let root = client.current_token().await.expect("current token");
+    assert_eq!(root.namespace, "/");
+
+    let first = client
+        .create_token(&new_token("/client/"))
+        .await
+        .expect("created token");
+    assert_eq!(first.parent, Some(root.id));
+
+    let second = client
+        .create_token(&new_token("/client/other/"))
+        .await
+        .expect("created token");
+    assert_eq!(client.tokens().await.unwrap().len(), 3);
+
+    let refreshed = client.refresh_token(first.id).await.expect("refreshed");
+    assert_ne!(refreshed.code, first.code);
+
+    client.delete_token(first.id).await.expect("deleted token");
+    assert_eq!(client.delete_tokens(&[second.id]).await.unwrap(), 1);
+    assert_eq!(client.tokens().await.unwrap().len(), 1);
+
+    common::teardown_database();
+}
+
+#[tokio::test]
+#[serial]
+async fn test_token_errors() {
+    if !common::is_database_running() {
+        eprintln!("Warning: Database not running, skipping test");
+        return;
+    }
+    common::setup("http://127.0.0.1:1");
+
+    let client = common::root_client().await;
+
+    match client.create_token(&new_token("invalid")).await {
+        Err(Error::BadRequest(error)) => assert_eq!(error.code, "bad_request"),
+        other => panic!("unexpected {:?}", other.err()),
+    }
+
+    let client = client.with_token("not-a-token");
+    match client.tokens().await {
+        Err(Error::Unauthorized(error)) => assert_eq!(error.code, "invalid_token"),
+        other => panic!("unexpected {:?}", other.err()),
+    }
+
+    common::teardown_database();
+}
@@ -0,0 +1,38 @@
+use metta_kg_client::{CSVParseDirection, Error};
+use serial_test::serial;
+use std::env;
+
+use crate::integrations::common;
+
+#[tokio::test]
+#[serial]
+async fn test_translation_errors() {
+    if !common::is_database_running() {
+        eprintln!("Warning: Database not running, skipping test");
+        return;
+    }
+    common::setup("http://127.0.0.1:1");
+    // the translations run, and fail, without an interpreter
+    env::set_var("METTA_KG_PYTHON", "/nonexistent/python");
+    env::set_var("ROCKET_TEMP_DIR", env::temp_dir());
+
+    let client = common::root_client().await;
+
+    // the parameters are accepted, so the translation itself fails
+    match client
+        .translate_csv("a;b\n1;2\n", CSVParseDirection::Row, ";")
+        .await
+    {
+        Err(Error::Api { status: 500, .. }) => {}
+        other => panic!("unexpected {:?}", other),
+    }
+
+    match client.translate_nt("<a> <b> <c> .\n").await {
+        Err(Error::Api { status: 500, .. }) => {}
+        other => panic!("unexpected {:?}", other),
+    }
+
+    env::remove_var("METTA_KG_PYTHON");
+    env::remove_var("ROCKET_TEMP_DIR");
+    common::teardown_database();
+}
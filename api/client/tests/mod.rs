@@ -0,0 +1,2 @@
+#[cfg(feature = "integration-tests")]
+mod integrations;
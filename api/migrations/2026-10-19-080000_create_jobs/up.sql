@@ -0,0 +1,12 @@
+CREATE TABLE jobs (
+    id SERIAL PRIMARY KEY NOT NULL,
+    token_id INTEGER NOT NULL REFERENCES tokens(id) ON DELETE CASCADE,
+    kind VARCHAR NOT NULL,
+    namespace VARCHAR NOT NULL,
+    status VARCHAR NOT NULL,
+    creation_timestamp TIMESTAMP NOT NULL,
+    start_timestamp TIMESTAMP,
+    finish_timestamp TIMESTAMP,
+    response TEXT,
+    error TEXT
+);
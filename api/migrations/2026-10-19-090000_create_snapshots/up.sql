@@ -0,0 +1,8 @@
+CREATE TABLE snapshots (
+    id SERIAL PRIMARY KEY NOT NULL,
+    name VARCHAR NOT NULL,
+    namespace VARCHAR NOT NULL,
+    token_id INTEGER REFERENCES tokens(id) ON DELETE SET NULL,
+    creation_timestamp TIMESTAMP NOT NULL,
+    UNIQUE (namespace, name)
+);
@@ -0,0 +1,11 @@
+CREATE TABLE saved_queries (
+    id SERIAL PRIMARY KEY NOT NULL,
+    namespace VARCHAR NOT NULL,
+    name VARCHAR NOT NULL,
+    patterns TEXT[] NOT NULL,
+    templates TEXT[] NOT NULL,
+    description VARCHAR NOT NULL,
+    token_id INTEGER REFERENCES tokens(id) ON DELETE SET NULL,
+    creation_timestamp TIMESTAMP NOT NULL,
+    UNIQUE (namespace, name)
+);
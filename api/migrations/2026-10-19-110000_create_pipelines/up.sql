@@ -0,0 +1,18 @@
+CREATE TABLE pipelines (
+    id SERIAL PRIMARY KEY NOT NULL,
+    name VARCHAR NOT NULL,
+    version INTEGER NOT NULL,
+    description VARCHAR NOT NULL,
+    token_id INTEGER REFERENCES tokens(id) ON DELETE SET NULL,
+    creation_timestamp TIMESTAMP NOT NULL,
+    UNIQUE (name, version)
+);
+
+CREATE TABLE pipeline_steps (
+    id SERIAL PRIMARY KEY NOT NULL,
+    pipeline_id INTEGER NOT NULL REFERENCES pipelines(id) ON DELETE CASCADE,
+    position INTEGER NOT NULL,
+    patterns TEXT[] NOT NULL,
+    templates TEXT[] NOT NULL,
+    UNIQUE (pipeline_id, position)
+);
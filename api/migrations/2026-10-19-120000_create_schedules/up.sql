@@ -0,0 +1,30 @@
+CREATE TABLE schedules (
+    id SERIAL PRIMARY KEY NOT NULL,
+    name VARCHAR NOT NULL,
+    namespace VARCHAR NOT NULL,
+    token_id INTEGER NOT NULL REFERENCES tokens(id) ON DELETE CASCADE,
+    saved_query_id INTEGER REFERENCES saved_queries(id) ON DELETE CASCADE,
+    pipeline_name VARCHAR,
+    pipeline_version INTEGER,
+    params TEXT NOT NULL,
+    cron VARCHAR,
+    watch_namespace VARCHAR,
+    enabled BOOLEAN NOT NULL DEFAULT TRUE,
+    last_run_timestamp TIMESTAMP,
+    last_error TEXT,
+    creation_timestamp TIMESTAMP NOT NULL,
+    -- a schedule runs either a saved query or a pipeline
+    CHECK ((saved_query_id IS NULL) <> (pipeline_name IS NULL)),
+    -- and is triggered either by time or by changes
+    CHECK ((cron IS NULL) <> (watch_namespace IS NULL))
+);
+
+CREATE TABLE schedule_runs (
+    id SERIAL PRIMARY KEY NOT NULL,
+    schedule_id INTEGER NOT NULL REFERENCES schedules(id) ON DELETE CASCADE,
+    trigger VARCHAR NOT NULL,
+    status VARCHAR NOT NULL,
+    start_timestamp TIMESTAMP NOT NULL,
+    finish_timestamp TIMESTAMP,
+    error TEXT
+);
@@ -0,0 +1,21 @@
+CREATE TABLE webhooks (
+    id SERIAL PRIMARY KEY NOT NULL,
+    namespace VARCHAR NOT NULL,
+    url VARCHAR NOT NULL,
+    token_id INTEGER NOT NULL REFERENCES tokens(id) ON DELETE CASCADE,
+    creation_timestamp TIMESTAMP NOT NULL
+);
+
+CREATE TABLE webhook_deliveries (
+    id SERIAL PRIMARY KEY NOT NULL,
+    webhook_id INTEGER NOT NULL REFERENCES webhooks(id) ON DELETE CASCADE,
+    operation VARCHAR NOT NULL,
+    namespace VARCHAR NOT NULL,
+    payload TEXT NOT NULL,
+    status VARCHAR NOT NULL,
+    attempts INTEGER NOT NULL,
+    response_status INTEGER,
+    error TEXT,
+    creation_timestamp TIMESTAMP NOT NULL,
+    last_attempt_timestamp TIMESTAMP
+);
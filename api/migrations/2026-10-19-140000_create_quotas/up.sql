@@ -0,0 +1,9 @@
+CREATE TABLE quotas (
+    id SERIAL PRIMARY KEY NOT NULL,
+    namespace VARCHAR NOT NULL UNIQUE,
+    max_atoms BIGINT,
+    max_upload_bytes BIGINT,
+    max_import_bytes BIGINT,
+    token_id INTEGER REFERENCES tokens(id) ON DELETE SET NULL,
+    update_timestamp TIMESTAMP NOT NULL
+);
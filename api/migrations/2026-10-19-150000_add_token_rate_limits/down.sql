@@ -0,0 +1,3 @@
+ALTER TABLE tokens
+    DROP COLUMN rate_limit,
+    DROP COLUMN concurrency_limit;
@@ -0,0 +1,3 @@
+ALTER TABLE tokens
+    ADD COLUMN rate_limit INTEGER,
+    ADD COLUMN concurrency_limit INTEGER;
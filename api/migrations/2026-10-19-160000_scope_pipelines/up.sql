@@ -0,0 +1,18 @@
+-- the namespace a pipeline belongs to, the one of the token that created it
+ALTER TABLE pipelines
+    ADD COLUMN namespace VARCHAR NOT NULL DEFAULT '/';
+
+UPDATE pipelines
+    SET namespace = tokens.namespace
+    FROM tokens
+    WHERE tokens.id = pipelines.token_id;
+
+ALTER TABLE pipelines
+    ALTER COLUMN namespace DROP DEFAULT;
+
+-- scheduled pipelines run the version they were created with
+UPDATE schedules
+    SET pipeline_version = (
+        SELECT max(version) FROM pipelines WHERE name = schedules.pipeline_name
+    )
+    WHERE pipeline_name IS NOT NULL AND pipeline_version IS NULL;
@@ -0,0 +1,15 @@
+ALTER TABLE tokens
+    ADD COLUMN rate_limit INTEGER,
+    ADD COLUMN concurrency_limit INTEGER;
+
+UPDATE tokens SET
+    rate_limit = rate_limit_write,
+    concurrency_limit = concurrency_limit_write;
+
+ALTER TABLE tokens
+    DROP COLUMN rate_limit_read,
+    DROP COLUMN rate_limit_write,
+    DROP COLUMN rate_limit_translate,
+    DROP COLUMN concurrency_limit_read,
+    DROP COLUMN concurrency_limit_write,
+    DROP COLUMN concurrency_limit_translate;
@@ -0,0 +1,19 @@
+ALTER TABLE tokens
+    ADD COLUMN rate_limit_read INTEGER,
+    ADD COLUMN rate_limit_write INTEGER,
+    ADD COLUMN rate_limit_translate INTEGER,
+    ADD COLUMN concurrency_limit_read INTEGER,
+    ADD COLUMN concurrency_limit_write INTEGER,
+    ADD COLUMN concurrency_limit_translate INTEGER;
+
+UPDATE tokens SET
+    rate_limit_read = rate_limit,
+    rate_limit_write = rate_limit,
+    rate_limit_translate = rate_limit,
+    concurrency_limit_read = concurrency_limit,
+    concurrency_limit_write = concurrency_limit,
+    concurrency_limit_translate = concurrency_limit;
+
+ALTER TABLE tokens
+    DROP COLUMN rate_limit,
+    DROP COLUMN concurrency_limit;
@@ -0,0 +1,3 @@
+ALTER TABLE pipelines
+    DROP CONSTRAINT pipelines_namespace_name_version_key,
+    ADD CONSTRAINT pipelines_name_version_key UNIQUE (name, version);
@@ -0,0 +1,5 @@
+-- pipeline names are unique within a namespace, so tenants can not see which
+-- names the others use
+ALTER TABLE pipelines
+    DROP CONSTRAINT pipelines_name_version_key,
+    ADD CONSTRAINT pipelines_namespace_name_version_key UNIQUE (namespace, name, version);
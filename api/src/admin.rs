@@ -0,0 +1,156 @@
+use chrono::Utc;
+use diesel::sql_types::Integer;
+use diesel::{Connection, ExpressionMethods, QueryDsl, RunQueryDsl};
+use diesel_migrations::MigrationHarness;
+use std::time::{Duration, Instant};
+use uuid::Uuid;
+
+use crate::backend::Backend;
+use crate::db::establish_connection;
+use crate::model::{Token, TokenInsert};
+use crate::mork_api::{MorkApiClient, ProbeRequest};
+use crate::routes::is_valid_namespace;
+use crate::MIGRATIONS;
+
+/// The id of the root token, created by the seed migration
+pub const ROOT_TOKEN_ID: i32 = 0;
+
+/// The root token, with its code
+pub fn root_token() -> Result<Token, String> {
+    use crate::schema::tokens::dsl::*;
+    let conn = &mut establish_connection();
+
+    tokens
+        .filter(id.eq(ROOT_TOKEN_ID))
+        .first(conn)
+        .map_err(|e| format!("Failed to load the root token: {e}"))
+}
+
+/// Gives the root token a new code
+pub fn rotate_root_token() -> Result<Token, String> {
+    use crate::schema::tokens::dsl::*;
+    let conn = &mut establish_connection();
+
+    diesel::update(tokens.filter(id.eq(ROOT_TOKEN_ID)))
+        .set(code.eq(Uuid::new_v4().to_string()))
+        .get_result(conn)
+        .map_err(|e| format!("Failed to rotate the root token: {e}"))
+}
+
+/// Every token, by id
+pub fn list_tokens() -> Result<Vec<Token>, String> {
+    use crate::schema::tokens::dsl::*;
+    let conn = &mut establish_connection();
+
+    tokens
+        .order(id)
+        .load(conn)
+        .map_err(|e| format!("Failed to load the tokens: {e}"))
+}
+
+/// Creates a token with a new code. Unlike through the API, its rights are not
+/// limited by those of its parent.
+pub fn create_token(new_token: TokenInsert) -> Result<Token, String> {
+    use crate::schema::tokens::dsl::*;
+    let conn = &mut establish_connection();
+
+    if !new_token.namespace.ends_with('/') || !is_valid_namespace(&new_token.namespace) {
+        return Err(format!("Invalid namespace {}", new_token.namespace));
+    }
+
+    let to_insert = TokenInsert {
+        code: Uuid::new_v4().to_string(),
+        creation_timestamp: Utc::now().naive_utc(),
+        ..new_token
+    };
+
+    diesel::insert_into(tokens)
+        .values(&to_insert)
+        .get_result(conn)
+        .map_err(|e| format!("Failed to create the token: {e}"))
+}
+
+/// Deletes a token and the tokens derived from it, returning how many were
+/// deleted. The root token can only be rotated.
+pub fn revoke_token(token_id: i32) -> Result<usize, String> {
+    if token_id == ROOT_TOKEN_ID {
+        return Err("The root token can not be revoked, rotate it instead".to_string());
+    }
+
+    let conn = &mut establish_connection();
+
+    let deleted = diesel::sql_query(
+        "WITH RECURSIVE rectree AS (
+        SELECT id
+            FROM tokens
+        WHERE id = $1
+        UNION ALL
+        SELECT t.id
+            FROM tokens t
+            JOIN rectree
+            ON t.parent = rectree.id
+        ) DELETE FROM tokens WHERE id IN (SELECT id FROM rectree);",
+    )
+    .bind::<Integer, _>(token_id)
+    .execute(conn)
+    .map_err(|e| format!("Failed to revoke the token: {e}"))?;
+
+    match deleted {
+        0 => Err(format!("No token has id {token_id}")),
+        deleted => Ok(deleted),
+    }
+}
+
+/// Restores exported tokens, keeping their ids and codes. Tokens whose id is
+/// already taken are skipped. Returns how many were inserted.
+pub fn import_tokens(exported: &[Token]) -> Result<usize, String> {
+    use crate::schema::tokens::dsl::*;
+    let conn = &mut establish_connection();
+
+    conn.transaction(|conn| {
+        let inserted = diesel::insert_into(tokens)
+            .values(exported)
+            .on_conflict(id)
+            .do_nothing()
+            .execute(conn)?;
+
+        // new tokens get ids after the imported ones
+        diesel::sql_query(
+            "SELECT setval(pg_get_serial_sequence('tokens', 'id'), \
+             (SELECT GREATEST(MAX(id), 1) FROM tokens));",
+        )
+        .execute(conn)?;
+
+        Ok(inserted)
+    })
+    .map_err(|e: diesel::result::Error| format!("Failed to import the tokens: {e}"))
+}
+
+/// Runs the pending migrations, returning their names
+pub fn run_migrations() -> Result<Vec<String>, String> {
+    let conn = &mut establish_connection();
+
+    conn.run_pending_migrations(MIGRATIONS)
+        .map(|versions| versions.iter().map(|version| version.to_string()).collect())
+        .map_err(|e| format!("Failed to run the migrations: {e}"))
+}
+
+/// Reverts the last migration, returning its name
+pub fn revert_migration() -> Result<String, String> {
+    let conn = &mut establish_connection();
+
+    conn.revert_last_migration(MIGRATIONS)
+        .map(|version| version.to_string())
+        .map_err(|e| format!("Failed to revert the migration: {e}"))
+}
+
+/// Probes MORK, returning its latency
+pub async fn check_mork(client: &MorkApiClient) -> Result<Duration, String> {
+    let started = Instant::now();
+
+    Backend::new(client.clone())
+        .dispatch(ProbeRequest::new())
+        .await
+        .map(|_| started.elapsed())
+        .map_err(|status| format!("MORK is unavailable: {status}"))
+}
@@ -0,0 +1,291 @@
+use regex::Regex;
+use rocket::http::Status;
+use rocket::serde::json::to_string;
+use serde::Serialize;
+use std::collections::{BTreeSet, HashMap};
+use std::sync::{LazyLock, Mutex};
+
+use super::{Dispatch, SpaceBackend};
+use crate::metta::{parse_atoms, Atom};
+use crate::mork_api::ExportFormat;
+
+/// The responses to writes, like `Added 3 atoms` or `Removed 1 atom`
+static REPORTED_COUNT: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^\w+ (\d+) atoms?$").unwrap());
+
+/// A single atom, as the patterns and templates are
+fn parse(source: &str) -> Result<Atom, Status> {
+    match parse_atoms(source).as_deref() {
+        Ok([atom]) => Ok(atom.clone()),
+        _ => {
+            tracing::warn!(source, "not a single MeTTa atom");
+            Err(Status::BadRequest)
+        }
+    }
+}
+
+type Bindings = HashMap<String, Atom>;
+
+/// Whether `atom` is an instance of `pattern`, extending `bindings` with the
+/// values of the variables of `pattern`. The variables of `atom` only match
+/// themselves.
+fn match_atom(pattern: &Atom, atom: &Atom, bindings: &mut Bindings) -> bool {
+    match (pattern, atom) {
+        (Atom::Variable(name), _) => match bindings.get(name) {
+            Some(value) => value == atom,
+            None => {
+                bindings.insert(name.clone(), atom.clone());
+                true
+            }
+        },
+        (Atom::Expression(patterns), Atom::Expression(atoms)) => {
+            patterns.len() == atoms.len()
+                && patterns
+                    .iter()
+                    .zip(atoms)
+                    .all(|(pattern, atom)| match_atom(pattern, atom, bindings))
+        }
+        _ => pattern == atom,
+    }
+}
+
+/// `template` with its bound variables replaced by their values
+fn substitute(template: &Atom, bindings: &Bindings) -> Atom {
+    match template {
+        Atom::Variable(name) => bindings
+            .get(name)
+            .cloned()
+            .unwrap_or_else(|| template.clone()),
+        Atom::Expression(children) => Atom::Expression(
+            children
+                .iter()
+                .map(|child| substitute(child, bindings))
+                .collect(),
+        ),
+        Atom::Symbol(_) => template.clone(),
+    }
+}
+
+/// An atom found by exploring, as MORK describes it
+#[derive(Serialize)]
+struct Explored {
+    expr: String,
+    token: Vec<u8>,
+}
+
+/// Keeps the atoms of all the spaces in memory, as MORK does: in one set, with
+/// every atom wrapped in the namespace encoding of its space. Nothing is kept
+/// across restarts.
+///
+/// Exploring is done in a single step, returning every matching atom with an
+/// empty token.
+#[derive(Default)]
+pub struct MemoryBackend {
+    atoms: Mutex<BTreeSet<Atom>>,
+}
+
+impl MemoryBackend {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The bindings of every atom matching `pattern`
+    fn matching(&self, pattern: &Atom) -> Vec<Bindings> {
+        self.atoms
+            .lock()
+            .unwrap()
+            .iter()
+            .filter_map(|atom| {
+                let mut bindings = Bindings::new();
+                match_atom(pattern, atom, &mut bindings).then_some(bindings)
+            })
+            .collect()
+    }
+
+    /// Adds `atoms`, returning how many were not there yet
+    fn insert(&self, atoms: impl IntoIterator<Item = Atom>) -> usize {
+        let mut stored = self.atoms.lock().unwrap();
+        atoms
+            .into_iter()
+            .filter(|atom| stored.insert(atom.clone()))
+            .count()
+    }
+
+    /// Adds the atoms of `data` matching `pattern`, rewritten with `template`
+    fn add(&self, pattern: &str, template: &str, data: &str) -> Result<String, Status> {
+        let pattern = parse(pattern)?;
+        let template = parse(template)?;
+        let atoms = parse_atoms(data).map_err(|e| {
+            tracing::warn!("Invalid MeTTa uploaded: {e}");
+            Status::BadRequest
+        })?;
+
+        let added = self.insert(atoms.iter().filter_map(|atom| {
+            let mut bindings = Bindings::new();
+            match_atom(&pattern, atom, &mut bindings).then(|| substitute(&template, &bindings))
+        }));
+
+        Ok(format!("Added {added} atoms"))
+    }
+}
+
+#[rocket::async_trait]
+impl SpaceBackend for MemoryBackend {
+    fn reported_count(&self, response: &str) -> Option<usize> {
+        REPORTED_COUNT
+            .captures(response.trim())?
+            .get(1)?
+            .as_str()
+            .parse()
+            .ok()
+    }
+
+    async fn upload(
+        &self,
+        _dispatch: &Dispatch,
+        pattern: &str,
+        template: &str,
+        data: &str,
+    ) -> Result<String, Status> {
+        self.add(pattern, template, data)
+    }
+
+    async fn import(
+        &self,
+        _dispatch: &Dispatch,
+        pattern: &str,
+        template: &str,
+        uri: &str,
+    ) -> Result<String, Status> {
+        let response = reqwest::get(uri).await.and_then(|r| r.error_for_status());
+        let data = match response {
+            Ok(response) => response.text().await,
+            Err(e) => Err(e),
+        }
+        .map_err(|e| {
+            tracing::warn!("Error fetching {uri} to import: {e}");
+            Status::BadGateway
+        })?;
+
+        self.add(pattern, template, &data)
+    }
+
+    async fn transform(
+        &self,
+        _dispatch: &Dispatch,
+        patterns: &[String],
+        templates: &[String],
+    ) -> Result<String, Status> {
+        let patterns = patterns
+            .iter()
+            .map(|pattern| parse(pattern))
+            .collect::<Result<Vec<_>, _>>()?;
+        let templates = templates
+            .iter()
+            .map(|template| parse(template))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        // the bindings of every way the patterns match together
+        let mut matches = vec![Bindings::new()];
+        {
+            let atoms = self.atoms.lock().unwrap();
+            for pattern in &patterns {
+                matches = matches
+                    .iter()
+                    .flat_map(|bindings| {
+                        atoms.iter().filter_map(move |atom| {
+                            let mut bindings = bindings.clone();
+                            match_atom(pattern, atom, &mut bindings).then_some(bindings)
+                        })
+                    })
+                    .collect();
+            }
+        }
+
+        let added = self.insert(matches.iter().flat_map(|bindings| {
+            templates
+                .iter()
+                .map(move |template| substitute(template, bindings))
+        }));
+
+        Ok(format!("Added {added} atoms"))
+    }
+
+    async fn explore(
+        &self,
+        _dispatch: &Dispatch,
+        pattern: &str,
+        token: &str,
+    ) -> Result<String, Status> {
+        let pattern = parse(pattern)?;
+
+        // everything is returned by the first step
+        let explored: Vec<Explored> = if token.is_empty() {
+            self.matching(&pattern)
+                .iter()
+                .map(|bindings| Explored {
+                    expr: substitute(&pattern, bindings).to_string(),
+                    token: vec![],
+                })
+                .collect()
+        } else {
+            vec![]
+        };
+
+        to_string(&explored).map_err(|_| Status::InternalServerError)
+    }
+
+    async fn export(
+        &self,
+        _dispatch: &Dispatch,
+        pattern: &str,
+        template: &str,
+        format: Option<ExportFormat>,
+        max_write: Option<usize>,
+    ) -> Result<String, Status> {
+        let pattern = parse(pattern)?;
+        let template = parse(template)?;
+
+        let atoms: BTreeSet<Atom> = self
+            .matching(&pattern)
+            .iter()
+            .map(|bindings| substitute(&template, bindings))
+            .collect();
+        let atoms = atoms.into_iter().take(max_write.unwrap_or(usize::MAX));
+
+        match format.unwrap_or_default() {
+            ExportFormat::Metta | ExportFormat::Raw => {
+                Ok(atoms.map(|atom| format!("{atom}\n")).collect())
+            }
+            ExportFormat::Json => {
+                let atoms: Vec<String> = atoms.map(|atom| atom.to_string()).collect();
+                to_string(&atoms).map_err(|_| Status::InternalServerError)
+            }
+            ExportFormat::Csv => Ok(atoms
+                .map(|atom| match atom {
+                    Atom::Expression(children) => {
+                        let cells: Vec<String> = children.iter().map(Atom::to_string).collect();
+                        format!("{}\n", cells.join(","))
+                    }
+                    atom => format!("{atom}\n"),
+                })
+                .collect()),
+        }
+    }
+
+    async fn clear(&self, _dispatch: &Dispatch, expr: &str) -> Result<String, Status> {
+        let expr = parse(expr)?;
+
+        let mut atoms = self.atoms.lock().unwrap();
+        let before = atoms.len();
+        atoms.retain(|atom| !match_atom(&expr, atom, &mut Bindings::new()));
+
+        Ok(format!("Removed {} atoms", before - atoms.len()))
+    }
+
+    async fn count(&self, _dispatch: &Dispatch, pattern: &str) -> Result<String, Status> {
+        let pattern = parse(pattern)?;
+
+        Ok(self.matching(&pattern).len().to_string())
+    }
+}
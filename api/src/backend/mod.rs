@@ -0,0 +1,347 @@
+pub mod memory;
+
+use rocket::http::Status;
+use rocket::request::{self, FromRequest};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+use uuid::Uuid;
+
+use crate::config::{Config, SpaceBackendKind};
+use crate::mork_api::{
+    CopyRequest, CountRequest, ExportFormat, MorkApiClient, Request, SNAPSHOT_NAMESPACE,
+};
+use crate::quotas;
+use crate::telemetry::{request_id, RequestId};
+
+use memory::MemoryBackend;
+
+/// What a request does to the atoms of the spaces. Patterns, templates and
+/// expressions are wrapped in the namespace encoding of `Namespace`.
+pub enum Call {
+    /// adds the atoms of `data` matching `pattern`, rewritten with `template`
+    Upload {
+        pattern: String,
+        template: String,
+        data: String,
+    },
+    /// adds the atoms of the resource at `uri` as `Upload` does
+    Import {
+        pattern: String,
+        template: String,
+        uri: String,
+    },
+    /// adds the `templates` for every way all the `patterns` match together
+    Transform {
+        patterns: Vec<String>,
+        templates: Vec<String>,
+    },
+    /// the atoms matching `pattern`, from the position given by `token`
+    Explore { pattern: String, token: String },
+    /// the atoms matching `pattern`, rewritten with `template`
+    Export {
+        pattern: String,
+        template: String,
+        format: Option<ExportFormat>,
+        max_write: Option<usize>,
+    },
+    /// removes the atoms matching `expr`
+    Clear { expr: String },
+    /// the number of atoms matching `pattern`
+    Count { pattern: String },
+}
+
+/// How a call is made, as described by its request
+pub struct Dispatch {
+    /// the name of the operation, e.g. `read`
+    pub operation: &'static str,
+    pub timeout: Option<Duration>,
+    /// whether the call can be retried
+    pub idempotent: bool,
+    /// the id of the API request making the call
+    pub request_id: Option<String>,
+}
+
+/// A store of the atoms of the spaces. Every call returns the response of the
+/// store as text, and fails with 503 when the store is unavailable.
+#[rocket::async_trait]
+pub trait SpaceBackend: Send + Sync {
+    async fn upload(
+        &self,
+        dispatch: &Dispatch,
+        pattern: &str,
+        template: &str,
+        data: &str,
+    ) -> Result<String, Status>;
+
+    async fn import(
+        &self,
+        dispatch: &Dispatch,
+        pattern: &str,
+        template: &str,
+        uri: &str,
+    ) -> Result<String, Status>;
+
+    async fn transform(
+        &self,
+        dispatch: &Dispatch,
+        patterns: &[String],
+        templates: &[String],
+    ) -> Result<String, Status>;
+
+    async fn explore(
+        &self,
+        dispatch: &Dispatch,
+        pattern: &str,
+        token: &str,
+    ) -> Result<String, Status>;
+
+    async fn export(
+        &self,
+        dispatch: &Dispatch,
+        pattern: &str,
+        template: &str,
+        format: Option<ExportFormat>,
+        max_write: Option<usize>,
+    ) -> Result<String, Status>;
+
+    async fn clear(&self, dispatch: &Dispatch, expr: &str) -> Result<String, Status>;
+
+    async fn count(&self, dispatch: &Dispatch, pattern: &str) -> Result<String, Status>;
+
+    /// The number of atoms a write added or removed, when its `response`
+    /// reports it. MORK does not, so the space is counted instead.
+    fn reported_count(&self, _response: &str) -> Option<usize> {
+        None
+    }
+}
+
+/// The backend of the spaces, chosen by `Config::space_backend`. Managed as
+/// Rocket state, so it is shared by every request.
+#[derive(Clone)]
+pub struct Backend {
+    backend: Arc<dyn SpaceBackend>,
+    request_id: Option<String>,
+    /// the timeout of every call, instead of the timeout of its request
+    timeout: Option<Duration>,
+}
+
+impl Backend {
+    pub fn new(backend: impl SpaceBackend + 'static) -> Self {
+        Backend {
+            backend: Arc::new(backend),
+            request_id: None,
+            timeout: None,
+        }
+    }
+
+    pub fn from_config(config: &Config) -> Self {
+        match config.space_backend {
+            SpaceBackendKind::Mork => Backend::new(MorkApiClient::new(config)),
+            SpaceBackendKind::Memory => Backend::new(MemoryBackend::new()),
+        }
+    }
+
+    /// A handle on the same backend, which passes `request_id` with every call
+    pub fn with_request_id(&self, request_id: RequestId) -> Self {
+        Backend {
+            request_id: Some(request_id.0),
+            ..self.clone()
+        }
+    }
+
+    /// A handle on the same backend, which makes every call with `timeout`.
+    /// Used by the jobs, which are detached from the HTTP request.
+    pub fn detached(&self, timeout: Duration) -> Self {
+        Backend {
+            timeout: Some(timeout),
+            ..self.clone()
+        }
+    }
+
+    /// Makes the call of `request`, once the quotas of the space it writes to
+    /// allow it. Fails with 413 when the call took the space above its atom
+    /// limit, which is only known once it is done.
+    pub async fn dispatch<R: Request>(&self, request: R) -> Result<String, Status> {
+        let call = request.call();
+        let dispatch = self.describe(&request);
+
+        let mut unchecked = vec![];
+        if let Some(path) = request.writes_to() {
+            let copied = request.copies_from();
+            unchecked = quotas::check_write(self, &path, &call, copied.as_deref()).await?;
+        }
+
+        let response = self.send(dispatch, call).await?;
+        quotas::check_written(self, &unchecked).await?;
+
+        Ok(response)
+    }
+
+    /// Makes the call of `request` as `dispatch` does, and tells how many
+    /// atoms it added or removed: from the response when the backend reports
+    /// it, or else by counting the space it changes before and after the
+    /// call. The count is off when other writes change the space meanwhile.
+    pub async fn dispatch_counted<R: Request>(
+        &self,
+        request: R,
+    ) -> Result<(String, Option<usize>), Status> {
+        let changed = request.changes();
+        let before = match &changed {
+            Some(path) => self.count(path).await.ok(),
+            None => None,
+        };
+
+        let response = self.dispatch(request).await?;
+        if let Some(count) = self.backend.reported_count(&response) {
+            return Ok((response, Some(count)));
+        }
+
+        let after = match &changed {
+            Some(path) if before.is_some() => self.count(path).await.ok(),
+            _ => None,
+        };
+
+        Ok((response, before.zip(after).map(|(b, a)| a.abs_diff(b))))
+    }
+
+    /// Makes the calls of `requests` in order, stopping at the first failure.
+    /// Returns the response of the last call.
+    pub async fn dispatch_all<R: Request>(&self, requests: Vec<R>) -> Result<String, Status> {
+        let mut response = String::new();
+
+        for request in requests {
+            response = self.dispatch(request).await?;
+        }
+
+        Ok(response)
+    }
+
+    /// Replaces the `target` subtree with a copy of the `source` subtree.
+    ///
+    /// MORK can not swap subtrees, so `source` is first copied to a staging
+    /// path and the live `target` is backed up next to it. The target is only
+    /// cleared once both copies succeeded, and is restored from its backup
+    /// when the copy from the staging path fails. Should that fail too, the
+    /// backup is kept and logged.
+    pub async fn replace(&self, source: PathBuf, target: PathBuf) -> Result<(), Status> {
+        let scratch =
+            PathBuf::from(SNAPSHOT_NAMESPACE).join(format!("r{}", Uuid::new_v4().simple()));
+        // both keep the name of the target, so they carry its data tag
+        let staged = scratch.join("staged").join(&target);
+        let previous = scratch.join("previous").join(&target);
+
+        let prepared: Result<String, Status> = async {
+            self.dispatch_all(CopyRequest::steps(source, staged.clone(), false))
+                .await?;
+            self.dispatch_all(CopyRequest::steps(target.clone(), previous.clone(), false))
+                .await
+        }
+        .await;
+
+        if let Err(e) = prepared {
+            self.remove_scratch(scratch).await;
+            return Err(e);
+        }
+
+        let swapped = self
+            .dispatch_all(CopyRequest::replace_steps(staged, target.clone()))
+            .await;
+
+        if let Err(e) = swapped {
+            let restored = self
+                .dispatch_all(CopyRequest::replace_steps(previous.clone(), target.clone()))
+                .await;
+
+            if restored.is_err() {
+                tracing::error!(
+                    space = %target.display(),
+                    backup = %previous.display(),
+                    "Kept the backup of a space that could not be restored",
+                );
+                return Err(e);
+            }
+
+            self.remove_scratch(scratch).await;
+            return Err(e);
+        }
+
+        self.remove_scratch(scratch).await;
+        Ok(())
+    }
+
+    async fn remove_scratch(&self, scratch: PathBuf) {
+        if let Err(e) = self.dispatch(CopyRequest::remove(scratch)).await {
+            tracing::error!("Failed to remove staged copy: {e}");
+        }
+    }
+
+    /// Counts the atoms of the `path` space and of all its subspaces
+    pub async fn count(&self, path: &Path) -> Result<usize, Status> {
+        let request = CountRequest::new().namespace(path.to_path_buf());
+        let response = self.send(self.describe(&request), request.call()).await?;
+
+        response.trim().parse().map_err(|_| {
+            tracing::error!(response, "The count of the atoms is not a number");
+            Status::InternalServerError
+        })
+    }
+
+    fn describe<R: Request>(&self, request: &R) -> Dispatch {
+        Dispatch {
+            operation: request.operation(),
+            timeout: self.timeout.or_else(|| request.timeout()),
+            idempotent: request.idempotent(),
+            request_id: self.request_id.clone(),
+        }
+    }
+
+    async fn send(&self, dispatch: Dispatch, call: Call) -> Result<String, Status> {
+        let backend = self.backend.as_ref();
+
+        match call {
+            Call::Upload {
+                pattern,
+                template,
+                data,
+            } => backend.upload(&dispatch, &pattern, &template, &data).await,
+            Call::Import {
+                pattern,
+                template,
+                uri,
+            } => backend.import(&dispatch, &pattern, &template, &uri).await,
+            Call::Transform {
+                patterns,
+                templates,
+            } => backend.transform(&dispatch, &patterns, &templates).await,
+            Call::Explore { pattern, token } => backend.explore(&dispatch, &pattern, &token).await,
+            Call::Export {
+                pattern,
+                template,
+                format,
+                max_write,
+            } => {
+                backend
+                    .export(&dispatch, &pattern, &template, format, max_write)
+                    .await
+            }
+            Call::Clear { expr } => backend.clear(&dispatch, &expr).await,
+            Call::Count { pattern } => backend.count(&dispatch, &pattern).await,
+        }
+    }
+}
+
+/// The managed backend, passing the id of the request
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for Backend {
+    type Error = ();
+
+    async fn from_request(request: &'r rocket::Request<'_>) -> request::Outcome<Self, ()> {
+        match request.rocket().state::<Backend>() {
+            Some(backend) => {
+                request::Outcome::Success(backend.with_request_id(request_id(request)))
+            }
+            None => request::Outcome::Error((Status::InternalServerError, ())),
+        }
+    }
+}
@@ -0,0 +1,209 @@
+use api::admin::{self, ROOT_TOKEN_ID};
+use api::config::Config;
+use api::db;
+use api::model::{Token, TokenInsert};
+use api::mork_api::MorkApiClient;
+use chrono::Utc;
+use pico_args::Arguments;
+use rocket::serde::json::{from_str, to_pretty_string};
+use rocket::tokio::runtime::Runtime;
+use std::fs;
+use std::process::ExitCode;
+
+const USAGE: &str = "\
+Manages MeTTa-KG without the API, using the database and MORK directly.
+
+Usage:
+  metta-kg-admin root-token [--rotate]    print, or rotate and print, the root token
+  metta-kg-admin tokens list
+  metta-kg-admin tokens create --namespace <namespace> [--description <text>]
+                 [--parent <id>] [--read] [--write] [--share-read] [--share-write]
+  metta-kg-admin tokens revoke <id>       also revokes the tokens derived from it
+  metta-kg-admin tokens export [<file>]   as JSON, to stdout without a file
+  metta-kg-admin tokens import <file>     skips the tokens whose id is taken
+  metta-kg-admin migrations run
+  metta-kg-admin migrations revert        reverts the last migration
+  metta-kg-admin mork check
+
+The configuration is read as by the API, from Rocket.toml, the environment
+and .env.
+";
+
+fn main() -> ExitCode {
+    let mut args = Arguments::from_env();
+
+    if args.contains(["-h", "--help"]) {
+        print!("{USAGE}");
+        return ExitCode::SUCCESS;
+    }
+
+    dotenv::dotenv().ok();
+
+    match run(args) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("{e}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+enum Command {
+    RootToken { rotate: bool },
+    ListTokens,
+    CreateToken(TokenInsert),
+    RevokeToken(i32),
+    ExportTokens(Option<String>),
+    ImportTokens(String),
+    RunMigrations,
+    RevertMigration,
+    CheckMork,
+}
+
+/// The command given by `args`, if any
+fn parse(mut args: Arguments) -> Result<Option<Command>, pico_args::Error> {
+    let command = args.subcommand()?;
+    let action = args.subcommand()?;
+
+    let command = match (command.as_deref(), action.as_deref()) {
+        (Some("root-token"), None) => Command::RootToken {
+            rotate: args.contains("--rotate"),
+        },
+        (Some("tokens"), Some("list")) => Command::ListTokens,
+        (Some("tokens"), Some("create")) => Command::CreateToken(TokenInsert {
+            code: String::new(),
+            description: args
+                .opt_value_from_str("--description")?
+                .unwrap_or_default(),
+            namespace: args.value_from_str("--namespace")?,
+            creation_timestamp: Utc::now().naive_utc(),
+            permission_read: args.contains("--read"),
+            permission_write: args.contains("--write"),
+            permission_share_share: false,
+            permission_share_read: args.contains("--share-read"),
+            permission_share_write: args.contains("--share-write"),
+            parent: Some(
+                args.opt_value_from_str("--parent")?
+                    .unwrap_or(ROOT_TOKEN_ID),
+            ),
+            rate_limit_read: None,
+            rate_limit_write: None,
+            rate_limit_translate: None,
+            concurrency_limit_read: None,
+            concurrency_limit_write: None,
+            concurrency_limit_translate: None,
+        }),
+        (Some("tokens"), Some("revoke")) => Command::RevokeToken(args.free_from_str()?),
+        (Some("tokens"), Some("export")) => Command::ExportTokens(args.opt_free_from_str()?),
+        (Some("tokens"), Some("import")) => Command::ImportTokens(args.free_from_str()?),
+        (Some("migrations"), Some("run")) => Command::RunMigrations,
+        (Some("migrations"), Some("revert")) => Command::RevertMigration,
+        (Some("mork"), Some("check")) => Command::CheckMork,
+        _ => return Ok(None),
+    };
+
+    let unused = args.finish();
+    if !unused.is_empty() {
+        return Err(pico_args::Error::ArgumentParsingFailed {
+            cause: format!("unexpected arguments {unused:?}"),
+        });
+    }
+
+    Ok(Some(command))
+}
+
+fn run(args: Arguments) -> Result<(), String> {
+    let command = match parse(args) {
+        Ok(Some(command)) => command,
+        Ok(None) => return Err(USAGE.to_string()),
+        Err(e) => return Err(format!("{e}\n\n{USAGE}")),
+    };
+
+    let config = Config::load().map_err(|e| format!("Invalid configuration: {e}"))?;
+    db::init(&config);
+
+    match command {
+        Command::RootToken { rotate } => {
+            let token = if rotate {
+                admin::rotate_root_token()?
+            } else {
+                admin::root_token()?
+            };
+            println!("{}", token.code);
+        }
+        Command::ListTokens => {
+            for token in admin::list_tokens()? {
+                println!("{}", describe(&token));
+            }
+        }
+        Command::CreateToken(new_token) => {
+            let token = admin::create_token(new_token)?;
+            println!("{}", describe(&token));
+            println!("{}", token.code);
+        }
+        Command::RevokeToken(id) => {
+            let revoked = admin::revoke_token(id)?;
+            println!("Revoked {revoked} tokens");
+        }
+        Command::ExportTokens(file) => {
+            let json = to_pretty_string(&admin::list_tokens()?).map_err(|e| e.to_string())?;
+
+            match file {
+                Some(file) => fs::write(&file, json).map_err(|e| format!("{file}: {e}"))?,
+                None => println!("{json}"),
+            }
+        }
+        Command::ImportTokens(file) => {
+            let json = fs::read_to_string(&file).map_err(|e| format!("{file}: {e}"))?;
+            let tokens: Vec<Token> = from_str(&json).map_err(|e| format!("{file}: {e}"))?;
+
+            let imported = admin::import_tokens(&tokens)?;
+            println!("Imported {imported} of {} tokens", tokens.len());
+        }
+        Command::RunMigrations => {
+            let versions = admin::run_migrations()?;
+            if versions.is_empty() {
+                println!("No pending migrations");
+            }
+            for version in versions {
+                println!("Ran {version}");
+            }
+        }
+        Command::RevertMigration => {
+            println!("Reverted {}", admin::revert_migration()?);
+        }
+        Command::CheckMork => {
+            let runtime = Runtime::new().map_err(|e| e.to_string())?;
+            let client = MorkApiClient::new(&config);
+            let latency = runtime.block_on(admin::check_mork(&client))?;
+            println!("MORK at {} is up, {latency:?}", config.mork_url);
+        }
+    }
+
+    Ok(())
+}
+
+/// `id  parent  namespace  permissions  description`, separated by tabs
+fn describe(token: &Token) -> String {
+    let permissions: Vec<&str> = [
+        (token.permission_read, "read"),
+        (token.permission_write, "write"),
+        (token.permission_share_read, "share-read"),
+        (token.permission_share_write, "share-write"),
+        (token.permission_share_share, "share-share"),
+    ]
+    .into_iter()
+    .filter_map(|(granted, name)| granted.then_some(name))
+    .collect();
+
+    format!(
+        "{}\t{}\t{}\t{}\t{}",
+        token.id,
+        token
+            .parent
+            .map_or_else(|| "-".to_string(), |parent| parent.to_string()),
+        token.namespace,
+        permissions.join(","),
+        token.description,
+    )
+}
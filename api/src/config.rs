@@ -0,0 +1,274 @@
+use rocket::data::ByteUnit;
+use rocket::figment::providers::Env;
+use rocket::figment::Figment;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::time::Duration;
+use tracing_subscriber::EnvFilter;
+use url::Url;
+
+/// The operations of the MORK client with a timeout of their own
+pub const MORK_OPERATIONS: [&str; 10] = [
+    "probe",
+    "read",
+    "export",
+    "explore",
+    "count",
+    "clear",
+    "import",
+    "upload",
+    "transform",
+    "copy",
+];
+
+/// The settings of the API, read from `Rocket.toml` and overridden by
+/// `METTA_KG_<SETTING>` environment variables, e.g. `METTA_KG_MORK_URL`.
+/// Managed as Rocket state.
+#[derive(Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct Config {
+    /// the origins allowed to make cross-origin requests
+    pub cors_origins: Vec<String>,
+    /// the largest body accepted by an upload
+    pub upload_limit: ByteUnit,
+    /// the interpreter running the translation scripts
+    pub python: PathBuf,
+    /// the directory of the translation scripts, e.g. `csv_to_metta_run.py`
+    pub translations_dir: PathBuf,
+    /// where the atoms of the spaces are kept
+    pub space_backend: SpaceBackendKind,
+    pub mork_url: String,
+    /// the timeout of the MORK operations without a timeout of their own
+    pub mork_timeout_ms: u64,
+    /// the timeouts of single MORK operations, by operation, e.g. `read`.
+    /// Set with `METTA_KG_MORK_TIMEOUT_<OPERATION>_MS`.
+    pub mork_timeouts_ms: HashMap<String, u64>,
+    /// the delay before the first retry of a request to MORK
+    pub mork_retry_delay_ms: u64,
+    /// how long the circuit breaker of the MORK client stays open
+    pub mork_breaker_cooldown_ms: u64,
+    /// the timeout of the MORK calls of background jobs, which are detached
+    /// from the HTTP request and may run much longer
+    pub job_timeout_ms: u64,
+    pub db_port: u16,
+    /// connections in the database pool
+    pub db_pool_size: u32,
+    /// the key signing the webhook deliveries
+    pub secret: Option<String>,
+    /// the delay before the first retry of a webhook delivery, doubled for
+    /// every next one
+    pub webhook_retry_delay_ms: u64,
+    /// the hosts webhooks may be registered for even though they do not
+    /// resolve to public addresses, e.g. `localhost`
+    pub webhook_allowed_hosts: Vec<String>,
+    /// the default requests per minute of a token or client address, for each
+    /// class of routes. Unlimited when unset.
+    pub rate_limit_read: Option<i32>,
+    pub rate_limit_write: Option<i32>,
+    pub rate_limit_translate: Option<i32>,
+    /// the default requests in progress at the same time of a token or client
+    /// address, for each class of routes. Unlimited when unset.
+    pub concurrency_limit_read: Option<i32>,
+    pub concurrency_limit_write: Option<i32>,
+    pub concurrency_limit_translate: Option<i32>,
+    /// the bearer token of the scrapes of `/metrics`, which is not served
+    /// without one
+    pub metrics_token: Option<String>,
+    /// the filter of the logs, e.g. `info` or `api=debug`
+    pub log: String,
+    pub log_format: LogFormat,
+}
+
+/// How the logs are written
+#[derive(Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum LogFormat {
+    Text,
+    Json,
+}
+
+/// The stores of the atoms of the spaces
+#[derive(Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum SpaceBackendKind {
+    /// the MORK server at `mork_url`
+    Mork,
+    /// the memory of the API, emptied on restart. For local development and
+    /// tests without MORK.
+    Memory,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            cors_origins: vec![
+                "http://localhost:3000".to_string(),
+                "https://metta-kg.vercel.app".to_string(),
+            ],
+            upload_limit: ByteUnit::Mebibyte(20),
+            python: PathBuf::from("./venv/bin/python"),
+            translations_dir: PathBuf::from("translations/src"),
+            space_backend: SpaceBackendKind::Mork,
+            // According to Dockerfile.mork
+            mork_url: "http://localhost:8001".to_string(),
+            mork_timeout_ms: 20_000,
+            mork_timeouts_ms: HashMap::new(),
+            mork_retry_delay_ms: 200,
+            mork_breaker_cooldown_ms: 30_000,
+            job_timeout_ms: 60 * 60 * 1_000,
+            db_port: 5432,
+            db_pool_size: 10,
+            secret: None,
+            webhook_retry_delay_ms: 1_000,
+            webhook_allowed_hosts: vec![],
+            rate_limit_read: None,
+            rate_limit_write: None,
+            rate_limit_translate: None,
+            concurrency_limit_read: None,
+            concurrency_limit_write: None,
+            concurrency_limit_translate: None,
+            metrics_token: None,
+            log: "info".to_string(),
+            log_format: LogFormat::Text,
+        }
+    }
+}
+
+impl Config {
+    /// The figment of Rocket, which reads `Rocket.toml`, with the `METTA_KG_`
+    /// environment variables on top. `METTA_KG_MORK_TIMEOUT_<OPERATION>_MS`
+    /// sets the timeout of an operation in `mork_timeouts_ms`.
+    pub fn figment() -> Figment {
+        let operation_timeouts = Env::prefixed("METTA_KG_MORK_TIMEOUT_")
+            .filter_map(|key| {
+                let key = key.as_str().to_ascii_lowercase();
+                let operation = key.strip_suffix("_ms")?;
+                Some(format!("mork_timeouts_ms.{operation}").into())
+            })
+            .global();
+
+        rocket::Config::figment()
+            .merge(Env::prefixed("METTA_KG_").global())
+            .merge(operation_timeouts)
+    }
+
+    /// Reads and validates the configuration
+    pub fn load() -> Result<Config, String> {
+        let config: Config = Config::figment().extract().map_err(|e| e.to_string())?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        for origin in &self.cors_origins {
+            Url::parse(origin).map_err(|e| format!("invalid CORS origin {origin}: {e}"))?;
+        }
+
+        match Url::parse(&self.mork_url) {
+            Ok(url) if ["http", "https"].contains(&url.scheme()) => {}
+            Ok(_) => return Err("the MORK URL must be http or https".into()),
+            Err(e) => return Err(format!("invalid MORK URL {}: {e}", self.mork_url)),
+        }
+
+        if self.upload_limit == 0 {
+            return Err("the upload limit must be positive".into());
+        }
+        if self.mork_timeout_ms == 0 {
+            return Err("the MORK timeout must be positive".into());
+        }
+        if self.job_timeout_ms == 0 {
+            return Err("the job timeout must be positive".into());
+        }
+        if self.db_port == 0 {
+            return Err("the database port must be positive".into());
+        }
+        if self.db_pool_size == 0 {
+            return Err("the database pool must have connections".into());
+        }
+
+        for (operation, timeout) in &self.mork_timeouts_ms {
+            if !MORK_OPERATIONS.contains(&operation.as_str()) {
+                return Err(format!("unknown MORK operation {operation}"));
+            }
+            if *timeout == 0 {
+                return Err(format!("the MORK {operation} timeout must be positive"));
+            }
+        }
+
+        if self.secret.as_deref() == Some("") {
+            return Err("the secret must not be empty".into());
+        }
+        if self.metrics_token.as_deref() == Some("") {
+            return Err("the metrics token must not be empty".into());
+        }
+        if self.webhook_retry_delay_ms == 0 {
+            return Err("the webhook retry delay must be positive".into());
+        }
+        if self
+            .webhook_allowed_hosts
+            .iter()
+            .any(|host| host.is_empty())
+        {
+            return Err("the allowed webhook hosts must not be empty".into());
+        }
+
+        let limits = [
+            self.rate_limit_read,
+            self.rate_limit_write,
+            self.rate_limit_translate,
+            self.concurrency_limit_read,
+            self.concurrency_limit_write,
+            self.concurrency_limit_translate,
+        ];
+        if limits.into_iter().flatten().any(|limit| limit <= 0) {
+            return Err("the rate and concurrency limits must be positive".into());
+        }
+
+        EnvFilter::try_new(&self.log)
+            .map_err(|e| format!("invalid log filter {}: {e}", self.log))?;
+
+        Ok(())
+    }
+
+    pub fn mork_timeout(&self) -> Duration {
+        Duration::from_millis(self.mork_timeout_ms)
+    }
+
+    /// The timeout of the MORK `operation`. Unless it is configured, reads
+    /// take 10 seconds, writes a minute and imports two minutes.
+    pub fn mork_operation_timeout(&self, operation: &str) -> Duration {
+        if let Some(timeout) = self.mork_timeouts_ms.get(operation) {
+            return Duration::from_millis(*timeout);
+        }
+
+        match operation {
+            "probe" => Duration::from_secs(2),
+            "read" | "export" | "explore" | "count" => Duration::from_secs(10),
+            "import" => Duration::from_secs(120),
+            "upload" | "transform" | "copy" => Duration::from_secs(60),
+            _ => self.mork_timeout(),
+        }
+    }
+
+    pub fn mork_retry_delay(&self) -> Duration {
+        Duration::from_millis(self.mork_retry_delay_ms)
+    }
+
+    pub fn mork_breaker_cooldown(&self) -> Duration {
+        Duration::from_millis(self.mork_breaker_cooldown_ms)
+    }
+
+    pub fn job_timeout(&self) -> Duration {
+        Duration::from_millis(self.job_timeout_ms)
+    }
+
+    pub fn webhook_retry_delay(&self) -> Duration {
+        Duration::from_millis(self.webhook_retry_delay_ms)
+    }
+
+    /// The path of the translation script `name`
+    pub fn translation_script(&self, name: &str) -> PathBuf {
+        self.translations_dir.join(name)
+    }
+}
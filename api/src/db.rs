@@ -1,17 +1,79 @@
 use diesel::pg::PgConnection;
-use diesel::Connection;
+use diesel::r2d2::{ConnectionManager, Pool, PooledConnection};
+use diesel_migrations::MigrationHarness;
 use std::env;
+use std::sync::OnceLock;
+use std::thread;
+use std::time::Duration;
 
-pub fn establish_connection() -> PgConnection {
+use crate::config::Config;
+use crate::MIGRATIONS;
+
+pub type DbPool = Pool<ConnectionManager<PgConnection>>;
+pub type DbConnection = PooledConnection<ConnectionManager<PgConnection>>;
+
+const CONNECTION_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// The longest wait between attempts to reach the database on startup
+const MAX_STARTUP_DELAY: Duration = Duration::from_secs(10);
+
+static POOL: OnceLock<DbPool> = OnceLock::new();
+
+fn database_url(port: u16) -> String {
     let user = env::var("POSTGRES_USER").expect("POSTGRES_USER must be set");
     let password = env::var("POSTGRES_PASSWORD").expect("POSTGRES_PASSWORD must be set");
     let db_name = env::var("POSTGRES_DB").expect("POSTGRES_DB must be set");
     let host = env::var("POSTGRES_HOST").unwrap_or_else(|_| "db".to_string());
-    let database_url = format!(
-        "postgresql://{}:{}@{}:5432/{}",
-        user, password, host, db_name
-    );
+    format!(
+        "postgresql://{}:{}@{}:{}/{}",
+        user, password, host, port, db_name
+    )
+}
+
+fn build_pool(config: &Config) -> DbPool {
+    Pool::builder()
+        .max_size(config.db_pool_size)
+        .min_idle(Some(0))
+        .connection_timeout(CONNECTION_TIMEOUT)
+        .build_unchecked(ConnectionManager::new(database_url(config.db_port)))
+}
+
+/// Creates the connection pool with `config`, unless it already exists
+pub fn init(config: &Config) {
+    POOL.get_or_init(|| build_pool(config));
+}
+
+/// The connection pool, created on first use with the loaded configuration
+/// unless `init` created it. Connections are opened lazily, so this does not
+/// fail while the database is down.
+pub fn pool() -> &'static DbPool {
+    POOL.get_or_init(|| build_pool(&Config::load().expect("valid configuration")))
+}
+
+pub fn establish_connection() -> DbConnection {
+    pool()
+        .get()
+        .unwrap_or_else(|e| panic!("Error connecting to the database {e}"))
+}
+
+/// Runs the pending migrations. While the database can not be reached, the
+/// connection is retried with a doubling delay.
+pub fn run_migrations() {
+    let mut delay = Duration::from_millis(500);
 
-    PgConnection::establish(&database_url)
-        .unwrap_or_else(|e| panic!("Error connecting to {database_url} {e}"))
+    loop {
+        match pool().get() {
+            Ok(mut connection) => {
+                connection
+                    .run_pending_migrations(MIGRATIONS)
+                    .expect("Failed to run migrations");
+                return;
+            }
+            Err(e) => {
+                tracing::warn!("Database unavailable, retrying in {delay:?}: {e}");
+                thread::sleep(delay);
+                delay = (delay * 2).min(MAX_STARTUP_DELAY);
+            }
+        }
+    }
 }
@@ -0,0 +1,75 @@
+use rocket::http::Status;
+use rocket::response::status::Custom;
+use rocket::response::{self, Responder};
+use rocket::serde::json::{to_value, Json, Value};
+use rocket::Request;
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+/// The body of every error response
+#[derive(Serialize, Deserialize, Clone, Debug, ToSchema)]
+pub struct ErrorBody {
+    /// a stable identifier of the error, e.g. `not_found` or `invalid_token`
+    pub code: String,
+    pub message: String,
+    /// more about the error, e.g. the report of a batch that failed
+    pub details: Option<Value>,
+}
+
+/// An error response, rendered as an `ErrorBody`
+#[derive(Debug)]
+pub struct ApiError {
+    pub status: Status,
+    pub body: ErrorBody,
+}
+
+/// `Not Found` as `not_found`
+fn status_code(status: Status) -> String {
+    match status.reason() {
+        Some(reason) => reason
+            .to_lowercase()
+            .replace(|c: char| !c.is_ascii_alphanumeric(), "_"),
+        None => "error".to_string(),
+    }
+}
+
+impl ApiError {
+    /// An error with the code and message of `status`
+    pub fn new(status: Status) -> Self {
+        ApiError {
+            status,
+            body: ErrorBody {
+                code: status_code(status),
+                message: status.reason().unwrap_or("Error").to_string(),
+                details: None,
+            },
+        }
+    }
+
+    pub fn code(mut self, code: &str) -> Self {
+        self.body.code = code.to_string();
+        self
+    }
+
+    pub fn message(mut self, message: impl Into<String>) -> Self {
+        self.body.message = message.into();
+        self
+    }
+
+    pub fn details(mut self, details: impl Serialize) -> Self {
+        self.body.details = to_value(details).ok();
+        self
+    }
+}
+
+impl From<Status> for ApiError {
+    fn from(status: Status) -> Self {
+        ApiError::new(status)
+    }
+}
+
+impl<'r> Responder<'r, 'static> for ApiError {
+    fn respond_to(self, request: &'r Request<'_>) -> response::Result<'static> {
+        Custom(self.status, Json(self.body)).respond_to(request)
+    }
+}
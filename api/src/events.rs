@@ -0,0 +1,148 @@
+use rocket::tokio::sync::broadcast;
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use utoipa::ToSchema;
+
+use crate::jobs::JobKind;
+use crate::model::Token;
+
+/// Events are dropped for subscribers that fall this far behind
+const CAPACITY: usize = 1024;
+
+#[derive(Serialize, Clone, Copy, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum SpaceEventKind {
+    Started,
+    Progress,
+    Finished,
+    Failed,
+}
+
+impl SpaceEventKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SpaceEventKind::Started => "started",
+            SpaceEventKind::Progress => "progress",
+            SpaceEventKind::Finished => "finished",
+            SpaceEventKind::Failed => "failed",
+        }
+    }
+}
+
+#[derive(Serialize, Clone, ToSchema)]
+pub struct SpaceEvent {
+    pub kind: SpaceEventKind,
+    pub operation: JobKind,
+    pub namespace: String,
+    pub token_id: i32,
+    pub job_id: Option<i32>,
+    /// number of atoms involved in the operation: the atoms uploaded, or the
+    /// atoms added or removed when the backend reports them, e.g. with
+    /// `Removed 3 atoms`. Not known otherwise.
+    pub count: Option<usize>,
+    pub message: Option<String>,
+}
+
+impl SpaceEvent {
+    /// Whether the event concerns `path` or one of its subspaces
+    pub fn is_within(&self, path: &Path) -> bool {
+        PathBuf::from(self.namespace.trim_start_matches('/')).starts_with(path)
+    }
+}
+
+/// Broadcasts the progress of space operations to the event streams.
+/// Managed as Rocket state.
+#[derive(Clone)]
+pub struct EventBus {
+    sender: broadcast::Sender<SpaceEvent>,
+}
+
+impl EventBus {
+    pub fn new() -> Self {
+        let (sender, _) = broadcast::channel(CAPACITY);
+        EventBus { sender }
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<SpaceEvent> {
+        self.sender.subscribe()
+    }
+
+    /// Describes an operation of `token` on the `path` space. No event is
+    /// emitted until one of the methods of `SpaceOperation` is called.
+    pub fn operation(&self, token: &Token, kind: JobKind, path: &Path) -> SpaceOperation {
+        SpaceOperation {
+            bus: self.clone(),
+            kind,
+            namespace: format!("/{}", path.to_string_lossy()),
+            token_id: token.id,
+            job_id: None,
+            count: None,
+        }
+    }
+}
+
+impl Default for EventBus {
+    fn default() -> Self {
+        EventBus::new()
+    }
+}
+
+#[derive(Clone)]
+pub struct SpaceOperation {
+    bus: EventBus,
+    pub kind: JobKind,
+    pub namespace: String,
+    pub token_id: i32,
+    pub job_id: Option<i32>,
+    pub count: Option<usize>,
+}
+
+impl SpaceOperation {
+    pub fn job(mut self, job_id: i32) -> Self {
+        self.job_id = Some(job_id);
+        self
+    }
+
+    pub fn count(mut self, count: usize) -> Self {
+        self.count = Some(count);
+        self
+    }
+
+    /// Takes `count`, the number of atoms added or removed as measured by
+    /// `Backend::dispatch_counted`, when no count is known yet
+    pub fn counted(mut self, count: Option<usize>) -> Self {
+        if self.count.is_none() {
+            self.count = count;
+        }
+        self
+    }
+
+    fn emit(&self, kind: SpaceEventKind, message: Option<String>) {
+        // sending only fails when nobody is listening
+        let _ = self.bus.sender.send(SpaceEvent {
+            kind,
+            operation: self.kind,
+            namespace: self.namespace.clone(),
+            token_id: self.token_id,
+            job_id: self.job_id,
+            count: self.count,
+            message,
+        });
+    }
+
+    pub fn started(&self) {
+        self.emit(SpaceEventKind::Started, None);
+    }
+
+    pub fn progress(&self, message: &str) {
+        self.emit(SpaceEventKind::Progress, Some(message.to_string()));
+    }
+
+    pub fn finished(&self) {
+        self.emit(SpaceEventKind::Finished, None);
+    }
+
+    pub fn failed(&self, message: &str) {
+        self.emit(SpaceEventKind::Failed, Some(message.to_string()));
+    }
+}
@@ -0,0 +1,271 @@
+use chrono::Utc;
+use diesel::{ExpressionMethods, QueryDsl, RunQueryDsl};
+use rocket::http::Status;
+use rocket::tokio::{self, task::AbortHandle};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tracing::Instrument;
+use utoipa::ToSchema;
+
+use crate::backend::Backend;
+use crate::config::Config;
+use crate::db::establish_connection;
+use crate::events::SpaceOperation;
+use crate::model::{Job, JobInsert};
+use crate::mork_api::Request;
+
+#[derive(Serialize, Clone, Copy, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum JobKind {
+    Transform,
+    Import,
+    Upload,
+    Clear,
+    Copy,
+    Move,
+    Snapshot,
+    Restore,
+}
+
+impl JobKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            JobKind::Transform => "transform",
+            JobKind::Import => "import",
+            JobKind::Upload => "upload",
+            JobKind::Clear => "clear",
+            JobKind::Copy => "copy",
+            JobKind::Move => "move",
+            JobKind::Snapshot => "snapshot",
+            JobKind::Restore => "restore",
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum JobStatus {
+    Pending,
+    Running,
+    Succeeded,
+    Failed,
+    Cancelled,
+}
+
+impl JobStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            JobStatus::Pending => "pending",
+            JobStatus::Running => "running",
+            JobStatus::Succeeded => "succeeded",
+            JobStatus::Failed => "failed",
+            JobStatus::Cancelled => "cancelled",
+        }
+    }
+}
+
+struct RunningJob {
+    handle: AbortHandle,
+    operation: SpaceOperation,
+}
+
+/// Runs space operations in the background and keeps track of the running ones
+/// so they can be cancelled. Managed as Rocket state.
+#[derive(Clone)]
+pub struct JobRunner {
+    running: Arc<Mutex<HashMap<i32, RunningJob>>>,
+    /// Jobs are detached from the HTTP request, so their calls are allowed to
+    /// run much longer than the default `Request::timeout`.
+    timeout: Duration,
+}
+
+impl JobRunner {
+    pub fn new(config: &Config) -> Self {
+        JobRunner {
+            running: Arc::default(),
+            timeout: config.job_timeout(),
+        }
+    }
+
+    /// Fails the jobs left pending or running by a previous process, whose
+    /// tasks died with it. Called on startup, before any job is submitted.
+    pub fn fail_interrupted() {
+        use crate::schema::jobs::dsl::*;
+
+        let failed = diesel::update(
+            jobs.filter(status.eq_any([JobStatus::Pending.as_str(), JobStatus::Running.as_str()])),
+        )
+        .set((
+            status.eq(JobStatus::Failed.as_str()),
+            finish_timestamp.eq(Utc::now().naive_utc()),
+            error.eq("interrupted by restart"),
+        ))
+        .execute(&mut establish_connection());
+
+        match failed {
+            Ok(0) => {}
+            Ok(count) => tracing::warn!(count, "Failed the jobs interrupted by a restart"),
+            Err(e) => tracing::error!("Failed to fail the interrupted jobs: {e}"),
+        }
+    }
+
+    /// Records a pending job for `operation` and dispatches `request` to the
+    /// backend in a background task. Returns the job as it was inserted.
+    pub fn submit<R>(
+        &self,
+        backend: &Backend,
+        operation: SpaceOperation,
+        request: R,
+    ) -> Result<Job, Status>
+    where
+        R: Request + Send + 'static,
+    {
+        // the count of an upload is known from its data
+        let counted = operation.count.is_none();
+
+        self.spawn(backend, operation, move |backend| async move {
+            if counted {
+                backend.dispatch_counted(request).await
+            } else {
+                backend.dispatch(request).await.map(|text| (text, None))
+            }
+        })
+    }
+
+    /// Records a pending job for `operation` and runs `task` in a background
+    /// task, with a handle on the backend that dispatches every call with the
+    /// job timeout. The task gives the response of the job and the number of
+    /// atoms it added or removed. Returns the job as it was inserted.
+    pub fn spawn<F, Fut>(
+        &self,
+        backend: &Backend,
+        operation: SpaceOperation,
+        task: F,
+    ) -> Result<Job, Status>
+    where
+        F: FnOnce(Backend) -> Fut + Send + 'static,
+        Fut: Future<Output = Result<(String, Option<usize>), Status>> + Send,
+    {
+        use crate::schema::jobs::dsl::*;
+        let conn = &mut establish_connection();
+
+        let to_insert = JobInsert {
+            token_id: operation.token_id,
+            kind: operation.kind.as_str().to_string(),
+            namespace: operation.namespace.clone(),
+            status: JobStatus::Pending.as_str().to_string(),
+            creation_timestamp: Utc::now().naive_utc(),
+        };
+
+        let job: Job = diesel::insert_into(jobs)
+            .values(&to_insert)
+            .get_result(conn)
+            .map_err(|_| Status::InternalServerError)?;
+
+        let operation = operation.job(job.id);
+        operation.started();
+
+        // hold the lock while spawning, so a job finishing immediately cannot
+        // remove its handle before it was registered
+        let mut running = self.running.lock().unwrap();
+
+        let job_id = job.id;
+        let runner = self.clone();
+        let task_operation = operation.clone();
+        let task_backend = backend.detached(self.timeout);
+        // the job logs in the span of the request that started it
+        let task = tokio::spawn(
+            async move {
+                runner.run(job_id, task_operation, task(task_backend)).await;
+            }
+            .in_current_span(),
+        );
+
+        running.insert(
+            job_id,
+            RunningJob {
+                handle: task.abort_handle(),
+                operation,
+            },
+        );
+
+        Ok(job)
+    }
+
+    async fn run(
+        &self,
+        job_id: i32,
+        operation: SpaceOperation,
+        task: impl Future<Output = Result<(String, Option<usize>), Status>>,
+    ) {
+        use crate::schema::jobs::dsl::*;
+
+        let started = diesel::update(jobs.filter(id.eq(job_id)))
+            .set((
+                status.eq(JobStatus::Running.as_str()),
+                start_timestamp.eq(Utc::now().naive_utc()),
+            ))
+            .execute(&mut establish_connection());
+
+        if let Err(e) = started {
+            tracing::error!(job_id, "Failed to start job: {e}");
+        }
+
+        operation.progress("dispatched to MORK");
+
+        let result = task.await;
+
+        let (new_status, new_response, new_error) = match result {
+            Ok((text, count)) => {
+                operation.counted(count).finished();
+                (JobStatus::Succeeded, Some(text), None)
+            }
+            Err(e) => {
+                operation.failed(&e.to_string());
+                (JobStatus::Failed, None, Some(e.to_string()))
+            }
+        };
+
+        let finished = diesel::update(jobs.filter(id.eq(job_id)))
+            .set((
+                status.eq(new_status.as_str()),
+                finish_timestamp.eq(Utc::now().naive_utc()),
+                response.eq(new_response),
+                error.eq(new_error),
+            ))
+            .execute(&mut establish_connection());
+
+        if let Err(e) = finished {
+            tracing::error!(job_id, "Failed to finish job: {e}");
+        }
+
+        self.running.lock().unwrap().remove(&job_id);
+    }
+
+    /// Aborts the task of a job that has not finished yet. Note that MORK may
+    /// still complete a request that was already sent.
+    pub fn cancel(&self, job_id: i32) -> Result<Job, Status> {
+        use crate::schema::jobs::dsl::*;
+
+        let job = match self.running.lock().unwrap().remove(&job_id) {
+            Some(job) => job,
+            None => return Err(Status::Conflict),
+        };
+
+        job.handle.abort();
+        job.operation.failed("cancelled");
+
+        diesel::update(
+            jobs.filter(id.eq(job_id))
+                .filter(status.eq_any([JobStatus::Pending.as_str(), JobStatus::Running.as_str()])),
+        )
+        .set((
+            status.eq(JobStatus::Cancelled.as_str()),
+            finish_timestamp.eq(Utc::now().naive_utc()),
+        ))
+        .get_result(&mut establish_connection())
+        .map_err(|_| Status::Conflict)
+    }
+}
@@ -1,29 +1,45 @@
+pub mod admin;
+pub mod backend;
+pub mod config;
 pub mod db;
+pub mod errors;
+pub mod events;
+pub mod jobs;
+pub mod metrics;
+pub mod metta;
 pub mod model;
 pub mod mork_api;
+pub mod openapi;
+pub mod quotas;
+pub mod rate_limits;
 pub mod routes;
+pub mod scheduler;
 pub mod schema;
+pub mod telemetry;
+pub mod versioning;
+pub mod webhooks;
 
-use diesel_migrations::{embed_migrations, EmbeddedMigrations, MigrationHarness};
+use diesel_migrations::{embed_migrations, EmbeddedMigrations};
 
 pub const MIGRATIONS: EmbeddedMigrations = embed_migrations!("migrations");
+use rocket::fairing::AdHoc;
 use rocket::http::Method;
-use rocket::{routes, Build, Rocket};
+use rocket::{catchers, routes, Build, Rocket};
 use rocket_cors::AllowedOrigins;
+use utoipa::OpenApi;
+use utoipa_swagger_ui::SwaggerUi;
 
 pub fn rocket() -> Rocket<Build> {
-    // TODO: move hardcoded allowed origins to database,
-    // or get backend and frontend hosted under same domain
-
     dotenv::dotenv().ok();
 
-    let mut connection = db::establish_connection();
-    connection
-        .run_pending_migrations(MIGRATIONS)
-        .expect("Failed to run migrations");
+    let config = config::Config::load().unwrap_or_else(|e| panic!("Invalid configuration: {e}"));
+    telemetry::init(&config);
+
+    db::init(&config);
+    db::run_migrations();
+    jobs::JobRunner::fail_interrupted();
 
-    let allowed_origins =
-        AllowedOrigins::some_exact(&["http://localhost:3000", "https://metta-kg.vercel.app"]);
+    let allowed_origins = AllowedOrigins::some_exact(&config.cors_origins);
 
     let cors = rocket_cors::CorsOptions {
         allowed_origins,
@@ -36,30 +52,109 @@ pub fn rocket() -> Rocket<Build> {
     .to_cors()
     .unwrap();
 
+    let events = events::EventBus::new();
+    let backend = backend::Backend::from_config(&config);
+
+    let api_routes = telemetry::traced(routes![
+        routes::translations::create_from_csv,
+        routes::translations::create_from_nt,
+        routes::translations::create_from_jsonld,
+        routes::translations::create_from_n3,
+        routes::tokens::get_all,
+        routes::tokens::get,
+        routes::tokens::create,
+        routes::tokens::update,
+        routes::tokens::delete,
+        routes::tokens::delete_batch,
+        routes::spaces::read,
+        routes::spaces::upload,
+        routes::spaces::import,
+        routes::spaces::transform,
+        routes::spaces::cross_transform,
+        routes::spaces::explore,
+        routes::spaces::export,
+        routes::spaces::clear,
+        routes::spaces::copy,
+        routes::spaces::move_space,
+        routes::spaces::events,
+        routes::spaces::diff,
+        routes::batch::batch,
+        routes::snapshots::create,
+        routes::snapshots::get_all,
+        routes::snapshots::export,
+        routes::snapshots::restore,
+        routes::snapshots::delete,
+        routes::queries::get_all,
+        routes::queries::create,
+        routes::queries::update,
+        routes::queries::delete,
+        routes::queries::view,
+        routes::pipelines::get_all,
+        routes::pipelines::get,
+        routes::pipelines::create,
+        routes::pipelines::run,
+        routes::schedules::get_all,
+        routes::schedules::get,
+        routes::schedules::create,
+        routes::schedules::enable,
+        routes::schedules::disable,
+        routes::schedules::run,
+        routes::schedules::get_runs,
+        routes::schedules::delete,
+        routes::webhooks::get_all,
+        routes::webhooks::create,
+        routes::webhooks::get_deliveries,
+        routes::webhooks::delete,
+        routes::quotas::get,
+        routes::quotas::set,
+        routes::quotas::delete,
+        routes::jobs::get_all,
+        routes::jobs::get,
+        routes::jobs::cancel,
+    ]);
+
     rocket::build()
+        .mount(versioning::API_BASE, api_routes.clone())
+        // the unversioned paths are kept as aliases until clients move to /v1
+        .mount("/", api_routes.clone())
+        .mount(
+            "/",
+            telemetry::traced(routes![
+                routes::health::healthz,
+                routes::health::readyz,
+                routes::metrics::get,
+            ]),
+        )
         .mount(
             "/",
-            routes![
-                routes::translations::create_from_csv,
-                routes::translations::create_from_nt,
-                routes::translations::create_from_jsonld,
-                routes::translations::create_from_n3,
-                routes::tokens::get_all,
-                routes::tokens::get,
-                routes::tokens::create,
-                routes::tokens::update,
-                routes::tokens::delete,
-                routes::tokens::delete_batch,
-                routes::spaces::read,
-                routes::spaces::upload,
-                routes::spaces::import,
-                routes::spaces::transform,
-                routes::spaces::explore,
-                routes::spaces::export,
-                routes::spaces::clear,
-            ],
+            SwaggerUi::new("/docs/<_..>").url("/openapi.json", openapi::ApiDoc::openapi()),
         )
         // .mount("/public", FileServer::from("static"))
+        .register("/", catchers![routes::errors::default])
+        .attach(telemetry::RequestTracing)
+        .attach(metrics::RequestMetrics)
         .attach(cors.clone())
+        .attach(rate_limits::RateLimiter::new(&config))
+        .attach(versioning::DeprecatedAliases::new(&api_routes))
         .manage(cors)
+        .manage(jobs::JobRunner::new(&config))
+        .manage(scheduler::Scheduler::new(events.clone(), backend.clone()))
+        .manage(webhooks::WebhookDispatcher::new(events.clone(), &config))
+        .manage(events)
+        .manage(backend)
+        .manage(config)
+        .attach(AdHoc::on_liftoff("Scheduler", |rocket| {
+            Box::pin(async move {
+                if let Some(scheduler) = rocket.state::<scheduler::Scheduler>() {
+                    scheduler.start(rocket.shutdown());
+                }
+            })
+        }))
+        .attach(AdHoc::on_liftoff("Webhooks", |rocket| {
+            Box::pin(async move {
+                if let Some(dispatcher) = rocket.state::<webhooks::WebhookDispatcher>() {
+                    dispatcher.start(rocket.shutdown());
+                }
+            })
+        }))
 }
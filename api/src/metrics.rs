@@ -0,0 +1,190 @@
+use diesel::{QueryDsl, RunQueryDsl};
+use prometheus::{
+    Encoder, HistogramOpts, HistogramVec, IntCounterVec, IntGauge, IntGaugeVec, Opts, Registry,
+    TextEncoder,
+};
+use rocket::fairing::{Fairing, Info, Kind};
+use rocket::{Data, Request, Response};
+use std::sync::OnceLock;
+use std::time::{Duration, Instant};
+
+use crate::db::{establish_connection, pool};
+
+static METRICS: OnceLock<Metrics> = OnceLock::new();
+
+/// The Prometheus series of the API. Labels only take values from bounded
+/// sets, such as route templates and operation names, never namespaces or
+/// token codes.
+pub struct Metrics {
+    registry: Registry,
+    requests: IntCounterVec,
+    request_duration: HistogramVec,
+    mork_duration: HistogramVec,
+    translation_duration: HistogramVec,
+    translation_failures: IntCounterVec,
+    db_connections: IntGaugeVec,
+    tokens: IntGauge,
+}
+
+/// The metrics of the process, registered on first use
+pub fn metrics() -> &'static Metrics {
+    METRICS.get_or_init(Metrics::new)
+}
+
+impl Metrics {
+    fn new() -> Self {
+        let registry = Registry::new_custom(Some("mettakg".to_string()), None)
+            .expect("the metrics prefix is valid");
+
+        let requests = IntCounterVec::new(
+            Opts::new("http_requests_total", "HTTP requests handled"),
+            &["method", "route", "status"],
+        )
+        .unwrap();
+        let request_duration = HistogramVec::new(
+            HistogramOpts::new(
+                "http_request_duration_seconds",
+                "Time spent handling HTTP requests",
+            ),
+            &["method", "route", "status"],
+        )
+        .unwrap();
+        let mork_duration = HistogramVec::new(
+            HistogramOpts::new(
+                "mork_request_duration_seconds",
+                "Time spent dispatching requests to MORK, including retries",
+            ),
+            &["operation", "outcome"],
+        )
+        .unwrap();
+        let translation_duration = HistogramVec::new(
+            HistogramOpts::new(
+                "translation_duration_seconds",
+                "Time spent in translation subprocesses",
+            )
+            .buckets(prometheus::exponential_buckets(0.1, 2.0, 12).unwrap()),
+            &["format"],
+        )
+        .unwrap();
+        let translation_failures = IntCounterVec::new(
+            Opts::new("translation_failures_total", "Failed translations"),
+            &["format"],
+        )
+        .unwrap();
+        let db_connections = IntGaugeVec::new(
+            Opts::new("db_pool_connections", "Connections in the database pool"),
+            &["state"],
+        )
+        .unwrap();
+        let tokens = IntGauge::new("tokens", "Tokens in the database").unwrap();
+
+        registry.register(Box::new(requests.clone())).unwrap();
+        registry
+            .register(Box::new(request_duration.clone()))
+            .unwrap();
+        registry.register(Box::new(mork_duration.clone())).unwrap();
+        registry
+            .register(Box::new(translation_duration.clone()))
+            .unwrap();
+        registry
+            .register(Box::new(translation_failures.clone()))
+            .unwrap();
+        registry.register(Box::new(db_connections.clone())).unwrap();
+        registry.register(Box::new(tokens.clone())).unwrap();
+
+        Metrics {
+            registry,
+            requests,
+            request_duration,
+            mork_duration,
+            translation_duration,
+            translation_failures,
+            db_connections,
+            tokens,
+        }
+    }
+
+    /// Records a dispatch of a MORK `operation`
+    pub fn mork_dispatch(&self, operation: &str, duration: Duration, succeeded: bool) {
+        let outcome = if succeeded { "success" } else { "failure" };
+
+        self.mork_duration
+            .with_label_values(&[operation, outcome])
+            .observe(duration.as_secs_f64());
+    }
+
+    /// Records a run of the translation subprocess for `format`
+    pub fn translation(&self, format: &str, duration: Duration, succeeded: bool) {
+        self.translation_duration
+            .with_label_values(&[format])
+            .observe(duration.as_secs_f64());
+
+        if !succeeded {
+            self.translation_failures.with_label_values(&[format]).inc();
+        }
+    }
+
+    /// Renders every series in the Prometheus text format. The gauges are
+    /// sampled at this point.
+    pub fn render(&self) -> String {
+        let state = pool().state();
+        let idle = state.idle_connections as i64;
+        self.db_connections
+            .with_label_values(&["active"])
+            .set(state.connections as i64 - idle);
+        self.db_connections.with_label_values(&["idle"]).set(idle);
+
+        let tokens = crate::schema::tokens::table
+            .count()
+            .get_result::<i64>(&mut establish_connection());
+        if let Ok(tokens) = tokens {
+            self.tokens.set(tokens);
+        }
+
+        let mut buffer = vec![];
+        if let Err(e) = TextEncoder::new().encode(&self.registry.gather(), &mut buffer) {
+            tracing::error!("Failed to encode metrics: {e}");
+        }
+
+        String::from_utf8(buffer).unwrap_or_default()
+    }
+}
+
+/// When the handling of a request started, kept in the request local cache
+struct RequestStart(Instant);
+
+/// Counts the requests and measures their latency, labelled by the route that
+/// handled them
+pub struct RequestMetrics;
+
+#[rocket::async_trait]
+impl Fairing for RequestMetrics {
+    fn info(&self) -> Info {
+        Info {
+            name: "Request metrics",
+            kind: Kind::Request | Kind::Response,
+        }
+    }
+
+    async fn on_request(&self, request: &mut Request<'_>, _: &mut Data<'_>) {
+        request.local_cache(|| RequestStart(Instant::now()));
+    }
+
+    async fn on_response<'r>(&self, request: &'r Request<'_>, response: &mut Response<'r>) {
+        let started = request.local_cache(|| RequestStart(Instant::now())).0;
+
+        let route = request
+            .route()
+            .map(|route| route.uri.to_string())
+            .unwrap_or_else(|| "unmatched".to_string());
+        let status = response.status().code.to_string();
+        let labels = [request.method().as_str(), route.as_str(), status.as_str()];
+
+        let metrics = metrics();
+        metrics.requests.with_label_values(&labels).inc();
+        metrics
+            .request_duration
+            .with_label_values(&labels)
+            .observe(started.elapsed().as_secs_f64());
+    }
+}
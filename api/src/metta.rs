@@ -0,0 +1,184 @@
+use std::fmt;
+
+/// A MeTTa atom: a symbol, a variable, or an expression of atoms. String
+/// literals are symbols, with their quotes.
+#[derive(Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Atom {
+    Symbol(String),
+    /// the name of the variable, with its `$`
+    Variable(String),
+    Expression(Vec<Atom>),
+}
+
+impl fmt::Display for Atom {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Atom::Symbol(name) | Atom::Variable(name) => write!(f, "{name}"),
+            Atom::Expression(children) => {
+                write!(f, "(")?;
+                for (i, child) in children.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, " ")?;
+                    }
+                    write!(f, "{child}")?;
+                }
+                write!(f, ")")
+            }
+        }
+    }
+}
+
+/// Reads atoms from MeTTa source, keeping track of the byte offset so the
+/// source of each atom can be sliced out
+struct Parser<'a> {
+    source: &'a str,
+    offset: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn new(source: &'a str) -> Self {
+        Parser { source, offset: 0 }
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.source[self.offset..].chars().next()
+    }
+
+    fn next(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.offset += c.len_utf8();
+        Some(c)
+    }
+
+    /// Skips whitespace and comments
+    fn skip(&mut self) {
+        while let Some(c) = self.peek() {
+            if c == ';' {
+                while let Some(c) = self.next() {
+                    if c == '\n' {
+                        break;
+                    }
+                }
+            } else if c.is_whitespace() {
+                self.next();
+            } else {
+                break;
+            }
+        }
+    }
+
+    fn atom(&mut self) -> Result<Atom, String> {
+        self.skip();
+
+        match self.peek() {
+            None => Err("expected an atom".to_string()),
+            Some(')') => Err("unbalanced ')'".to_string()),
+            Some('(') => {
+                self.next();
+                let mut children = vec![];
+
+                loop {
+                    self.skip();
+                    match self.peek() {
+                        None => return Err("missing ')'".to_string()),
+                        Some(')') => {
+                            self.next();
+                            return Ok(Atom::Expression(children));
+                        }
+                        Some(_) => children.push(self.atom()?),
+                    }
+                }
+            }
+            Some('"') => {
+                let start = self.offset;
+                self.next();
+
+                loop {
+                    match self.next() {
+                        None => return Err("unterminated string".to_string()),
+                        Some('\\') => {
+                            self.next();
+                        }
+                        Some('"') => {
+                            return Ok(Atom::Symbol(self.source[start..self.offset].to_string()))
+                        }
+                        Some(_) => (),
+                    }
+                }
+            }
+            Some(_) => {
+                let start = self.offset;
+                while let Some(c) = self.peek() {
+                    if c.is_whitespace() || c == '(' || c == ')' || c == '"' {
+                        break;
+                    }
+                    self.next();
+                }
+
+                let name = self.source[start..self.offset].to_string();
+                if name.starts_with('$') {
+                    Ok(Atom::Variable(name))
+                } else {
+                    Ok(Atom::Symbol(name))
+                }
+            }
+        }
+    }
+
+    /// Skips to the next atom, returning false at the end of the source
+    fn at_atom(&mut self) -> bool {
+        self.skip();
+        self.peek().is_some()
+    }
+}
+
+/// Parses the top level atoms of MeTTa source
+pub fn parse_atoms(metta: &str) -> Result<Vec<Atom>, String> {
+    let mut parser = Parser::new(metta);
+    let mut atoms = vec![];
+
+    while parser.at_atom() {
+        atoms.push(parser.atom()?);
+    }
+
+    Ok(atoms)
+}
+
+/// Splits MeTTa source into the source of its top level atoms, skipping
+/// comments. Malformed source is not rejected: a stray `)` is skipped and
+/// an atom left open runs to the end of the source.
+pub fn split_atoms(metta: &str) -> Vec<&str> {
+    let mut parser = Parser::new(metta);
+    let mut atoms = vec![];
+
+    while parser.at_atom() {
+        let start = parser.offset;
+        match parser.atom() {
+            Ok(_) => atoms.push(&metta[start..parser.offset]),
+            Err(_) if parser.offset == start => {
+                parser.next();
+            }
+            Err(_) => {
+                atoms.push(&metta[start..]);
+                break;
+            }
+        }
+    }
+
+    atoms
+}
+
+/// Counts the top level atoms of MeTTa source
+pub fn count_atoms(metta: &str) -> usize {
+    split_atoms(metta).len()
+}
+
+/// Checks that `metta` is a single well formed atom, as MORK expects for the
+/// patterns and templates of a transformation
+pub fn validate_atom(metta: &str) -> Result<(), String> {
+    match parse_atoms(metta)?.len() {
+        1 => Ok(()),
+        0 => Err("empty atom".to_string()),
+        n => Err(format!("expected a single atom, found {n}")),
+    }
+}
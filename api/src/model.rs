@@ -1,7 +1,11 @@
-use crate::schema::tokens;
+use crate::schema::{
+    jobs, pipeline_steps, pipelines, quotas, saved_queries, schedule_runs, schedules, snapshots,
+    tokens, webhook_deliveries, webhooks,
+};
 use chrono::NaiveDateTime;
-use diesel::{Insertable, Queryable, QueryableByName, Selectable};
+use diesel::{AsChangeset, Insertable, Queryable, QueryableByName, Selectable};
 use rocket::serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
 
 #[derive(Serialize, Deserialize, Insertable, Clone)]
 #[diesel(table_name = tokens)]
@@ -16,9 +20,19 @@ pub struct TokenInsert {
     pub permission_share_read: bool,
     pub permission_share_write: bool,
     pub parent: Option<i32>,
+    /// requests per minute, for each class of routes
+    pub rate_limit_read: Option<i32>,
+    pub rate_limit_write: Option<i32>,
+    pub rate_limit_translate: Option<i32>,
+    /// requests in progress at the same time, for each class of routes
+    pub concurrency_limit_read: Option<i32>,
+    pub concurrency_limit_write: Option<i32>,
+    pub concurrency_limit_translate: Option<i32>,
 }
 
-#[derive(Serialize, Deserialize, Queryable, Selectable, Clone, QueryableByName)]
+#[derive(
+    Serialize, Deserialize, Queryable, Selectable, Insertable, Clone, QueryableByName, ToSchema,
+)]
 #[diesel(table_name = tokens)]
 pub struct Token {
     pub id: i32,
@@ -32,4 +46,251 @@ pub struct Token {
     pub permission_share_read: bool,
     pub permission_share_write: bool,
     pub parent: Option<i32>,
+    /// requests per minute, for each class of routes
+    pub rate_limit_read: Option<i32>,
+    pub rate_limit_write: Option<i32>,
+    pub rate_limit_translate: Option<i32>,
+    /// requests in progress at the same time, for each class of routes
+    pub concurrency_limit_read: Option<i32>,
+    pub concurrency_limit_write: Option<i32>,
+    pub concurrency_limit_translate: Option<i32>,
+}
+
+#[derive(Serialize, Deserialize, Insertable, Clone)]
+#[diesel(table_name = jobs)]
+pub struct JobInsert {
+    pub token_id: i32,
+    pub kind: String,
+    pub namespace: String,
+    pub status: String,
+    pub creation_timestamp: NaiveDateTime,
+}
+
+#[derive(Serialize, Deserialize, Queryable, Selectable, Clone, QueryableByName, ToSchema)]
+#[diesel(table_name = jobs)]
+pub struct Job {
+    pub id: i32,
+    pub token_id: i32,
+    pub kind: String,
+    pub namespace: String,
+    pub status: String,
+    pub creation_timestamp: NaiveDateTime,
+    pub start_timestamp: Option<NaiveDateTime>,
+    pub finish_timestamp: Option<NaiveDateTime>,
+    pub response: Option<String>,
+    pub error: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Insertable, Clone)]
+#[diesel(table_name = snapshots)]
+pub struct SnapshotInsert {
+    pub name: String,
+    pub namespace: String,
+    pub token_id: Option<i32>,
+    pub creation_timestamp: NaiveDateTime,
+}
+
+#[derive(Serialize, Deserialize, Queryable, Selectable, Clone, ToSchema)]
+#[diesel(table_name = snapshots)]
+pub struct Snapshot {
+    pub id: i32,
+    pub name: String,
+    pub namespace: String,
+    pub token_id: Option<i32>,
+    pub creation_timestamp: NaiveDateTime,
+}
+
+#[derive(Serialize, Deserialize, Insertable, Clone)]
+#[diesel(table_name = saved_queries)]
+pub struct SavedQueryInsert {
+    pub namespace: String,
+    pub name: String,
+    pub patterns: Vec<String>,
+    pub templates: Vec<String>,
+    pub description: String,
+    pub token_id: Option<i32>,
+    pub creation_timestamp: NaiveDateTime,
+}
+
+#[derive(Serialize, Deserialize, Queryable, Selectable, Clone, ToSchema)]
+#[diesel(table_name = saved_queries)]
+pub struct SavedQuery {
+    pub id: i32,
+    pub namespace: String,
+    pub name: String,
+    pub patterns: Vec<String>,
+    pub templates: Vec<String>,
+    pub description: String,
+    pub token_id: Option<i32>,
+    pub creation_timestamp: NaiveDateTime,
+}
+
+#[derive(Serialize, Deserialize, Insertable, Clone)]
+#[diesel(table_name = pipelines)]
+pub struct PipelineInsert {
+    pub name: String,
+    pub version: i32,
+    pub description: String,
+    pub token_id: Option<i32>,
+    pub creation_timestamp: NaiveDateTime,
+    pub namespace: String,
+}
+
+#[derive(Serialize, Deserialize, Queryable, Selectable, Clone, ToSchema)]
+#[diesel(table_name = pipelines)]
+pub struct Pipeline {
+    pub id: i32,
+    pub name: String,
+    pub version: i32,
+    pub description: String,
+    pub token_id: Option<i32>,
+    pub creation_timestamp: NaiveDateTime,
+    /// the namespace of the token that created the first version
+    pub namespace: String,
+}
+
+#[derive(Serialize, Deserialize, Insertable, Clone)]
+#[diesel(table_name = pipeline_steps)]
+pub struct PipelineStepInsert {
+    pub pipeline_id: i32,
+    pub position: i32,
+    pub patterns: Vec<String>,
+    pub templates: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize, Queryable, Selectable, Clone, ToSchema)]
+#[diesel(table_name = pipeline_steps)]
+pub struct PipelineStep {
+    pub id: i32,
+    pub pipeline_id: i32,
+    pub position: i32,
+    pub patterns: Vec<String>,
+    pub templates: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize, Insertable, Clone)]
+#[diesel(table_name = schedules)]
+pub struct ScheduleInsert {
+    pub name: String,
+    pub namespace: String,
+    pub token_id: i32,
+    pub saved_query_id: Option<i32>,
+    pub pipeline_name: Option<String>,
+    pub pipeline_version: Option<i32>,
+    pub params: String,
+    pub cron: Option<String>,
+    pub watch_namespace: Option<String>,
+    pub creation_timestamp: NaiveDateTime,
+}
+
+#[derive(Serialize, Deserialize, Queryable, Selectable, Clone, QueryableByName, ToSchema)]
+#[diesel(table_name = schedules)]
+pub struct Schedule {
+    pub id: i32,
+    pub name: String,
+    pub namespace: String,
+    pub token_id: i32,
+    pub saved_query_id: Option<i32>,
+    pub pipeline_name: Option<String>,
+    pub pipeline_version: Option<i32>,
+    /// the pipeline parameters, as a JSON object
+    pub params: String,
+    pub cron: Option<String>,
+    pub watch_namespace: Option<String>,
+    pub enabled: bool,
+    pub last_run_timestamp: Option<NaiveDateTime>,
+    pub last_error: Option<String>,
+    pub creation_timestamp: NaiveDateTime,
+}
+
+#[derive(Serialize, Deserialize, Insertable, Clone)]
+#[diesel(table_name = schedule_runs)]
+pub struct ScheduleRunInsert {
+    pub schedule_id: i32,
+    pub trigger: String,
+    pub status: String,
+    pub start_timestamp: NaiveDateTime,
+}
+
+#[derive(Serialize, Deserialize, Queryable, Selectable, Clone, ToSchema)]
+#[diesel(table_name = schedule_runs)]
+pub struct ScheduleRun {
+    pub id: i32,
+    pub schedule_id: i32,
+    pub trigger: String,
+    pub status: String,
+    pub start_timestamp: NaiveDateTime,
+    pub finish_timestamp: Option<NaiveDateTime>,
+    pub error: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Insertable, Clone)]
+#[diesel(table_name = webhooks)]
+pub struct WebhookInsert {
+    pub namespace: String,
+    pub url: String,
+    pub token_id: i32,
+    pub creation_timestamp: NaiveDateTime,
+}
+
+#[derive(Serialize, Deserialize, Queryable, Selectable, Clone, QueryableByName, ToSchema)]
+#[diesel(table_name = webhooks)]
+pub struct Webhook {
+    pub id: i32,
+    pub namespace: String,
+    pub url: String,
+    pub token_id: i32,
+    pub creation_timestamp: NaiveDateTime,
+}
+
+#[derive(Serialize, Deserialize, Insertable, Clone)]
+#[diesel(table_name = webhook_deliveries)]
+pub struct WebhookDeliveryInsert {
+    pub webhook_id: i32,
+    pub operation: String,
+    pub namespace: String,
+    pub payload: String,
+    pub status: String,
+    pub attempts: i32,
+    pub creation_timestamp: NaiveDateTime,
+}
+
+#[derive(Serialize, Deserialize, Queryable, Selectable, Clone, ToSchema)]
+#[diesel(table_name = webhook_deliveries)]
+pub struct WebhookDelivery {
+    pub id: i32,
+    pub webhook_id: i32,
+    pub operation: String,
+    pub namespace: String,
+    pub payload: String,
+    pub status: String,
+    pub attempts: i32,
+    pub response_status: Option<i32>,
+    pub error: Option<String>,
+    pub creation_timestamp: NaiveDateTime,
+    pub last_attempt_timestamp: Option<NaiveDateTime>,
+}
+
+#[derive(Serialize, Deserialize, Insertable, AsChangeset, Clone)]
+#[diesel(table_name = quotas)]
+#[diesel(treat_none_as_null = true)]
+pub struct QuotaInsert {
+    pub namespace: String,
+    pub max_atoms: Option<i64>,
+    pub max_upload_bytes: Option<i64>,
+    pub max_import_bytes: Option<i64>,
+    pub token_id: Option<i32>,
+    pub update_timestamp: NaiveDateTime,
+}
+
+#[derive(Serialize, Deserialize, Queryable, Selectable, Clone, ToSchema)]
+#[diesel(table_name = quotas)]
+pub struct Quota {
+    pub id: i32,
+    pub namespace: String,
+    pub max_atoms: Option<i64>,
+    pub max_upload_bytes: Option<i64>,
+    pub max_import_bytes: Option<i64>,
+    pub token_id: Option<i32>,
+    pub update_timestamp: NaiveDateTime,
 }
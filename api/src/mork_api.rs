@@ -1,13 +1,24 @@
-use reqwest::{Client, Method};
+use rand::Rng;
+use reqwest::{Client, Method, RequestBuilder};
+use rocket::form::FromFormField;
 use rocket::http::Status;
+use rocket::tokio::time;
 use serde::{Deserialize, Serialize};
-use std::any::Any;
-use std::env;
+use std::collections::HashMap;
 use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use utoipa::ToSchema;
 
-#[derive(Serialize, Deserialize, Clone)]
-#[allow(dead_code)]
+use crate::backend::{Call, Dispatch, SpaceBackend};
+use crate::config::{Config, MORK_OPERATIONS};
+use crate::metrics::metrics;
+use crate::telemetry::REQUEST_ID_HEADER;
+
+/// The formats MORK can export a space in
+#[derive(Serialize, Deserialize, FromFormField, Clone, Copy, Default, ToSchema)]
 pub enum ExportFormat {
+    #[default]
     Metta,
     Json,
     Csv,
@@ -30,6 +41,13 @@ impl Default for TransformDetails {
     }
 }
 
+/// Snapshots are stored under this top level space. Namespace segments can not
+/// start with '_', so no token can be scoped to it.
+pub const SNAPSHOT_NAMESPACE: &str = "_snapshots";
+
+/// Probes of MORK export this top level space, which nothing is written to
+const PROBE_NAMESPACE: &str = "_probe";
+
 #[derive(Serialize, Deserialize, Clone)]
 pub struct Namespace {
     path: Vec<String>,
@@ -61,9 +79,13 @@ impl Namespace {
     }
 
     pub fn with_namespace(&self, value: &str) -> String {
-        let mut result = value.to_string();
+        self.with_prefix(&format!("({} {})", self.data_tag(), value))
+    }
 
-        result = format!("({} {})", self.data_tag(), result);
+    /// Wraps `value` in the path of the namespace only. Used as a pattern, it
+    /// matches the atoms of the space and of all of its subspaces.
+    pub fn with_prefix(&self, value: &str) -> String {
+        let mut result = value.to_string();
 
         for name in self.path.iter().rev() {
             result = format!("({name} {result})");
@@ -71,6 +93,20 @@ impl Namespace {
 
         result
     }
+
+    /// The path of the space, e.g. `a/b`, empty for the root space
+    pub fn path(&self) -> PathBuf {
+        self.path.iter().collect()
+    }
+
+    pub fn is_root(&self) -> bool {
+        self.path.is_empty()
+    }
+
+    /// Whether `self` is `other` or one of its subspaces
+    pub fn is_within(&self, other: &Namespace) -> bool {
+        self.path.starts_with(&other.path)
+    }
 }
 
 impl From<PathBuf> for Namespace {
@@ -102,77 +138,361 @@ impl TransformDetails {
     }
 }
 
-pub struct MorkApiClient {
-    base_url: String,
-    client: Client,
+/// Idempotent requests are retried this many times after failing to reach MORK
+const MAX_RETRIES: u32 = 2;
+
+/// The circuit opens after this many consecutive failures to reach MORK
+const BREAKER_THRESHOLD: u32 = 5;
+
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Stops sending requests to MORK after repeated failures to reach it, so
+/// they fail fast instead of waiting for their timeout
+#[derive(Default)]
+struct CircuitBreaker {
+    failures: u32,
+    open_until: Option<Instant>,
 }
 
-impl Default for MorkApiClient {
-    fn default() -> Self {
-        Self {
-            base_url: "http://localhost:8001".to_string(), // According to Dockerfile.mork
-            client: Client::new(),
+impl CircuitBreaker {
+    /// Whether a request may be sent. Once the cooldown is over, a single
+    /// request is let through while the circuit stays open for the others.
+    fn admit(&mut self, cooldown: Duration) -> bool {
+        match self.open_until {
+            Some(open_until) if Instant::now() < open_until => false,
+            Some(_) => {
+                self.open_until = Some(Instant::now() + cooldown);
+                true
+            }
+            None => true,
+        }
+    }
+
+    fn succeeded(&mut self) {
+        self.failures = 0;
+        self.open_until = None;
+    }
+
+    fn failed(&mut self, cooldown: Duration) {
+        self.failures += 1;
+        if self.failures >= BREAKER_THRESHOLD {
+            self.open_until = Some(Instant::now() + cooldown);
         }
     }
 }
 
+/// The client of the MORK server, the `SpaceBackend` of the API unless it is
+/// configured otherwise. Its connection pool and circuit breaker are shared by
+/// its clones.
+#[derive(Clone)]
+pub struct MorkApiClient {
+    base_url: String,
+    client: Client,
+    default_timeout: Duration,
+    /// the timeouts of the operations with a timeout of their own
+    operation_timeouts: HashMap<&'static str, Duration>,
+    /// the delay before the first retry, doubled for every next one and
+    /// jittered
+    retry_delay: Duration,
+    /// how long an open circuit fails requests before letting one through to
+    /// probe MORK
+    breaker_cooldown: Duration,
+    breaker: Arc<Mutex<CircuitBreaker>>,
+}
+
 impl MorkApiClient {
-    pub fn new() -> Self {
-        if let Ok(mork_url) = env::var("METTA_KG_MORK_URL") {
-            Self {
-                base_url: mork_url,
-                client: Client::new(),
-            }
-        } else {
-            Self::default()
+    pub fn new(config: &Config) -> Self {
+        let client = Client::builder()
+            .connect_timeout(CONNECT_TIMEOUT)
+            .pool_idle_timeout(Duration::from_secs(90))
+            .pool_max_idle_per_host(32)
+            .build()
+            .expect("the MORK client configuration is valid");
+
+        Self {
+            base_url: config.mork_url.clone(),
+            client,
+            default_timeout: config.mork_timeout(),
+            operation_timeouts: MORK_OPERATIONS
+                .into_iter()
+                .map(|operation| (operation, config.mork_operation_timeout(operation)))
+                .collect(),
+            retry_delay: config.mork_retry_delay(),
+            breaker_cooldown: config.mork_breaker_cooldown(),
+            breaker: Arc::new(Mutex::new(CircuitBreaker::default())),
         }
     }
 
-    pub async fn dispatch<R: Request>(&self, request: R) -> Result<String, Status> {
-        let url = format!("{}{}", self.base_url, request.path());
-        let mut http_request = self.client.request(request.method(), &url);
+    /// Sends a request to MORK, with `body` as text. Fails with 503 while the
+    /// circuit is open, or when MORK can not be reached. Idempotent requests
+    /// are retried with a jittered backoff, as long as the timeout of the
+    /// request has not run out: it bounds every attempt together.
+    async fn send(
+        &self,
+        dispatch: &Dispatch,
+        method: Method,
+        path: String,
+        body: Option<String>,
+    ) -> Result<String, Status> {
+        let started = Instant::now();
+
+        let result = self.send_with_retries(dispatch, method, path, body).await;
+
+        metrics().mork_dispatch(dispatch.operation, started.elapsed(), result.is_ok());
+        tracing::debug!(
+            request_id = dispatch.request_id.as_deref(),
+            operation = dispatch.operation,
+            latency_ms = started.elapsed().as_millis() as u64,
+            succeeded = result.is_ok(),
+            "dispatched to MORK"
+        );
 
-        if request.path().starts_with("/upload/") || request.path() == "/transform" {
-            if let Some(body) = request.body() {
-                if let Some(body_str) = (&body as &dyn Any).downcast_ref::<String>() {
-                    http_request = http_request
-                        .header("Content-Type", "text/plain")
-                        .body(body_str.clone());
-                } else {
-                    eprintln!("Upload endpoint called with non-string body type");
-                    return Err(Status::InternalServerError);
-                }
+        result
+    }
+
+    async fn send_with_retries(
+        &self,
+        dispatch: &Dispatch,
+        method: Method,
+        path: String,
+        body: Option<String>,
+    ) -> Result<String, Status> {
+        let retries = if dispatch.idempotent { MAX_RETRIES } else { 0 };
+        let deadline = Instant::now() + self.timeout(dispatch);
+        let mut delay = self.retry_delay;
+        let mut attempt = 0;
+
+        loop {
+            if !self.breaker.lock().unwrap().admit(self.breaker_cooldown) {
+                return Err(Status::ServiceUnavailable);
             }
-        } else if let Some(body) = request.body() {
-            http_request = http_request.json(&body);
-        }
 
-        http_request = http_request.timeout(request.timeout());
-        match http_request.send().await {
-            Ok(resp) => match resp.text().await {
-                Ok(text) => Ok(text),
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            let http_request = self
+                .build(dispatch, method.clone(), &path, body.clone())
+                .timeout(remaining);
+
+            match http_request.send().await {
+                Ok(response) => {
+                    self.breaker.lock().unwrap().succeeded();
+
+                    return match response.text().await {
+                        Ok(text) => Ok(text),
+                        Err(e) => {
+                            tracing::error!(
+                                request_id = dispatch.request_id.as_deref(),
+                                "Error reading Mork API response text: {e}"
+                            );
+                            Err(Status::InternalServerError)
+                        }
+                    };
+                }
                 Err(e) => {
-                    eprintln!("Error reading Mork API response text: {e}");
-                    Err(Status::InternalServerError)
+                    tracing::warn!(
+                        request_id = dispatch.request_id.as_deref(),
+                        attempt,
+                        "Error sending request to Mork API: {e}"
+                    );
+                    self.breaker.lock().unwrap().failed(self.breaker_cooldown);
+
+                    if attempt == retries {
+                        return Err(Status::ServiceUnavailable);
+                    }
                 }
-            },
-            Err(e) => {
-                eprintln!("Error sending request to Mork API: {e}");
-                Err(Status::InternalServerError)
             }
+
+            attempt += 1;
+            let jitter = rand::thread_rng().gen_range(0.5..1.5);
+            let backoff = delay.mul_f64(jitter);
+
+            // a retry that could not start before the deadline is not tried
+            if Instant::now() + backoff >= deadline {
+                tracing::warn!(
+                    request_id = dispatch.request_id.as_deref(),
+                    attempt,
+                    "No time left to retry the request to Mork API"
+                );
+                return Err(Status::ServiceUnavailable);
+            }
+
+            time::sleep(backoff).await;
+            delay *= 2;
         }
     }
+
+    fn build(
+        &self,
+        dispatch: &Dispatch,
+        method: Method,
+        path: &str,
+        body: Option<String>,
+    ) -> RequestBuilder {
+        let url = format!("{}{}", self.base_url, path);
+        let mut http_request = self.client.request(method, &url);
+
+        if let Some(body) = body {
+            http_request = http_request.header("Content-Type", "text/plain").body(body);
+        }
+
+        if let Some(request_id) = &dispatch.request_id {
+            http_request = http_request.header(REQUEST_ID_HEADER, request_id);
+        }
+
+        http_request
+    }
+
+    /// The timeout of the request, for all of its attempts
+    fn timeout(&self, dispatch: &Dispatch) -> Duration {
+        dispatch.timeout.unwrap_or_else(|| {
+            self.operation_timeouts
+                .get(dispatch.operation)
+                .copied()
+                .unwrap_or(self.default_timeout)
+        })
+    }
 }
 
+#[rocket::async_trait]
+impl SpaceBackend for MorkApiClient {
+    async fn upload(
+        &self,
+        dispatch: &Dispatch,
+        pattern: &str,
+        template: &str,
+        data: &str,
+    ) -> Result<String, Status> {
+        let path = format!(
+            "/upload/{}/{}",
+            urlencoding::encode(pattern),
+            urlencoding::encode(template)
+        );
+
+        self.send(dispatch, Method::POST, path, Some(data.to_string()))
+            .await
+    }
+
+    async fn import(
+        &self,
+        dispatch: &Dispatch,
+        pattern: &str,
+        template: &str,
+        uri: &str,
+    ) -> Result<String, Status> {
+        let path = format!(
+            "/import/{}/{}/?uri={uri}",
+            urlencoding::encode(pattern),
+            urlencoding::encode(template)
+        );
+
+        self.send(dispatch, Method::GET, path, None).await
+    }
+
+    async fn transform(
+        &self,
+        dispatch: &Dispatch,
+        patterns: &[String],
+        templates: &[String],
+    ) -> Result<String, Status> {
+        let code = format!(
+            "(transform (, {}) (, {}))",
+            patterns.join(" "),
+            templates.join(" ")
+        );
+
+        self.send(dispatch, Method::POST, "/transform".to_string(), Some(code))
+            .await
+    }
+
+    async fn explore(
+        &self,
+        dispatch: &Dispatch,
+        pattern: &str,
+        token: &str,
+    ) -> Result<String, Status> {
+        let path = format!("/explore/{}/{token}/", urlencoding::encode(pattern));
+
+        self.send(dispatch, Method::GET, path, None).await
+    }
+
+    async fn export(
+        &self,
+        dispatch: &Dispatch,
+        pattern: &str,
+        template: &str,
+        format: Option<ExportFormat>,
+        max_write: Option<usize>,
+    ) -> Result<String, Status> {
+        let mut path = format!(
+            "/export/{}/{}",
+            urlencoding::encode(pattern),
+            urlencoding::encode(template)
+        );
+
+        let mut query_params = Vec::new();
+
+        if let Some(format) = format {
+            let format_str = match format {
+                ExportFormat::Metta => "metta",
+                ExportFormat::Json => "json",
+                ExportFormat::Csv => "csv",
+                ExportFormat::Raw => "raw",
+            };
+            query_params.push(format!("format={format_str}"));
+        }
+
+        if let Some(max_write) = max_write {
+            query_params.push(format!("max_write={max_write}"));
+        }
+
+        if !query_params.is_empty() {
+            path.push_str("/?");
+            path.push_str(&query_params.join("&"));
+        }
+
+        self.send(dispatch, Method::GET, path, None).await
+    }
+
+    async fn clear(&self, dispatch: &Dispatch, expr: &str) -> Result<String, Status> {
+        let path = format!("/clear/{}", urlencoding::encode(expr));
+
+        self.send(dispatch, Method::GET, path, None).await
+    }
+
+    async fn count(&self, dispatch: &Dispatch, pattern: &str) -> Result<String, Status> {
+        let path = format!("/count/{}", urlencoding::encode(pattern));
+
+        self.send(dispatch, Method::GET, path, None).await
+    }
+}
+
+/// A request to the backend of the spaces
 pub trait Request {
-    type Body: Serialize + Any;
-    fn method(&self) -> Method;
-    fn path(&self) -> String;
-    fn body(&self) -> Option<Self::Body> {
+    /// the name of the operation, e.g. `transform`
+    fn operation(&self) -> &'static str;
+    /// what the request does to the spaces
+    fn call(&self) -> Call;
+    /// overrides the timeout of the operation
+    fn timeout(&self) -> Option<Duration> {
+        None
+    }
+    /// Whether sending the request again has no further effect, so it can be
+    /// retried
+    fn idempotent(&self) -> bool {
+        false
+    }
+    /// the space the request adds atoms to, checked against its quotas before
+    /// the request is dispatched
+    fn writes_to(&self) -> Option<PathBuf> {
+        None
+    }
+    /// the subtree whose atoms the request copies into `writes_to`
+    fn copies_from(&self) -> Option<PathBuf> {
         None
     }
-    fn timeout(&self) -> std::time::Duration {
-        std::time::Duration::from_secs(20)
+    /// the space the request adds atoms to or removes atoms from, counted
+    /// before and after the request to report the change
+    fn changes(&self) -> Option<PathBuf> {
+        self.writes_to()
     }
 }
 
@@ -180,6 +500,10 @@ pub trait Request {
 pub struct TransformRequest {
     namespace: Namespace,
     transform_input: TransformDetails,
+    /// the namespace of each pattern, `namespace` is used for missing entries
+    sources: Vec<Namespace>,
+    /// the namespace of the templates, `namespace` is used if absent
+    target: Option<Namespace>,
 }
 
 impl TransformRequest {
@@ -197,52 +521,55 @@ impl TransformRequest {
         self
     }
 
-    fn multi_patterns(&self) -> String {
-        format!(
-            "(, {})",
-            self.transform_input
-                .patterns
-                .iter()
-                .map(|pattern| { self.namespace.with_namespace(pattern) })
-                .collect::<Vec<String>>()
-                .join(" ")
-        )
+    pub fn sources(mut self, sources: Vec<PathBuf>) -> Self {
+        self.sources = sources.into_iter().map(Namespace::from).collect();
+        self
     }
 
-    fn multi_templates(&self) -> String {
-        format!(
-            "(, {})",
-            self.transform_input
-                .templates
-                .iter()
-                .map(|pattern| { self.namespace.with_namespace(pattern) })
-                .collect::<Vec<String>>()
-                .join(" ")
-        )
+    pub fn target(mut self, target: PathBuf) -> Self {
+        self.target = Some(Namespace::from(target));
+        self
     }
 
-    pub fn transform_code(&self) -> String {
-        format!(
-            "(transform {} {})",
-            self.multi_patterns(),
-            self.multi_templates()
-        )
+    fn patterns(&self) -> Vec<String> {
+        self.transform_input
+            .patterns
+            .iter()
+            .enumerate()
+            .map(|(i, pattern)| {
+                self.sources
+                    .get(i)
+                    .unwrap_or(&self.namespace)
+                    .with_namespace(pattern)
+            })
+            .collect()
+    }
+
+    fn templates(&self) -> Vec<String> {
+        let target = self.target.as_ref().unwrap_or(&self.namespace);
+
+        self.transform_input
+            .templates
+            .iter()
+            .map(|template| target.with_namespace(template))
+            .collect()
     }
 }
 
 impl Request for TransformRequest {
-    type Body = String;
-
-    fn method(&self) -> Method {
-        Method::POST
+    fn operation(&self) -> &'static str {
+        "transform"
     }
 
-    fn path(&self) -> String {
-        "/transform".to_string()
+    fn call(&self) -> Call {
+        Call::Transform {
+            patterns: self.patterns(),
+            templates: self.templates(),
+        }
     }
 
-    fn body(&self) -> Option<Self::Body> {
-        Some(self.transform_code())
+    fn writes_to(&self) -> Option<PathBuf> {
+        Some(self.target.as_ref().unwrap_or(&self.namespace).path())
     }
 }
 
@@ -270,30 +597,25 @@ impl ImportRequest {
 }
 
 impl Request for ImportRequest {
-    type Body = ();
-
-    fn method(&self) -> Method {
-        Method::GET
-    }
-
-    fn path(&self) -> String {
-        format!(
-            "/import/{}/{}/?uri={}",
-            urlencoding::encode("$x"),
-            urlencoding::encode(
-                &self.namespace.with_namespace(
-                    self.transform_input
-                        .templates
-                        .first()
-                        .unwrap_or(&"$x".to_string())
-                )
+    fn operation(&self) -> &'static str {
+        "import"
+    }
+
+    fn call(&self) -> Call {
+        Call::Import {
+            pattern: "$x".to_string(),
+            template: self.namespace.with_namespace(
+                self.transform_input
+                    .templates
+                    .first()
+                    .unwrap_or(&"$x".to_string()),
             ),
-            self.uri
-        )
+            uri: self.uri.clone(),
+        }
     }
 
-    fn body(&self) -> Option<Self::Body> {
-        None
+    fn writes_to(&self) -> Option<PathBuf> {
+        Some(self.namespace.path())
     }
 }
 
@@ -318,31 +640,31 @@ impl ReadRequest {
 }
 
 impl Request for ReadRequest {
-    type Body = ();
-
-    fn method(&self) -> Method {
-        Method::GET
+    fn operation(&self) -> &'static str {
+        "read"
     }
 
-    fn path(&self) -> String {
-        let path = format!(
-            "/export/{}/{}",
-            urlencoding::encode(
-                &self.namespace.with_namespace(
-                    self.transform_input
-                        .patterns
-                        .first()
-                        .unwrap_or(&String::from("$x"))
-                )
-            ),
-            urlencoding::encode(
+    fn call(&self) -> Call {
+        Call::Export {
+            pattern: self.namespace.with_namespace(
                 self.transform_input
-                    .templates
+                    .patterns
                     .first()
-                    .unwrap_or(&String::from("$x"))
-            )
-        );
-        path
+                    .unwrap_or(&String::from("$x")),
+            ),
+            template: self
+                .transform_input
+                .templates
+                .first()
+                .cloned()
+                .unwrap_or_else(|| String::from("$x")),
+            format: None,
+            max_write: None,
+        }
+    }
+
+    fn idempotent(&self) -> bool {
+        true
     }
 }
 
@@ -375,18 +697,19 @@ impl ExploreRequest {
 }
 
 impl Request for ExploreRequest {
-    type Body = ();
+    fn operation(&self) -> &'static str {
+        "explore"
+    }
 
-    fn method(&self) -> Method {
-        Method::GET
+    fn call(&self) -> Call {
+        Call::Explore {
+            pattern: self.namespace.with_namespace(&self.pattern),
+            token: self.token.clone(),
+        }
     }
 
-    fn path(&self) -> String {
-        format!(
-            "/explore/{}/{}/",
-            urlencoding::encode(&self.namespace.with_namespace(&self.pattern)),
-            self.token
-        )
+    fn idempotent(&self) -> bool {
+        true
     }
 }
 
@@ -425,21 +748,20 @@ impl UploadRequest {
 }
 
 impl Request for UploadRequest {
-    type Body = String;
-
-    fn method(&self) -> Method {
-        Method::POST
+    fn operation(&self) -> &'static str {
+        "upload"
     }
 
-    fn path(&self) -> String {
-        format!(
-            "/upload/{}/{}",
-            urlencoding::encode(&self.pattern),
-            urlencoding::encode(&self.namespace.with_namespace(&self.template))
-        )
+    fn call(&self) -> Call {
+        Call::Upload {
+            pattern: self.pattern.clone(),
+            template: self.namespace.with_namespace(&self.template),
+            data: self.data.clone(),
+        }
     }
-    fn body(&self) -> Option<Self::Body> {
-        Some(self.data.clone())
+
+    fn writes_to(&self) -> Option<PathBuf> {
+        Some(self.namespace.path())
     }
 }
 
@@ -476,48 +798,61 @@ impl ExportRequest {
         self.format = Some(format);
         self
     }
+
+    /// Exports at most `max_write` atoms
+    pub fn max_write(mut self, max_write: usize) -> Self {
+        self.max_write = Some(max_write);
+        self
+    }
 }
 
 impl Request for ExportRequest {
-    type Body = ();
-
-    fn method(&self) -> Method {
-        Method::GET
+    fn operation(&self) -> &'static str {
+        "export"
     }
 
-    fn path(&self) -> String {
-        let mut path = format!(
-            "/export/{}/{}",
-            urlencoding::encode(&self.namespace.with_namespace(&self.pattern)),
-            urlencoding::encode(&self.template)
-        );
+    fn call(&self) -> Call {
+        Call::Export {
+            pattern: self.namespace.with_namespace(&self.pattern),
+            template: self.template.clone(),
+            format: self.format,
+            max_write: self.max_write,
+        }
+    }
 
-        let mut query_params = Vec::new();
+    fn idempotent(&self) -> bool {
+        true
+    }
+}
 
-        if let Some(format) = &self.format {
-            let format_str = match format {
-                ExportFormat::Metta => "metta",
-                ExportFormat::Json => "json",
-                ExportFormat::Csv => "csv",
-                ExportFormat::Raw => "raw",
-            };
-            query_params.push(format!("format={format_str}"));
-        }
+/// A cheap request checking that MORK answers, exporting a space that is
+/// always empty
+pub struct ProbeRequest(ExportRequest);
 
-        if let Some(max_write) = self.max_write {
-            query_params.push(format!("max_write={max_write}"));
-        }
+impl ProbeRequest {
+    pub fn new() -> Self {
+        ProbeRequest(
+            ExportRequest::new()
+                .namespace(PathBuf::from(PROBE_NAMESPACE))
+                .pattern("$x".to_string())
+                .template("$x".to_string()),
+        )
+    }
+}
 
-        if !query_params.is_empty() {
-            path.push_str("/?");
-            path.push_str(&query_params.join("&"));
-        }
+impl Default for ProbeRequest {
+    fn default() -> Self {
+        ProbeRequest::new()
+    }
+}
 
-        path
+impl Request for ProbeRequest {
+    fn operation(&self) -> &'static str {
+        "probe"
     }
 
-    fn body(&self) -> Option<Self::Body> {
-        None
+    fn call(&self) -> Call {
+        self.0.call()
     }
 }
 
@@ -544,19 +879,195 @@ impl ClearRequest {
 }
 
 impl Request for ClearRequest {
-    type Body = ();
+    fn operation(&self) -> &'static str {
+        "clear"
+    }
 
-    fn method(&self) -> Method {
-        Method::GET
+    fn call(&self) -> Call {
+        Call::Clear {
+            expr: self.namespace.with_namespace(&self.expr),
+        }
     }
 
-    fn path(&self) -> String {
-        let expr_to_use = self.namespace.with_namespace(&self.expr);
+    fn idempotent(&self) -> bool {
+        true
+    }
 
-        format!("/clear/{}", urlencoding::encode(&expr_to_use))
+    fn changes(&self) -> Option<PathBuf> {
+        Some(self.namespace.path())
     }
+}
 
-    fn body(&self) -> Option<Self::Body> {
-        None
+/// Counts the atoms of a space and of all its subspaces
+#[derive(Default)]
+pub struct CountRequest {
+    namespace: Namespace,
+}
+
+impl CountRequest {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn namespace(mut self, ns: PathBuf) -> Self {
+        self.namespace = Namespace::from(ns);
+        self
+    }
+}
+
+impl Request for CountRequest {
+    fn operation(&self) -> &'static str {
+        "count"
+    }
+
+    fn call(&self) -> Call {
+        Call::Count {
+            pattern: self.namespace.with_prefix("$x"),
+        }
+    }
+
+    fn idempotent(&self) -> bool {
+        true
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum CopyStep {
+    /// copies every atom of the source subtree under the target path
+    CopySubtree,
+    /// gives the copied atoms of the source space the data tag of the target
+    Retag,
+    /// removes the copied atoms that still carry the data tag of the source
+    ClearStaleTag,
+    /// removes the source subtree, completing a move
+    ClearSource,
+    /// removes the target subtree before it is replaced
+    ClearTarget,
+}
+
+/// One step of copying or moving the `source` subtree to `target`. The data
+/// tag of a space depends on its name, so when the names differ the copied
+/// atoms of the space itself have to be retagged.
+pub struct CopyRequest {
+    source: Namespace,
+    target: Namespace,
+    step: CopyStep,
+}
+
+impl CopyRequest {
+    /// The steps to copy, or move when `remove_source` is set, `source` to `target`
+    pub fn steps(source: PathBuf, target: PathBuf, remove_source: bool) -> Vec<CopyRequest> {
+        let different_tags = Namespace::from(source.clone()).data_tag()
+            != Namespace::from(target.clone()).data_tag();
+
+        let mut steps = vec![CopyStep::CopySubtree];
+
+        if different_tags {
+            steps.push(CopyStep::Retag);
+            steps.push(CopyStep::ClearStaleTag);
+        }
+
+        if remove_source {
+            steps.push(CopyStep::ClearSource);
+        }
+
+        Self::with_steps(source, target, steps)
+    }
+
+    /// The steps to replace the `target` subtree with a copy of `source`
+    pub fn replace_steps(source: PathBuf, target: PathBuf) -> Vec<CopyRequest> {
+        let different_tags = Namespace::from(source.clone()).data_tag()
+            != Namespace::from(target.clone()).data_tag();
+
+        let mut steps = vec![CopyStep::ClearTarget, CopyStep::CopySubtree];
+
+        if different_tags {
+            steps.push(CopyStep::Retag);
+            steps.push(CopyStep::ClearStaleTag);
+        }
+
+        Self::with_steps(source, target, steps)
+    }
+
+    /// The step to remove the `source` subtree
+    pub fn remove(source: PathBuf) -> CopyRequest {
+        let source = Namespace::from(source);
+
+        CopyRequest {
+            target: source.clone(),
+            source,
+            step: CopyStep::ClearSource,
+        }
+    }
+
+    fn with_steps(source: PathBuf, target: PathBuf, steps: Vec<CopyStep>) -> Vec<CopyRequest> {
+        let source = Namespace::from(source);
+        let target = Namespace::from(target);
+
+        steps
+            .into_iter()
+            .map(|step| CopyRequest {
+                source: source.clone(),
+                target: target.clone(),
+                step,
+            })
+            .collect()
+    }
+
+    pub fn step(&self) -> CopyStep {
+        self.step
+    }
+
+    /// the atoms of the target space that still carry the source data tag
+    fn stale_tag_pattern(&self) -> String {
+        self.target
+            .with_prefix(&format!("({} $x)", self.source.data_tag()))
+    }
+
+    fn transformation(&self, pattern: String, template: String) -> Call {
+        Call::Transform {
+            patterns: vec![pattern],
+            templates: vec![template],
+        }
+    }
+}
+
+impl Request for CopyRequest {
+    fn operation(&self) -> &'static str {
+        "copy"
+    }
+
+    fn call(&self) -> Call {
+        match self.step {
+            CopyStep::CopySubtree => {
+                self.transformation(self.source.with_prefix("$x"), self.target.with_prefix("$x"))
+            }
+            CopyStep::Retag => {
+                self.transformation(self.stale_tag_pattern(), self.target.with_namespace("$x"))
+            }
+            CopyStep::ClearStaleTag => Call::Clear {
+                expr: self.stale_tag_pattern(),
+            },
+            CopyStep::ClearSource => Call::Clear {
+                expr: self.source.with_prefix("$x"),
+            },
+            CopyStep::ClearTarget => Call::Clear {
+                expr: self.target.with_prefix("$x"),
+            },
+        }
+    }
+
+    /// the clears can be retried, the transformations would copy again
+    fn idempotent(&self) -> bool {
+        !matches!(self.step, CopyStep::CopySubtree | CopyStep::Retag)
+    }
+
+    /// only copying the subtree adds atoms, retagging replaces them
+    fn writes_to(&self) -> Option<PathBuf> {
+        (self.step == CopyStep::CopySubtree).then(|| self.target.path())
+    }
+
+    fn copies_from(&self) -> Option<PathBuf> {
+        (self.step == CopyStep::CopySubtree).then(|| self.source.path())
     }
 }
@@ -0,0 +1,170 @@
+use utoipa::openapi::security::{ApiKey, ApiKeyValue, SecurityScheme};
+use utoipa::openapi::{Content, PathItem, Ref, RefOr};
+use utoipa::{Modify, OpenApi};
+
+use crate::errors::ErrorBody;
+use crate::mork_api;
+use crate::routes;
+
+/// The OpenAPI document of the API, generated from the route handlers and the
+/// types they take and return. Served at `/openapi.json`, with interactive
+/// docs at `/docs/`.
+#[derive(OpenApi)]
+#[openapi(
+    info(title = "MeTTa-KG API"),
+    paths(
+        routes::health::healthz,
+        routes::health::readyz,
+        routes::metrics::get,
+    ),
+    nest((path = "/v1", api = V1)),
+    components(schemas(ErrorBody)),
+    modifiers(&TokenAuth, &OperationIds, &ErrorResponses),
+    security(("token" = [])),
+    tags(
+        (name = "spaces", description = "Reading and writing the atoms of spaces in MORK"),
+        (name = "snapshots", description = "Named copies of spaces"),
+        (name = "queries", description = "Saved patterns and templates, and the views they define"),
+        (name = "pipelines", description = "Versioned sequences of transformations"),
+        (name = "schedules", description = "Queries and pipelines run on a cron expression or on changes"),
+        (name = "webhooks", description = "Notifications of the changes to spaces"),
+        (name = "quotas", description = "Limits on the size of spaces"),
+        (name = "jobs", description = "Operations run in the background"),
+        (name = "tokens", description = "Tokens and the tokens derived from them"),
+        (name = "translations", description = "Conversions of other formats to MeTTa"),
+        (name = "health", description = "Liveness and readiness"),
+        (name = "metrics", description = "Prometheus metrics"),
+    ),
+)]
+pub struct ApiDoc;
+
+/// The routes mounted at `/v1`. Their unversioned aliases are deprecated and
+/// left out.
+#[derive(OpenApi)]
+#[openapi(
+    paths(
+        routes::translations::create_from_csv,
+        routes::translations::create_from_nt,
+        routes::translations::create_from_jsonld,
+        routes::translations::create_from_n3,
+        routes::tokens::get_all,
+        routes::tokens::get,
+        routes::tokens::create,
+        routes::tokens::update,
+        routes::tokens::delete,
+        routes::tokens::delete_batch,
+        routes::spaces::read,
+        routes::spaces::upload,
+        routes::spaces::import,
+        routes::spaces::transform,
+        routes::spaces::cross_transform,
+        routes::spaces::explore,
+        routes::spaces::export,
+        routes::spaces::clear,
+        routes::spaces::copy,
+        routes::spaces::move_space,
+        routes::spaces::events,
+        routes::spaces::diff,
+        routes::batch::batch,
+        routes::snapshots::create,
+        routes::snapshots::get_all,
+        routes::snapshots::export,
+        routes::snapshots::restore,
+        routes::snapshots::delete,
+        routes::queries::get_all,
+        routes::queries::create,
+        routes::queries::update,
+        routes::queries::delete,
+        routes::queries::view,
+        routes::pipelines::get_all,
+        routes::pipelines::get,
+        routes::pipelines::create,
+        routes::pipelines::run,
+        routes::schedules::get_all,
+        routes::schedules::get,
+        routes::schedules::create,
+        routes::schedules::enable,
+        routes::schedules::disable,
+        routes::schedules::run,
+        routes::schedules::get_runs,
+        routes::schedules::delete,
+        routes::webhooks::get_all,
+        routes::webhooks::create,
+        routes::webhooks::get_deliveries,
+        routes::webhooks::delete,
+        routes::quotas::get,
+        routes::quotas::set,
+        routes::quotas::delete,
+        routes::jobs::get_all,
+        routes::jobs::get,
+        routes::jobs::cancel,
+    ),
+    // schemas only referenced from query parameters
+    components(schemas(routes::translations::CSVParseDirection, mork_api::ExportFormat))
+)]
+struct V1;
+
+/// Declares the token, sent as is in the `authorization` header
+struct TokenAuth;
+
+impl Modify for TokenAuth {
+    fn modify(&self, openapi: &mut utoipa::openapi::OpenApi) {
+        let components = openapi.components.get_or_insert_with(Default::default);
+
+        components.add_security_scheme(
+            "token",
+            SecurityScheme::ApiKey(ApiKey::Header(ApiKeyValue::new("authorization"))),
+        );
+    }
+}
+
+/// Prefixes the id of every operation with its tag, since handlers in
+/// different modules share names such as `get_all`
+struct OperationIds;
+
+impl Modify for OperationIds {
+    fn modify(&self, openapi: &mut utoipa::openapi::OpenApi) {
+        for item in openapi.paths.paths.values_mut() {
+            let PathItem {
+                get, post, delete, ..
+            } = item;
+
+            for operation in [get, post, delete].into_iter().flatten() {
+                let tag = operation.tags.iter().flatten().next();
+
+                if let (Some(tag), Some(id)) = (tag, &operation.operation_id) {
+                    operation.operation_id = Some(format!("{tag}_{id}"));
+                }
+            }
+        }
+    }
+}
+
+/// Gives every error response the `ErrorBody` returned by the catchers, unless
+/// it documents another body
+struct ErrorResponses;
+
+impl Modify for ErrorResponses {
+    fn modify(&self, openapi: &mut utoipa::openapi::OpenApi) {
+        for item in openapi.paths.paths.values_mut() {
+            let PathItem {
+                get, post, delete, ..
+            } = item;
+
+            for operation in [get, post, delete].into_iter().flatten() {
+                for (status, response) in operation.responses.responses.iter_mut() {
+                    let RefOr::T(response) = response else {
+                        continue;
+                    };
+
+                    if status.as_str() >= "400" && response.content.is_empty() {
+                        response.content.insert(
+                            "application/json".to_string(),
+                            Content::new(Some(Ref::from_schema_name("ErrorBody"))),
+                        );
+                    }
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,208 @@
+use diesel::{ExpressionMethods, QueryDsl, RunQueryDsl, SelectableHelper};
+use reqwest::header::CONTENT_LENGTH;
+use rocket::http::Status;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use utoipa::ToSchema;
+
+use crate::backend::{Backend, Call};
+use crate::db::establish_connection;
+use crate::metta::count_atoms;
+use crate::model::Quota;
+use crate::routes::is_reserved;
+use crate::webhooks::{is_allowed_url, public_client};
+
+/// A limit and the space that sets it
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, ToSchema)]
+pub struct QuotaLimit {
+    pub limit: i64,
+    pub namespace: String,
+}
+
+/// The limits that apply to a space. Every limit is inherited from the
+/// nearest enclosing space that sets it. The atom limit of a space applies to
+/// its whole subtree, the space and all of its subspaces together.
+#[derive(Serialize, Deserialize, Clone, Default, Debug, ToSchema)]
+pub struct EffectiveQuota {
+    pub max_atoms: Option<QuotaLimit>,
+    pub max_upload_bytes: Option<QuotaLimit>,
+    pub max_import_bytes: Option<QuotaLimit>,
+}
+
+/// The namespace of the `path` space, as quotas are stored, e.g. `/a/b/`
+pub fn namespace(path: &Path) -> String {
+    if path.as_os_str().is_empty() {
+        "/".to_string()
+    } else {
+        format!("/{}/", path.to_string_lossy())
+    }
+}
+
+/// The quotas set on the `path` space and on the spaces enclosing it, the
+/// nearest space first
+fn enclosing_quotas(path: &Path) -> Result<Vec<Quota>, Status> {
+    use crate::schema::quotas::dsl;
+
+    let namespaces: Vec<String> = path.ancestors().map(namespace).collect();
+
+    let mut results: Vec<Quota> = dsl::quotas
+        .select(Quota::as_select())
+        .filter(dsl::namespace.eq_any(namespaces))
+        .get_results(&mut establish_connection())
+        .map_err(|_| Status::InternalServerError)?;
+
+    results.sort_by_key(|quota| std::cmp::Reverse(quota.namespace.len()));
+
+    Ok(results)
+}
+
+/// Looks up the limits that apply to the `path` space
+pub fn effective_quota(path: &Path) -> Result<EffectiveQuota, Status> {
+    Ok(EffectiveQuota::inherited(&enclosing_quotas(path)?))
+}
+
+/// Checks a write adding atoms to the `path` space, with `call`, against the
+/// quotas. The atom limit of every enclosing space is checked, on the count
+/// of its subtree.
+///
+/// Uploads and copies, of the `copied` subtree, are checked with the number
+/// of atoms they add. Other writes are only let through while the subtrees
+/// are below their limits, and their number of atoms is only known once they
+/// are done: the quotas returned are to be checked again with
+/// `check_written` then.
+///
+/// Writes to the reserved spaces and copies out of them, which restore a
+/// snapshot, are not checked.
+pub async fn check_write(
+    backend: &Backend,
+    path: &Path,
+    call: &Call,
+    copied: Option<&Path>,
+) -> Result<Vec<Quota>, Status> {
+    if is_reserved(path) || copied.is_some_and(is_reserved) {
+        return Ok(vec![]);
+    }
+
+    let mut quotas = enclosing_quotas(path)?;
+    let quota = EffectiveQuota::inherited(&quotas);
+    quotas.retain(|quota| quota.max_atoms.is_some());
+
+    let (added, counted) = match call {
+        Call::Upload { data, .. } => {
+            quota.check_upload_size(data)?;
+            (count_atoms(data), true)
+        }
+        Call::Import { uri, .. } => {
+            quota.check_import(uri).await?;
+            (0, false)
+        }
+        _ => match copied {
+            Some(source) if !quotas.is_empty() => (backend.count(source).await?, true),
+            Some(_) => (0, true),
+            None => (0, false),
+        },
+    };
+
+    for quota in &quotas {
+        let atoms = subtree_atoms(backend, quota).await?;
+        if (atoms + added.max(1)) as i64 > quota.max_atoms.unwrap_or_default() {
+            return Err(Status::PayloadTooLarge);
+        }
+    }
+
+    Ok(if counted { vec![] } else { quotas })
+}
+
+/// Counts the subtrees of `quotas` again once a write is done, and fails with
+/// 413 when it took one above its atom limit. The atoms written are kept, and
+/// the overage is logged; the writes that follow are refused until atoms are
+/// removed.
+pub async fn check_written(backend: &Backend, quotas: &[Quota]) -> Result<(), Status> {
+    let mut result = Ok(());
+
+    for quota in quotas {
+        let max_atoms = quota.max_atoms.unwrap_or_default();
+        let atoms = subtree_atoms(backend, quota).await?;
+
+        if atoms as i64 > max_atoms {
+            tracing::warn!(
+                namespace = quota.namespace,
+                atoms,
+                max_atoms,
+                "A write took the space above its atom limit"
+            );
+            result = Err(Status::PayloadTooLarge);
+        }
+    }
+
+    result
+}
+
+/// The number of atoms in the subtree a quota is set on
+async fn subtree_atoms(backend: &Backend, quota: &Quota) -> Result<usize, Status> {
+    backend
+        .count(Path::new(quota.namespace.trim_matches('/')))
+        .await
+}
+
+impl EffectiveQuota {
+    /// The limits inherited from `quotas`, the quotas of a space and of the
+    /// spaces enclosing it with the nearest space first
+    fn inherited(quotas: &[Quota]) -> Self {
+        let inherited = |limit: fn(&Quota) -> Option<i64>| {
+            quotas.iter().find_map(|quota| {
+                limit(quota).map(|limit| QuotaLimit {
+                    limit,
+                    namespace: quota.namespace.clone(),
+                })
+            })
+        };
+
+        EffectiveQuota {
+            max_atoms: inherited(|quota| quota.max_atoms),
+            max_upload_bytes: inherited(|quota| quota.max_upload_bytes),
+            max_import_bytes: inherited(|quota| quota.max_import_bytes),
+        }
+    }
+
+    /// Checks the size of uploaded `data` against the upload size limit
+    pub fn check_upload_size(&self, data: &str) -> Result<(), Status> {
+        match &self.max_upload_bytes {
+            Some(max_upload_bytes) if data.len() as i64 > max_upload_bytes.limit => {
+                Err(Status::PayloadTooLarge)
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Checks the size of the resource at `uri` against the import size limit.
+    /// The size is only asked for to public addresses, as the webhooks are
+    /// sent; other resources and those that do not report their size are let
+    /// through, and are left to the atom limits.
+    pub async fn check_import(&self, uri: &str) -> Result<(), Status> {
+        let Some(max_import_bytes) = &self.max_import_bytes else {
+            return Ok(());
+        };
+
+        if !uri.starts_with("http://") && !uri.starts_with("https://") {
+            return Ok(());
+        }
+
+        if !is_allowed_url(uri, &[]).await {
+            return Ok(());
+        }
+
+        let size = public_client(vec![])
+            .head(uri)
+            .send()
+            .await
+            .ok()
+            .and_then(|response| response.headers().get(CONTENT_LENGTH).cloned())
+            .and_then(|length| length.to_str().ok()?.parse::<i64>().ok());
+
+        match size {
+            Some(size) if size > max_import_bytes.limit => Err(Status::PayloadTooLarge),
+            _ => Ok(()),
+        }
+    }
+}
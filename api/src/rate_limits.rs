@@ -0,0 +1,290 @@
+use rocket::fairing::{Fairing, Info, Kind};
+use rocket::http::{ContentType, Header, Method, Status};
+use rocket::serde::json::to_string;
+use rocket::{Data, Request, Response};
+use std::collections::HashMap;
+use std::io::Cursor;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use crate::config::Config;
+use crate::errors::ApiError;
+use crate::model::Token;
+use crate::routes::find_token;
+use crate::versioning::unversioned;
+
+/// Throttled requests are rewritten to this URI, which no route serves, so no
+/// handler runs for them
+const THROTTLED_URI: &str = "/_throttled";
+
+/// Probes of orchestrators, uptime checks and metrics scrapes are never
+/// throttled
+const UNLIMITED_URIS: [&str; 3] = ["/healthz", "/readyz", "/metrics"];
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum RouteClass {
+    Read,
+    Write,
+    Translate,
+}
+
+impl RouteClass {
+    pub fn of(method: Method, path: &str) -> RouteClass {
+        let segments: Vec<&str> = unversioned(path)
+            .split('/')
+            .filter(|s| !s.is_empty())
+            .collect();
+
+        match (method, segments.as_slice()) {
+            (_, ["translations", ..]) => RouteClass::Translate,
+            (Method::Get, _) => RouteClass::Read,
+            (Method::Post, ["spaces", "export" | "explore", ..]) => RouteClass::Read,
+            (Method::Post, ["snapshots", _, "export"]) => RouteClass::Read,
+            _ => RouteClass::Write,
+        }
+    }
+}
+
+/// The limits of a client for one class of routes. `None` means unlimited.
+#[derive(Clone, Copy, Default, Debug)]
+pub struct Limits {
+    /// requests per minute
+    pub rate: Option<i32>,
+    /// requests in progress at the same time
+    pub concurrency: Option<i32>,
+}
+
+impl Limits {
+    /// The default limits of a class of routes, from the configuration
+    fn from_config(config: &Config, class: RouteClass) -> Self {
+        match class {
+            RouteClass::Read => Limits {
+                rate: config.rate_limit_read,
+                concurrency: config.concurrency_limit_read,
+            },
+            RouteClass::Write => Limits {
+                rate: config.rate_limit_write,
+                concurrency: config.concurrency_limit_write,
+            },
+            RouteClass::Translate => Limits {
+                rate: config.rate_limit_translate,
+                concurrency: config.concurrency_limit_translate,
+            },
+        }
+    }
+
+    /// The limits of `token` for a class of routes, falling back to the
+    /// defaults of the class
+    fn for_token(self, token: &Token, class: RouteClass) -> Self {
+        let (rate, concurrency) = match class {
+            RouteClass::Read => (token.rate_limit_read, token.concurrency_limit_read),
+            RouteClass::Write => (token.rate_limit_write, token.concurrency_limit_write),
+            RouteClass::Translate => (
+                token.rate_limit_translate,
+                token.concurrency_limit_translate,
+            ),
+        };
+
+        Limits {
+            rate: rate.or(self.rate),
+            concurrency: concurrency.or(self.concurrency),
+        }
+    }
+}
+
+/// Buckets unused for this long are full again, or hold no request, and are
+/// evicted
+const IDLE_AFTER: Duration = Duration::from_secs(60);
+
+/// Who the limits apply to: a token, or the client address for requests
+/// without one
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+enum Client {
+    Token(i32),
+    Address(String),
+}
+
+/// A token bucket, refilled at `rate` requests per minute
+struct Bucket {
+    tokens: f64,
+    refilled: Instant,
+    in_progress: i32,
+}
+
+impl Bucket {
+    fn new(rate: i32) -> Self {
+        Bucket {
+            tokens: rate as f64,
+            refilled: Instant::now(),
+            in_progress: 0,
+        }
+    }
+
+    /// Takes a request from the bucket, or returns the seconds to wait
+    fn take(&mut self, rate: i32) -> Result<(), u64> {
+        let per_second = rate as f64 / 60.0;
+        let now = Instant::now();
+
+        self.tokens = (self.tokens + now.duration_since(self.refilled).as_secs_f64() * per_second)
+            .min(rate as f64);
+        self.refilled = now;
+
+        if self.tokens >= 1.0 {
+            self.tokens -= 1.0;
+            Ok(())
+        } else {
+            Err(((1.0 - self.tokens) / per_second).ceil() as u64)
+        }
+    }
+}
+
+/// The buckets of the clients. The idle ones are swept at most once every
+/// `IDLE_AFTER`, so clients that stop sending requests are forgotten.
+struct Buckets {
+    by_client: HashMap<(Client, RouteClass), Bucket>,
+    swept: Instant,
+}
+
+impl Buckets {
+    fn new() -> Self {
+        Buckets {
+            by_client: HashMap::new(),
+            swept: Instant::now(),
+        }
+    }
+
+    /// Evicts the buckets without requests in progress that have not been
+    /// taken from for `IDLE_AFTER`. A new bucket of the client is the same as
+    /// the evicted one.
+    fn sweep(&mut self) {
+        if self.swept.elapsed() < IDLE_AFTER {
+            return;
+        }
+
+        self.by_client
+            .retain(|_, bucket| bucket.in_progress > 0 || bucket.refilled.elapsed() < IDLE_AFTER);
+        self.swept = Instant::now();
+    }
+}
+
+/// What the fairing decided for a request, kept in the request local cache
+#[derive(Clone, Default)]
+enum Decision {
+    #[default]
+    Unlimited,
+    Admitted(Client, RouteClass),
+    Throttled {
+        retry_after: u64,
+    },
+}
+
+/// Enforces request rate and concurrency limits per token and class of
+/// routes. Throttled requests get `429 Too Many Requests` with a
+/// `Retry-After` header.
+pub struct RateLimiter {
+    defaults: HashMap<RouteClass, Limits>,
+    buckets: Arc<Mutex<Buckets>>,
+}
+
+impl RateLimiter {
+    pub fn new(config: &Config) -> Self {
+        let defaults = [RouteClass::Read, RouteClass::Write, RouteClass::Translate]
+            .into_iter()
+            .map(|class| (class, Limits::from_config(config, class)))
+            .collect();
+
+        RateLimiter {
+            defaults,
+            buckets: Arc::new(Mutex::new(Buckets::new())),
+        }
+    }
+
+    fn decide(&self, request: &Request<'_>) -> Decision {
+        if UNLIMITED_URIS.contains(&request.uri().path().as_str()) {
+            return Decision::Unlimited;
+        }
+
+        let class = RouteClass::of(request.method(), request.uri().path().as_str());
+        let defaults = self.defaults.get(&class).copied().unwrap_or_default();
+
+        let (client, limits) = match request.headers().get_one("authorization") {
+            Some(code) => match find_token(request, code) {
+                Ok(token) => (Client::Token(token.id), defaults.for_token(&token, class)),
+                // the token guard rejects the request
+                Err(_) => return Decision::Unlimited,
+            },
+            None => match request.client_ip() {
+                Some(ip) => (Client::Address(ip.to_string()), defaults),
+                None => return Decision::Unlimited,
+            },
+        };
+
+        if limits.rate.is_none() && limits.concurrency.is_none() {
+            return Decision::Unlimited;
+        }
+
+        let mut buckets = self.buckets.lock().unwrap();
+        buckets.sweep();
+
+        let bucket = buckets
+            .by_client
+            .entry((client.clone(), class))
+            .or_insert_with(|| Bucket::new(limits.rate.unwrap_or(0)));
+
+        if let Some(concurrency) = limits.concurrency {
+            if bucket.in_progress >= concurrency {
+                return Decision::Throttled { retry_after: 1 };
+            }
+        }
+
+        if let Some(rate) = limits.rate {
+            if let Err(retry_after) = bucket.take(rate) {
+                return Decision::Throttled { retry_after };
+            }
+        }
+
+        bucket.in_progress += 1;
+        Decision::Admitted(client, class)
+    }
+}
+
+#[rocket::async_trait]
+impl Fairing for RateLimiter {
+    fn info(&self) -> Info {
+        Info {
+            name: "Rate limiter",
+            kind: Kind::Request | Kind::Response,
+        }
+    }
+
+    async fn on_request(&self, request: &mut Request<'_>, _: &mut Data<'_>) {
+        let decision = self.decide(request);
+
+        if let Decision::Throttled { .. } = decision {
+            request.set_method(Method::Get);
+            request.set_uri(rocket::http::uri::Origin::parse(THROTTLED_URI).unwrap());
+        }
+
+        request.local_cache(|| decision);
+    }
+
+    async fn on_response<'r>(&self, request: &'r Request<'_>, response: &mut Response<'r>) {
+        match request.local_cache(Decision::default) {
+            Decision::Unlimited => (),
+            Decision::Admitted(client, class) => {
+                let mut buckets = self.buckets.lock().unwrap();
+                if let Some(bucket) = buckets.by_client.get_mut(&(client.clone(), *class)) {
+                    bucket.in_progress -= 1;
+                }
+            }
+            Decision::Throttled { retry_after } => {
+                let error = ApiError::new(Status::TooManyRequests);
+                let body = to_string(&error.body).unwrap_or_default();
+                response.set_status(error.status);
+                response.set_header(ContentType::JSON);
+                response.set_header(Header::new("Retry-After", retry_after.to_string()));
+                response.set_sized_body(body.len(), Cursor::new(body));
+            }
+        }
+    }
+}
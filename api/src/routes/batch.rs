@@ -0,0 +1,299 @@
+use rocket::http::Status;
+use rocket::post;
+use rocket::serde::json::Json;
+use rocket::State;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use url::Url;
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+use super::is_valid_namespace;
+use crate::backend::Backend;
+use crate::errors::ApiError;
+use crate::events::EventBus;
+use crate::jobs::JobKind;
+use crate::metta::count_atoms;
+use crate::model::Token;
+use crate::mork_api::{
+    ClearRequest, CopyRequest, ImportRequest, TransformDetails, TransformRequest, UploadRequest,
+    SNAPSHOT_NAMESPACE,
+};
+
+/// One operation of a batch, on the space at `namespace`
+#[derive(Serialize, Deserialize, Clone, ToSchema)]
+#[serde(tag = "op", rename_all = "lowercase")]
+pub enum BatchOperation {
+    Upload {
+        namespace: String,
+        data: String,
+    },
+    Transform {
+        namespace: String,
+        patterns: Vec<String>,
+        templates: Vec<String>,
+    },
+    Clear {
+        namespace: String,
+        expr: String,
+    },
+    Import {
+        namespace: String,
+        uri: String,
+    },
+}
+
+impl BatchOperation {
+    fn namespace(&self) -> &str {
+        match self {
+            BatchOperation::Upload { namespace, .. }
+            | BatchOperation::Transform { namespace, .. }
+            | BatchOperation::Clear { namespace, .. }
+            | BatchOperation::Import { namespace, .. } => namespace,
+        }
+    }
+
+    fn path(&self) -> PathBuf {
+        PathBuf::from(self.namespace().trim_matches('/'))
+    }
+
+    fn kind(&self) -> JobKind {
+        match self {
+            BatchOperation::Upload { .. } => JobKind::Upload,
+            BatchOperation::Transform { .. } => JobKind::Transform,
+            BatchOperation::Clear { .. } => JobKind::Clear,
+            BatchOperation::Import { .. } => JobKind::Import,
+        }
+    }
+
+    fn authorize(&self, token: &Token) -> Result<(), Status> {
+        // the root space can not be backed up, as it contains the backups
+        if !is_valid_namespace(self.namespace()) || self.namespace() == "/" {
+            return Err(Status::BadRequest);
+        }
+
+        if !self.namespace().starts_with(&token.namespace) || !token.permission_write {
+            return Err(Status::Unauthorized);
+        }
+
+        match self {
+            BatchOperation::Transform { .. } if !token.permission_read => Err(Status::Unauthorized),
+            BatchOperation::Import { uri, .. } if Url::parse(uri).is_err() => {
+                Err(Status::BadRequest)
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Dispatches the operation, with the number of atoms it added or removed
+    async fn dispatch(&self, backend: &Backend) -> Result<Option<usize>, Status> {
+        let path = self.path();
+
+        match self {
+            BatchOperation::Upload { data, .. } => {
+                let request = UploadRequest::new()
+                    .namespace(path)
+                    .pattern("$x".to_string())
+                    .template("$x".to_string())
+                    .data(data.clone());
+                backend.dispatch(request).await.map(|_| None)
+            }
+            BatchOperation::Transform {
+                patterns,
+                templates,
+                ..
+            } => {
+                let request = TransformRequest::new().namespace(path).transform_input(
+                    TransformDetails::new()
+                        .patterns(patterns.clone())
+                        .templates(templates.clone()),
+                );
+                backend
+                    .dispatch_counted(request)
+                    .await
+                    .map(|(_, count)| count)
+            }
+            BatchOperation::Clear { expr, .. } => {
+                let request = ClearRequest::new().namespace(path).expr(expr.clone());
+                backend
+                    .dispatch_counted(request)
+                    .await
+                    .map(|(_, count)| count)
+            }
+            BatchOperation::Import { uri, .. } => {
+                let request = ImportRequest::new().namespace(path).uri(uri.clone());
+                backend
+                    .dispatch_counted(request)
+                    .await
+                    .map(|(_, count)| count)
+            }
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Default, ToSchema)]
+pub struct BatchReport {
+    /// the number of operations that completed, in order
+    pub completed: usize,
+    pub error: Option<String>,
+    /// whether the affected spaces were restored after a failure
+    pub rolled_back: bool,
+    /// the namespace of the backup of the affected spaces, kept when they
+    /// could not be restored, to restore them by hand
+    pub backup: Option<String>,
+}
+
+/// The spaces touched by `operations`, leaving out those contained in others
+fn affected_spaces(operations: &[BatchOperation]) -> Vec<PathBuf> {
+    let mut paths: Vec<PathBuf> = operations.iter().map(|op| op.path()).collect();
+    paths.sort();
+    paths.dedup();
+
+    let mut spaces: Vec<PathBuf> = vec![];
+    for path in paths {
+        // sorted, so a containing space always comes first
+        if !spaces.iter().any(|space| path.starts_with(space)) {
+            spaces.push(path);
+        }
+    }
+
+    spaces
+}
+
+/// Executes a sequence of upload, transform, clear and import operations. All
+/// operations are authorized up front. The affected spaces are backed up
+/// first, and restored if any operation fails, so either all operations take
+/// effect or none do. The operations are reported as finished once all of
+/// them completed, or as failed when they are rolled back. When the spaces
+/// can not be restored, their backup is kept, and named in the report.
+#[utoipa::path(
+    tag = "spaces",
+    request_body = Vec<BatchOperation>,
+    responses(
+        (status = 200, description = "Every operation completed", body = BatchReport),
+        (status = 400, description = "An operation is invalid, so none ran, with the report in `details`"),
+        (status = 401, description = "The token can not write to a space, so no operation ran, with the report in `details`"),
+        (status = 413, description = "An operation exceeds a quota, with the report in `details`"),
+        (status = 500, description = "An operation failed, with the report in `details`"),
+        (status = 503, description = "MORK is unavailable while backing up the spaces, with the report in `details`"),
+    ),
+)]
+#[post("/spaces/batch", data = "<operations>")]
+pub async fn batch(
+    token: Token,
+    events: &State<EventBus>,
+    backend: Backend,
+    operations: Json<Vec<BatchOperation>>,
+) -> Result<Json<BatchReport>, ApiError> {
+    let fail = |status: Status, report: BatchReport| {
+        let error = match report.error.clone() {
+            Some(message) => ApiError::new(status).message(message),
+            None => ApiError::new(status),
+        };
+        Err(error.details(report))
+    };
+
+    for operation in operations.iter() {
+        if let Err(status) = operation.authorize(&token) {
+            return fail(
+                status,
+                BatchReport {
+                    error: Some(format!("{} on {}", status, operation.namespace())),
+                    ..Default::default()
+                },
+            );
+        }
+    }
+
+    let backup_root =
+        PathBuf::from(SNAPSHOT_NAMESPACE).join(format!("b{}", Uuid::new_v4().simple()));
+    let spaces = affected_spaces(&operations);
+
+    for space in &spaces {
+        let backup = CopyRequest::steps(space.clone(), backup_root.join(space), false);
+
+        if let Err(e) = backend.dispatch_all(backup).await {
+            let _ = backend
+                .dispatch(CopyRequest::remove(backup_root.clone()))
+                .await;
+            return fail(
+                e,
+                BatchReport {
+                    error: Some(format!("Failed to back up affected spaces: {e}")),
+                    ..Default::default()
+                },
+            );
+        }
+    }
+
+    let mut report = BatchReport::default();
+    let mut status = Status::InternalServerError;
+    // the operations that completed, reported as finished once all of them have
+    let mut completed = vec![];
+
+    for operation in operations.iter() {
+        let mut space_operation = events.operation(&token, operation.kind(), &operation.path());
+        if let BatchOperation::Upload { data, .. } = operation {
+            space_operation = space_operation.count(count_atoms(data));
+        }
+        space_operation.started();
+
+        match operation.dispatch(&backend).await {
+            Ok(count) => {
+                completed.push(space_operation.counted(count));
+                report.completed += 1;
+            }
+            Err(e) => {
+                space_operation.failed(&e.to_string());
+                if e == Status::PayloadTooLarge {
+                    status = e;
+                }
+                report.error = Some(format!(
+                    "Operation {} on {} failed: {e}",
+                    report.completed,
+                    operation.namespace()
+                ));
+                break;
+            }
+        }
+    }
+
+    if let Some(error) = &report.error {
+        let mut restored = true;
+        for space in &spaces {
+            restored &= backend
+                .replace(backup_root.join(space), space.clone())
+                .await
+                .is_ok();
+        }
+        report.rolled_back = restored;
+
+        let message = if restored {
+            format!("Rolled back: {error}")
+        } else {
+            format!("Failed to roll back: {error}")
+        };
+        for space_operation in &completed {
+            space_operation.failed(&message);
+        }
+    } else {
+        for space_operation in &completed {
+            space_operation.finished();
+        }
+    }
+
+    // the backup is the only copy of the spaces before the batch until they
+    // are restored
+    if report.error.is_some() && !report.rolled_back {
+        tracing::error!(backup = %backup_root.display(), "Kept batch backup after failed rollback");
+        report.backup = Some(format!("/{}/", backup_root.display()));
+    } else if let Err(e) = backend.dispatch(CopyRequest::remove(backup_root)).await {
+        tracing::error!("Failed to remove batch backup: {e}");
+    }
+
+    if report.error.is_some() {
+        return fail(status, report);
+    }
+
+    Ok(Json(report))
+}
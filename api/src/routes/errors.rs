@@ -0,0 +1,15 @@
+use rocket::http::Status;
+use rocket::{catch, Request};
+
+use super::AuthError;
+use crate::errors::ApiError;
+
+/// Renders every error as an `ErrorBody`, including the rejections of the
+/// `Token` guard
+#[catch(default)]
+pub fn default(status: Status, request: &Request<'_>) -> ApiError {
+    match request.local_cache(|| None::<AuthError>) {
+        Some(error) => error.to_error(),
+        None => ApiError::new(status),
+    }
+}
@@ -0,0 +1,148 @@
+use diesel::{sql_query, RunQueryDsl};
+use diesel_migrations::MigrationHarness;
+use rocket::get;
+use rocket::http::Status;
+use rocket::response::status::Custom;
+use rocket::serde::json::Json;
+use rocket::tokio::{task, time};
+use serde::{Deserialize, Serialize};
+use std::time::{Duration, Instant};
+use utoipa::ToSchema;
+
+use crate::backend::Backend;
+use crate::db::pool;
+use crate::mork_api::ProbeRequest;
+use crate::MIGRATIONS;
+
+/// The state of one dependency and how long checking it took
+#[derive(Serialize, Deserialize, Clone, ToSchema)]
+pub struct DependencyStatus {
+    pub up: bool,
+    pub latency_ms: u128,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl DependencyStatus {
+    fn of(started: Instant, result: Result<(), String>) -> Self {
+        DependencyStatus {
+            up: result.is_ok(),
+            latency_ms: started.elapsed().as_millis(),
+            error: result.err(),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, ToSchema)]
+pub struct Readiness {
+    pub ready: bool,
+    pub database: DependencyStatus,
+    /// whether every migration has been applied
+    pub migrations: DependencyStatus,
+    pub mork: DependencyStatus,
+}
+
+/// How long readiness waits for the database, connecting included
+const DATABASE_CHECK_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Checks the database and its migrations, blocking on the pool
+fn check_database() -> (DependencyStatus, DependencyStatus) {
+    let started = Instant::now();
+    let mut connection = pool()
+        .get_timeout(DATABASE_CHECK_TIMEOUT)
+        .map_err(|e| e.to_string());
+    let database = DependencyStatus::of(
+        started,
+        connection.as_mut().map_err(|e| e.clone()).and_then(|conn| {
+            sql_query("SELECT 1")
+                .execute(conn)
+                .map(|_| ())
+                .map_err(|e| e.to_string())
+        }),
+    );
+
+    let started = Instant::now();
+    let migrations = DependencyStatus::of(
+        started,
+        connection.and_then(|mut conn| match conn.has_pending_migration(MIGRATIONS) {
+            Ok(false) => Ok(()),
+            Ok(true) => Err("migrations are pending".to_string()),
+            Err(e) => Err(e.to_string()),
+        }),
+    );
+
+    (database, migrations)
+}
+
+/// Liveness, answers as long as the API is running
+#[utoipa::path(
+    tag = "health",
+    responses((status = 200, description = "The API is running", body = String)),
+    security(()),
+)]
+#[get("/healthz")]
+pub fn healthz() -> &'static str {
+    "OK"
+}
+
+/// Readiness, checks the database, its migrations and MORK. Responds with
+/// 503 when any of them is down.
+#[utoipa::path(
+    tag = "health",
+    responses(
+        (status = 200, description = "Every dependency is up", body = Readiness),
+        (status = 503, description = "A dependency is down", body = Readiness),
+    ),
+    security(()),
+)]
+#[get("/readyz")]
+pub async fn readyz(backend: Backend) -> Custom<Json<Readiness>> {
+    let started = Instant::now();
+    // the pool blocks while it waits for a connection, so the database is
+    // checked off the async workers, and given up on after a while
+    let checked = time::timeout(DATABASE_CHECK_TIMEOUT, task::spawn_blocking(check_database));
+    let (database, migrations) = match checked.await {
+        Ok(Ok(statuses)) => statuses,
+        Ok(Err(e)) => {
+            let error = format!("the check failed: {e}");
+            (
+                DependencyStatus::of(started, Err(error.clone())),
+                DependencyStatus::of(started, Err(error)),
+            )
+        }
+        Err(_) => {
+            let error = "the check timed out".to_string();
+            (
+                DependencyStatus::of(started, Err(error.clone())),
+                DependencyStatus::of(started, Err(error)),
+            )
+        }
+    };
+
+    let started = Instant::now();
+    let mork = DependencyStatus::of(
+        started,
+        backend
+            .dispatch(ProbeRequest::new())
+            .await
+            .map(|_| ())
+            .map_err(|status| status.to_string()),
+    );
+
+    let ready = database.up && migrations.up && mork.up;
+    let status = if ready {
+        Status::Ok
+    } else {
+        Status::ServiceUnavailable
+    };
+
+    Custom(
+        status,
+        Json(Readiness {
+            ready,
+            database,
+            migrations,
+            mork,
+        }),
+    )
+}
@@ -0,0 +1,83 @@
+use diesel::sql_types::Integer;
+use diesel::RunQueryDsl;
+use rocket::http::Status;
+use rocket::serde::json::Json;
+use rocket::{delete, get, State};
+
+use crate::db::establish_connection;
+use crate::jobs::JobRunner;
+use crate::model::{Job, Token};
+
+/// jobs created by `token` or by any of the tokens derived from it
+const VISIBLE_JOBS: &str = "WITH RECURSIVE rectree AS (
+    SELECT id
+        FROM tokens
+    WHERE id = $1
+    UNION ALL
+    SELECT t.id
+        FROM tokens t
+        JOIN rectree
+        ON t.parent = rectree.id
+    ) SELECT * FROM jobs WHERE token_id IN (SELECT id FROM rectree)";
+
+fn find_visible(token: &Token, job_id: i32) -> Result<Job, Status> {
+    let conn = &mut establish_connection();
+
+    diesel::sql_query(format!("{VISIBLE_JOBS} AND id = $2;"))
+        .bind::<Integer, _>(token.id)
+        .bind::<Integer, _>(job_id)
+        .get_result::<Job>(conn)
+        .map_err(|_| Status::NotFound)
+}
+
+#[utoipa::path(
+    tag = "jobs",
+    responses((status = 200, description = "The jobs of the token and of the tokens derived from it, newest first", body = Vec<Job>)),
+)]
+#[get("/jobs")]
+pub fn get_all(token: Token) -> Result<Json<Vec<Job>>, Status> {
+    let conn = &mut establish_connection();
+
+    let results = diesel::sql_query(format!("{VISIBLE_JOBS} ORDER BY id DESC;"))
+        .bind::<Integer, _>(token.id)
+        .get_results::<Job>(conn);
+
+    match results {
+        Ok(results) => Ok(Json(results)),
+        Err(_) => Err(Status::InternalServerError),
+    }
+}
+
+/// Reports the status, timings and MORK response or error of a job
+#[utoipa::path(
+    tag = "jobs",
+    responses(
+        (status = 200, body = Job),
+        (status = 404, description = "No visible job has this id"),
+    ),
+)]
+#[get("/jobs/<job_id>")]
+pub fn get(token: Token, job_id: i32) -> Result<Json<Job>, Status> {
+    find_visible(&token, job_id).map(Json)
+}
+
+/// Cancels a pending or running job
+#[utoipa::path(
+    tag = "jobs",
+    responses(
+        (status = 200, description = "The cancelled job", body = Job),
+        (status = 401, description = "The token can not write"),
+        (status = 404, description = "No visible job has this id"),
+        (status = 409, description = "The job already finished"),
+    ),
+)]
+#[delete("/jobs/<job_id>")]
+pub fn cancel(token: Token, runner: &State<JobRunner>, job_id: i32) -> Result<Json<Job>, Status> {
+    if !token.permission_write {
+        return Err(Status::Unauthorized);
+    }
+
+    find_visible(&token, job_id)?;
+
+    runner.cancel(job_id).map(Json)
+}
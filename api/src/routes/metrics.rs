@@ -0,0 +1,53 @@
+use rocket::http::{ContentType, Status};
+use rocket::request::{self, FromRequest};
+use rocket::{get, Request, State};
+
+use crate::config::Config;
+use crate::metrics::metrics;
+use crate::webhooks::secrets_match;
+
+/// The bearer token of a request, from its `Authorization` header
+pub struct Bearer(Option<String>);
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for Bearer {
+    type Error = ();
+
+    async fn from_request(request: &'r Request<'_>) -> request::Outcome<Self, Self::Error> {
+        let token = request
+            .headers()
+            .get_one("authorization")
+            .and_then(|value| value.strip_prefix("Bearer "))
+            .map(str::to_string);
+
+        request::Outcome::Success(Bearer(token))
+    }
+}
+
+/// The metrics of the API in the Prometheus text format. Only served when a
+/// metrics token is configured, to requests bearing it.
+#[utoipa::path(
+    tag = "metrics",
+    responses(
+        (status = 200, description = "The metrics in the Prometheus text format", body = String, content_type = "text/plain"),
+        (status = 401, description = "The request does not bear the metrics token"),
+        (status = 404, description = "No metrics token is configured"),
+    ),
+    security(()),
+)]
+#[get("/metrics")]
+pub fn get(config: &State<Config>, bearer: Bearer) -> Result<(ContentType, String), Status> {
+    match &config.metrics_token {
+        None => Err(Status::NotFound),
+        Some(token)
+            if bearer
+                .0
+                .as_deref()
+                .is_some_and(|given| secrets_match(token, given)) =>
+        {
+            let content_type = ContentType::new("text", "plain").with_params(("version", "0.0.4"));
+            Ok((content_type, metrics().render()))
+        }
+        Some(_) => Err(Status::Unauthorized),
+    }
+}
@@ -1,5 +1,8 @@
-use crate::{db::establish_connection, model::Token};
+use crate::errors::ApiError;
+use crate::telemetry::record_token;
+use crate::{db::establish_connection, model::Token, mork_api::SNAPSHOT_NAMESPACE};
 use diesel::{ExpressionMethods, QueryDsl, RunQueryDsl, SelectableHelper};
+use regex::Regex;
 use rocket::{
     self,
     http::Status,
@@ -8,24 +11,82 @@ use rocket::{
     Request,
 };
 use serde::{Deserialize, Serialize};
+use std::path::Path;
 
+pub mod batch;
+pub mod errors;
+pub mod health;
+pub mod jobs;
+pub mod metrics;
+pub mod pipelines;
+pub mod queries;
+pub mod quotas;
+pub mod schedules;
+pub mod snapshots;
 pub mod spaces;
 pub mod tokens;
 pub mod translations;
+pub mod webhooks;
 
-#[derive(Serialize, Deserialize, Debug)]
+/// Whether `namespace` follows the namespace rules, e.g. `/space/subspace/`.
+/// Segments start with a letter or digit, so the routes next to the spaces,
+/// such as `/spaces/_events/<path..>`, can never shadow one.
+pub fn is_valid_namespace(namespace: &str) -> bool {
+    let namespace_regex =
+        Regex::new(r"^/(([a-zA-Z0-9])+([a-zA-Z0-9]|\-|_)*([a-zA-Z0-9])/)*$").unwrap();
+
+    namespace_regex.is_match(namespace)
+}
+
+/// Whether `path` lies in a space managed by the API itself, which can not be
+/// written to directly
+pub fn is_reserved(path: &Path) -> bool {
+    path.starts_with(SNAPSHOT_NAMESPACE)
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy)]
 pub enum AuthError {
+    /// the request has no `authorization` header
+    MissingToken,
+    /// no token has the code in the `authorization` header
     InvalidToken,
     Unknown,
 }
 
+impl AuthError {
+    pub fn status(&self) -> Status {
+        match self {
+            AuthError::MissingToken | AuthError::InvalidToken => Status::Unauthorized,
+            AuthError::Unknown => Status::InternalServerError,
+        }
+    }
+
+    pub fn to_error(self) -> ApiError {
+        match self {
+            AuthError::MissingToken => ApiError::new(self.status())
+                .code("missing_token")
+                .message("The authorization header is missing"),
+            AuthError::InvalidToken => ApiError::new(self.status())
+                .code("invalid_token")
+                .message("No token has this code"),
+            AuthError::Unknown => {
+                ApiError::new(self.status()).message("The token could not be checked")
+            }
+        }
+    }
+
+    /// Rejects `request`, keeping the error for the catcher
+    fn reject(self, request: &Request<'_>) -> request::Outcome<Token, AuthError> {
+        request.local_cache(|| Some(self));
+        Outcome::Error((self.status(), self))
+    }
+}
+
 #[rocket::async_trait]
 impl<'r> FromRequest<'r> for Token {
     type Error = AuthError;
 
     async fn from_request(request: &'r Request<'_>) -> request::Outcome<Token, Self::Error> {
-        use crate::schema::tokens::dsl::*;
-
         /*
 
         return Outcome::Success(Token {
@@ -42,23 +103,43 @@ impl<'r> FromRequest<'r> for Token {
             parent: None,
         }); */
 
-        let token = match request.headers().get_one("authorization") {
-            Some(token) => token,
-            None => {
-                return request::Outcome::Error((Status::Unauthorized, Self::Error::InvalidToken))
-            }
+        let code = match request.headers().get_one("authorization") {
+            Some(code) => code,
+            None => return AuthError::MissingToken.reject(request),
         };
 
-        let conn = &mut establish_connection();
-
-        let result = tokens
-            .select(Token::as_select())
-            .filter(code.eq(token))
-            .get_result(conn);
-
-        match result {
-            Ok(claims) => Outcome::Success(claims),
-            Err(_) => Outcome::Error((Status::Unauthorized, Self::Error::Unknown)),
+        match find_token(request, code) {
+            Ok(token) => {
+                record_token(request, &token);
+                Outcome::Success(token)
+            }
+            Err(e) => e.reject(request),
         }
     }
 }
+
+/// The result of looking up the token of a request, kept in the request local
+/// cache
+struct FoundToken(Result<Token, AuthError>);
+
+/// The token with `token_code`, looked up once per request, as the rate
+/// limiter and the `Token` guard both need it
+pub fn find_token(request: &Request<'_>, token_code: &str) -> Result<Token, AuthError> {
+    use crate::schema::tokens::dsl::*;
+
+    request
+        .local_cache(|| {
+            let result = tokens
+                .select(Token::as_select())
+                .filter(code.eq(token_code))
+                .get_result(&mut establish_connection());
+
+            FoundToken(match result {
+                Ok(token) => Ok(token),
+                Err(diesel::result::Error::NotFound) => Err(AuthError::InvalidToken),
+                Err(_) => Err(AuthError::Unknown),
+            })
+        })
+        .0
+        .clone()
+}
@@ -0,0 +1,499 @@
+use chrono::Utc;
+use diesel::result::{DatabaseErrorKind, Error};
+use diesel::sql_types::Integer;
+use diesel::{
+    Connection, ExpressionMethods, OptionalExtension, QueryDsl, RunQueryDsl, SelectableHelper,
+};
+use regex::{Captures, Regex};
+use rocket::http::Status;
+use rocket::serde::json::Json;
+use rocket::{get, post, State};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use utoipa::ToSchema;
+
+use super::is_reserved;
+use crate::backend::Backend;
+use crate::db::{establish_connection, DbConnection};
+use crate::errors::ApiError;
+use crate::events::EventBus;
+use crate::jobs::JobKind;
+use crate::metta::validate_atom;
+use crate::model::{Pipeline, PipelineInsert, PipelineStep, PipelineStepInsert, Token};
+use crate::mork_api::{TransformDetails, TransformRequest};
+use crate::quotas;
+
+/// One transformation of a pipeline. Patterns and templates may contain
+/// `{{param}}` placeholders, which are filled in when the pipeline is run.
+#[derive(Serialize, Deserialize, Clone, ToSchema)]
+pub struct PipelineStepInput {
+    pub patterns: Vec<String>,
+    pub templates: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, ToSchema)]
+pub struct PipelineInput {
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    pub steps: Vec<PipelineStepInput>,
+}
+
+/// A version of a pipeline with its steps, in order
+#[derive(Serialize, Deserialize, Clone, ToSchema)]
+pub struct PipelineDetails {
+    #[serde(flatten)]
+    pub pipeline: Pipeline,
+    pub steps: Vec<PipelineStepInput>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Default, ToSchema)]
+pub struct PipelineRun {
+    #[serde(default)]
+    pub params: HashMap<String, String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Debug, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum StepStatus {
+    Succeeded,
+    Failed,
+    /// the step did not pass validation, so the pipeline was not run
+    Invalid,
+    Skipped,
+}
+
+#[derive(Serialize, Deserialize, Clone, ToSchema)]
+pub struct StepReport {
+    pub position: usize,
+    pub status: StepStatus,
+    /// the patterns and templates with the parameters filled in
+    pub patterns: Vec<String>,
+    pub templates: Vec<String>,
+    pub error: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Default, ToSchema)]
+pub struct PipelineReport {
+    pub name: String,
+    pub version: i32,
+    pub steps: Vec<StepReport>,
+    pub error: Option<String>,
+}
+
+fn is_valid_name(name: &str) -> bool {
+    let name_regex = Regex::new(r"^[a-zA-Z0-9]([a-zA-Z0-9]|\-|_)*$").unwrap();
+
+    name_regex.is_match(name)
+}
+
+/// Replaces the `{{param}}` placeholders of `text` with the values of `params`
+fn substitute(text: &str, params: &HashMap<String, String>) -> Result<String, String> {
+    let placeholder_regex = Regex::new(r"\{\{\s*([a-zA-Z_][a-zA-Z0-9_]*)\s*\}\}").unwrap();
+
+    let mut missing = None;
+    let result = placeholder_regex.replace_all(text, |captures: &Captures| {
+        let param = &captures[1];
+        match params.get(param) {
+            Some(value) => value.clone(),
+            None => {
+                missing.get_or_insert_with(|| param.to_string());
+                String::new()
+            }
+        }
+    });
+
+    match missing {
+        Some(param) => Err(format!("missing parameter `{param}`")),
+        None => Ok(result.into_owned()),
+    }
+}
+
+/// Fills in the parameters of a step and validates the resulting atoms
+fn prepare(position: usize, step: &PipelineStep, params: &HashMap<String, String>) -> StepReport {
+    let mut report = StepReport {
+        position,
+        status: StepStatus::Skipped,
+        patterns: vec![],
+        templates: vec![],
+        error: None,
+    };
+
+    let filled = |atoms: &[String]| -> Result<Vec<String>, String> {
+        atoms
+            .iter()
+            .map(|atom| {
+                let atom = substitute(atom, params)?;
+                validate_atom(&atom).map_err(|e| format!("{e} in `{atom}`"))?;
+                Ok(atom)
+            })
+            .collect()
+    };
+
+    match (filled(&step.patterns), filled(&step.templates)) {
+        (Ok(patterns), Ok(templates)) => {
+            report.patterns = patterns;
+            report.templates = templates;
+        }
+        (Err(e), _) | (_, Err(e)) => {
+            report.status = StepStatus::Invalid;
+            report.error = Some(e);
+        }
+    }
+
+    if report.error.is_none() && (step.patterns.is_empty() || step.templates.is_empty()) {
+        report.status = StepStatus::Invalid;
+        report.error = Some("a step needs at least one pattern and one template".to_string());
+    }
+
+    report
+}
+
+/// `token` itself if it is `$2`, or the token `$2` if it is derived from `token`
+const OWNED_TOKEN: &str = "WITH RECURSIVE rectree AS (
+    SELECT id
+        FROM tokens
+    WHERE id = $1
+    UNION ALL
+    SELECT t.id
+        FROM tokens t
+        JOIN rectree
+        ON t.parent = rectree.id
+    ) SELECT * FROM tokens WHERE id IN (SELECT id FROM rectree) AND id = $2;";
+
+/// The namespace of `token` and the ones enclosing it, whose pipelines the
+/// token can see and run
+fn visible_namespaces(token: &Token) -> Vec<String> {
+    Path::new(token.namespace.trim_matches('/'))
+        .ancestors()
+        .map(quotas::namespace)
+        .collect()
+}
+
+/// Whether `token` can add versions to `pipeline`: it must be the token that
+/// created the first version or one it is derived from. Once that token is
+/// deleted, the tokens of the namespace of the pipeline can.
+fn is_owner(conn: &mut DbConnection, token: &Token, pipeline: &Pipeline) -> Result<bool, Error> {
+    match pipeline.token_id {
+        Some(owner) => Ok(!diesel::sql_query(OWNED_TOKEN)
+            .bind::<Integer, _>(token.id)
+            .bind::<Integer, _>(owner)
+            .get_results::<Token>(conn)?
+            .is_empty()),
+        None => Ok(pipeline.namespace.starts_with(&token.namespace)),
+    }
+}
+
+/// Loads a version of a pipeline visible to `token`, or its latest version,
+/// with its steps. A name is resolved in the namespace of the token first,
+/// then in the nearest namespace enclosing it that has a pipeline by that
+/// name.
+pub fn load(
+    token: &Token,
+    pipeline_name: &str,
+    pipeline_version: Option<i32>,
+) -> Result<(Pipeline, Vec<PipelineStep>), Status> {
+    use crate::schema::pipeline_steps::dsl::*;
+    use crate::schema::pipelines::dsl::*;
+    let conn = &mut establish_connection();
+
+    // the enclosing namespaces are prefixes of each other, so the nearest
+    // one sorts last
+    let pipeline_namespace: String = pipelines
+        .select(namespace)
+        .filter(name.eq(pipeline_name))
+        .filter(namespace.eq_any(visible_namespaces(token)))
+        .order(namespace.desc())
+        .first(conn)
+        .map_err(|_| Status::NotFound)?;
+
+    let mut query = pipelines
+        .select(Pipeline::as_select())
+        .filter(namespace.eq(pipeline_namespace))
+        .filter(name.eq(pipeline_name))
+        .into_boxed();
+    if let Some(pipeline_version) = pipeline_version {
+        query = query.filter(version.eq(pipeline_version));
+    }
+
+    let pipeline: Pipeline = query
+        .order(version.desc())
+        .first(conn)
+        .map_err(|_| Status::NotFound)?;
+
+    let steps = pipeline_steps
+        .select(PipelineStep::as_select())
+        .filter(pipeline_id.eq(pipeline.id))
+        .order(position.asc())
+        .get_results(conn)
+        .map_err(|_| Status::InternalServerError)?;
+
+    Ok((pipeline, steps))
+}
+
+/// Lists the latest version of every pipeline visible to the token, as its
+/// name resolves for the token
+#[utoipa::path(
+    tag = "pipelines",
+    responses((status = 200, body = Vec<Pipeline>)),
+)]
+#[get("/pipelines")]
+pub fn get_all(token: Token) -> Result<Json<Vec<Pipeline>>, Status> {
+    use crate::schema::pipelines::dsl::*;
+
+    let mut results: Vec<Pipeline> = pipelines
+        .select(Pipeline::as_select())
+        .filter(namespace.eq_any(visible_namespaces(&token)))
+        .order((name.asc(), namespace.desc(), version.desc()))
+        .get_results(&mut establish_connection())
+        .map_err(|_| Status::InternalServerError)?;
+
+    results.dedup_by(|a, b| a.name == b.name);
+
+    Ok(Json(results))
+}
+
+/// Gets a version of a pipeline, the latest one if no version is given
+#[utoipa::path(
+    tag = "pipelines",
+    responses(
+        (status = 200, body = PipelineDetails),
+        (status = 404, description = "The pipeline or version does not exist, or is not visible to the token"),
+    ),
+)]
+#[get("/pipelines/<pipeline_name>?<version>")]
+pub fn get(
+    token: Token,
+    pipeline_name: &str,
+    version: Option<i32>,
+) -> Result<Json<PipelineDetails>, Status> {
+    let (pipeline, steps) = load(&token, pipeline_name, version)?;
+
+    Ok(Json(PipelineDetails {
+        pipeline,
+        steps: steps
+            .into_iter()
+            .map(|step| PipelineStepInput {
+                patterns: step.patterns,
+                templates: step.templates,
+            })
+            .collect(),
+    }))
+}
+
+/// Stores the steps as a new version of the pipeline named in the input.
+/// Earlier versions are kept, so runs can refer to them. Pipelines belong to
+/// the namespace of the token that creates them and their names are unique
+/// within it. Only the token that created a pipeline and the ones it is
+/// derived from can add versions to it.
+#[utoipa::path(
+    tag = "pipelines",
+    request_body = PipelineInput,
+    responses(
+        (status = 200, description = "The new version of the pipeline", body = Pipeline),
+        (status = 400, description = "The name is invalid or there are no steps"),
+        (status = 401, description = "The token can not write, or the pipeline of its namespace belongs to another token"),
+        (status = 409, description = "The version was created concurrently"),
+    ),
+)]
+#[post("/pipelines", data = "<input>")]
+pub fn create(token: Token, input: Json<PipelineInput>) -> Result<Json<Pipeline>, Status> {
+    use crate::schema::pipelines::dsl::*;
+
+    if !token.permission_write {
+        return Err(Status::Unauthorized);
+    }
+
+    if !is_valid_name(&input.name) || input.steps.is_empty() {
+        return Err(Status::BadRequest);
+    }
+
+    let conn = &mut establish_connection();
+
+    let result = conn.transaction(|conn| {
+        let first: Option<Pipeline> = pipelines
+            .select(Pipeline::as_select())
+            .filter(namespace.eq(&token.namespace))
+            .filter(name.eq(&input.name))
+            .order(version.asc())
+            .first(conn)
+            .optional()?;
+
+        if let Some(first) = &first {
+            if !is_owner(conn, &token, first)? {
+                return Err(Error::NotFound);
+            }
+        }
+
+        let latest: Option<i32> = pipelines
+            .select(diesel::dsl::max(version))
+            .filter(namespace.eq(&token.namespace))
+            .filter(name.eq(&input.name))
+            .get_result(conn)?;
+
+        let to_insert = PipelineInsert {
+            name: input.name.clone(),
+            version: latest.unwrap_or(0) + 1,
+            description: input.description.clone(),
+            token_id: Some(token.id),
+            creation_timestamp: Utc::now().naive_utc(),
+            namespace: token.namespace.clone(),
+        };
+
+        let pipeline: Pipeline = diesel::insert_into(pipelines)
+            .values(&to_insert)
+            .get_result(conn)?;
+
+        let steps: Vec<PipelineStepInsert> = input
+            .steps
+            .iter()
+            .enumerate()
+            .map(|(i, step)| PipelineStepInsert {
+                pipeline_id: pipeline.id,
+                position: i as i32,
+                patterns: step.patterns.clone(),
+                templates: step.templates.clone(),
+            })
+            .collect();
+
+        diesel::insert_into(crate::schema::pipeline_steps::table)
+            .values(&steps)
+            .execute(conn)?;
+
+        Ok(pipeline)
+    });
+
+    match result {
+        Ok(pipeline) => Ok(Json(pipeline)),
+        // another version was stored concurrently
+        Err(Error::DatabaseError(DatabaseErrorKind::UniqueViolation, _)) => Err(Status::Conflict),
+        // the pipeline belongs to another token
+        Err(Error::NotFound) => Err(Status::Unauthorized),
+        Err(_) => Err(Status::InternalServerError),
+    }
+}
+
+/// Runs the steps of a pipeline as transformations on the `path` space, in
+/// order, with the authority of `token`. All steps are validated before the
+/// first one runs, and the pipeline stops at the first step that fails. Steps
+/// that already ran are not undone.
+pub async fn execute(
+    token: &Token,
+    events: &EventBus,
+    backend: &Backend,
+    pipeline_name: &str,
+    version: Option<i32>,
+    path: PathBuf,
+    params: &HashMap<String, String>,
+) -> Result<PipelineReport, (Status, PipelineReport)> {
+    let token_namespace = token.namespace.strip_prefix("/").unwrap();
+    if !path.starts_with(token_namespace)
+        || is_reserved(&path)
+        || !token.permission_read
+        || !token.permission_write
+    {
+        return Err((Status::Unauthorized, PipelineReport::default()));
+    }
+
+    let (pipeline, steps) = load(token, pipeline_name, version)
+        .map_err(|status| (status, PipelineReport::default()))?;
+
+    let mut report = PipelineReport {
+        name: pipeline.name,
+        version: pipeline.version,
+        steps: steps
+            .iter()
+            .enumerate()
+            .map(|(i, step)| prepare(i, step, params))
+            .collect(),
+        error: None,
+    };
+
+    if let Some(invalid) = report
+        .steps
+        .iter()
+        .find(|step| step.status == StepStatus::Invalid)
+    {
+        report.error = Some(format!("Step {} is invalid", invalid.position));
+        return Err((Status::UnprocessableEntity, report));
+    }
+
+    let operation = events.operation(token, JobKind::Transform, &path);
+    operation.started();
+
+    for step in report.steps.iter_mut() {
+        operation.progress(&format!(
+            "step {} of pipeline {}",
+            step.position, pipeline_name
+        ));
+
+        let request = TransformRequest::new()
+            .namespace(path.clone())
+            .transform_input(
+                TransformDetails::new()
+                    .patterns(step.patterns.clone())
+                    .templates(step.templates.clone()),
+            );
+
+        match backend.dispatch(request).await {
+            Ok(_) => step.status = StepStatus::Succeeded,
+            Err(e) => {
+                step.status = StepStatus::Failed;
+                step.error = Some(e.to_string());
+                report.error = Some(format!("Step {} failed: {e}", step.position));
+                break;
+            }
+        }
+    }
+
+    if let Some(error) = &report.error {
+        operation.failed(error);
+        return Err((Status::InternalServerError, report));
+    }
+
+    operation.finished();
+    Ok(report)
+}
+
+/// Runs a pipeline on the `<path..>` space, see `execute`
+#[utoipa::path(
+    tag = "pipelines",
+    request_body = PipelineRun,
+    responses(
+        (status = 200, description = "Every step succeeded", body = PipelineReport),
+        (status = 401, description = "The token can not read and write the space, with the report in `details`"),
+        (status = 404, description = "The pipeline or version does not exist or is not visible to the token, with the report in `details`"),
+        (status = 422, description = "A step is invalid once its parameters are filled in, so none ran, with the report in `details`"),
+        (status = 500, description = "A step failed, the ones before it are not undone, with the report in `details`"),
+    ),
+)]
+#[post("/pipelines/<pipeline_name>/run/<path..>?<version>", data = "<run>")]
+pub async fn run(
+    token: Token,
+    events: &State<EventBus>,
+    backend: Backend,
+    pipeline_name: &str,
+    path: PathBuf,
+    version: Option<i32>,
+    run: Json<PipelineRun>,
+) -> Result<Json<PipelineReport>, ApiError> {
+    execute(
+        &token,
+        events,
+        &backend,
+        pipeline_name,
+        version,
+        path,
+        &run.params,
+    )
+    .await
+    .map(Json)
+    .map_err(|(status, report)| {
+        let error = match report.error.clone() {
+            Some(message) => ApiError::new(status).message(message),
+            None => ApiError::new(status),
+        };
+        error.details(report)
+    })
+}
@@ -0,0 +1,251 @@
+use chrono::Utc;
+use diesel::result::{DatabaseErrorKind, Error};
+use diesel::{ExpressionMethods, QueryDsl, RunQueryDsl, SelectableHelper};
+use rocket::http::Status;
+use rocket::serde::json::Json;
+use rocket::{delete, get, post};
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use utoipa::ToSchema;
+
+use super::is_valid_namespace;
+use crate::backend::Backend;
+use crate::db::establish_connection;
+use crate::model::{SavedQuery, SavedQueryInsert, Token};
+use crate::mork_api::{ExportFormat, ExportRequest};
+
+/// A pattern/template pair saved under `name` for the space at `namespace`
+#[derive(Serialize, Deserialize, Clone, ToSchema)]
+pub struct SavedQueryInput {
+    pub namespace: String,
+    pub name: String,
+    pub patterns: Vec<String>,
+    pub templates: Vec<String>,
+    #[serde(default)]
+    pub description: String,
+}
+
+fn map_error(e: Error) -> Status {
+    match e {
+        Error::DatabaseError(DatabaseErrorKind::UniqueViolation, _) => Status::Conflict,
+        Error::NotFound => Status::NotFound,
+        _ => Status::InternalServerError,
+    }
+}
+
+/// Loads a saved query of a space within the namespace of `token`
+fn find(token: &Token, query_id: i32) -> Result<SavedQuery, Status> {
+    use crate::schema::saved_queries::dsl::*;
+    let conn = &mut establish_connection();
+
+    let query: SavedQuery = saved_queries
+        .select(SavedQuery::as_select())
+        .filter(id.eq(query_id))
+        .get_result(conn)
+        .map_err(map_error)?;
+
+    if !query.namespace.starts_with(&token.namespace) {
+        return Err(Status::NotFound);
+    }
+
+    Ok(query)
+}
+
+fn validate(token: &Token, input: &SavedQueryInput) -> Result<(), Status> {
+    if !is_valid_namespace(&input.namespace) || input.name.trim().is_empty() {
+        return Err(Status::BadRequest);
+    }
+
+    if !input.namespace.starts_with(&token.namespace) || !token.permission_write {
+        return Err(Status::Unauthorized);
+    }
+
+    Ok(())
+}
+
+/// Lists the saved queries of the `<path..>` space and its subspaces
+#[utoipa::path(
+    tag = "queries",
+    responses(
+        (status = 200, body = Vec<SavedQuery>),
+        (status = 401, description = "The token can not read the space"),
+    ),
+)]
+#[get("/queries/<path..>")]
+pub fn get_all(token: Token, path: PathBuf) -> Result<Json<Vec<SavedQuery>>, Status> {
+    use crate::schema::saved_queries::dsl::*;
+
+    if !path.starts_with(token.namespace.strip_prefix("/").unwrap()) || !token.permission_read {
+        return Err(Status::Unauthorized);
+    }
+
+    let results = saved_queries
+        .select(SavedQuery::as_select())
+        .order((namespace.asc(), name.asc()))
+        .get_results(&mut establish_connection());
+
+    match results {
+        Ok(results) => Ok(Json(
+            results
+                .into_iter()
+                .filter(|query| PathBuf::from(query.namespace.trim_matches('/')).starts_with(&path))
+                .collect(),
+        )),
+        Err(_) => Err(Status::InternalServerError),
+    }
+}
+
+#[utoipa::path(
+    tag = "queries",
+    request_body = SavedQueryInput,
+    responses(
+        (status = 200, body = SavedQuery),
+        (status = 400, description = "The namespace is invalid or the name is empty"),
+        (status = 401, description = "The token can not write to the space"),
+        (status = 409, description = "The space already has a saved query with this name"),
+    ),
+)]
+#[post("/queries", data = "<input>")]
+pub fn create(token: Token, input: Json<SavedQueryInput>) -> Result<Json<SavedQuery>, Status> {
+    use crate::schema::saved_queries::dsl::*;
+
+    validate(&token, &input)?;
+
+    let to_insert = SavedQueryInsert {
+        namespace: input.namespace.clone(),
+        name: input.name.clone(),
+        patterns: input.patterns.clone(),
+        templates: input.templates.clone(),
+        description: input.description.clone(),
+        token_id: Some(token.id),
+        creation_timestamp: Utc::now().naive_utc(),
+    };
+
+    diesel::insert_into(saved_queries)
+        .values(&to_insert)
+        .get_result(&mut establish_connection())
+        .map(Json)
+        .map_err(map_error)
+}
+
+/// Replaces the name, patterns, templates and description of a saved query.
+/// The namespace of a saved query can not be changed.
+#[utoipa::path(
+    tag = "queries",
+    request_body = SavedQueryInput,
+    responses(
+        (status = 200, body = SavedQuery),
+        (status = 400, description = "The input is invalid or changes the namespace"),
+        (status = 401, description = "The token can not write to the space"),
+        (status = 404, description = "No visible saved query has this id"),
+        (status = 409, description = "The space already has a saved query with this name"),
+    ),
+)]
+#[post("/queries/<query_id>", data = "<input>")]
+pub fn update(
+    token: Token,
+    query_id: i32,
+    input: Json<SavedQueryInput>,
+) -> Result<Json<SavedQuery>, Status> {
+    use crate::schema::saved_queries::dsl::*;
+
+    validate(&token, &input)?;
+
+    let query = find(&token, query_id)?;
+    if query.namespace != input.namespace {
+        return Err(Status::BadRequest);
+    }
+
+    diesel::update(saved_queries.filter(id.eq(query.id)))
+        .set((
+            name.eq(&input.name),
+            patterns.eq(&input.patterns),
+            templates.eq(&input.templates),
+            description.eq(&input.description),
+        ))
+        .get_result(&mut establish_connection())
+        .map(Json)
+        .map_err(map_error)
+}
+
+#[utoipa::path(
+    tag = "queries",
+    responses(
+        (status = 200, description = "The saved query was deleted"),
+        (status = 401, description = "The token can not write"),
+        (status = 404, description = "No visible saved query has this id"),
+    ),
+)]
+#[delete("/queries/<query_id>")]
+pub fn delete(token: Token, query_id: i32) -> Status {
+    use crate::schema::saved_queries::dsl::*;
+
+    if !token.permission_write {
+        return Status::Unauthorized;
+    }
+
+    let query = match find(&token, query_id) {
+        Ok(query) => query,
+        Err(status) => return status,
+    };
+
+    match diesel::delete(saved_queries.filter(id.eq(query.id))).execute(&mut establish_connection())
+    {
+        Ok(_) => Status::Ok,
+        Err(_) => Status::NotFound,
+    }
+}
+
+/// Runs the saved query named by the last segment of `<path..>` as an export on
+/// the space named by the other segments, e.g. `/spaces/_views/space/my-view`.
+/// Only saved queries with a single pattern and template can be run as views.
+#[utoipa::path(
+    tag = "queries",
+    responses(
+        (status = 200, description = "The atoms exported by the saved query", body = String, content_type = "application/json"),
+        (status = 401, description = "The token can not read the space"),
+        (status = 404, description = "The space has no saved query with this name"),
+        (status = 422, description = "The saved query has several patterns or templates"),
+        (status = 503, description = "MORK is unavailable"),
+    ),
+)]
+#[get("/spaces/_views/<path..>")]
+pub async fn view(token: Token, backend: Backend, path: PathBuf) -> Result<Json<String>, Status> {
+    use crate::schema::saved_queries::dsl::*;
+
+    let view_name = path
+        .file_name()
+        .map(|view_name| view_name.to_string_lossy().to_string())
+        .ok_or(Status::NotFound)?;
+    let space = path.parent().map(PathBuf::from).unwrap_or_default();
+
+    if !space.starts_with(token.namespace.strip_prefix("/").unwrap()) || !token.permission_read {
+        return Err(Status::Unauthorized);
+    }
+
+    let space_namespace = if space.as_os_str().is_empty() {
+        "/".to_string()
+    } else {
+        format!("/{}/", space.to_string_lossy())
+    };
+
+    let query: SavedQuery = saved_queries
+        .select(SavedQuery::as_select())
+        .filter(namespace.eq(space_namespace))
+        .filter(name.eq(view_name))
+        .get_result(&mut establish_connection())
+        .map_err(map_error)?;
+
+    let (pattern, template) = match (query.patterns.as_slice(), query.templates.as_slice()) {
+        ([pattern], [template]) => (pattern.clone(), template.clone()),
+        _ => return Err(Status::UnprocessableEntity),
+    };
+
+    let request = ExportRequest::new()
+        .namespace(space)
+        .pattern(pattern)
+        .template(template)
+        .format(ExportFormat::Metta);
+
+    backend.dispatch(request).await.map(Json)
+}
@@ -0,0 +1,172 @@
+use chrono::Utc;
+use diesel::{ExpressionMethods, QueryDsl, RunQueryDsl};
+use rocket::http::Status;
+use rocket::serde::json::Json;
+use rocket::{delete, get, post};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use utoipa::ToSchema;
+
+use crate::backend::Backend;
+use crate::db::establish_connection;
+use crate::model::{QuotaInsert, Token};
+use crate::quotas::{effective_quota, namespace, EffectiveQuota, QuotaLimit};
+
+/// The limits set on a space. Limits left out are inherited from the
+/// enclosing spaces.
+#[derive(Serialize, Deserialize, Clone, Default, ToSchema)]
+pub struct QuotaInput {
+    pub max_atoms: Option<i64>,
+    pub max_upload_bytes: Option<i64>,
+    pub max_import_bytes: Option<i64>,
+}
+
+#[derive(Serialize, Deserialize, Clone, ToSchema)]
+pub struct QuotaReport {
+    pub namespace: String,
+    pub quota: EffectiveQuota,
+    /// the number of atoms in the space and its subspaces, which the atom
+    /// limits of the space apply to
+    pub atoms: usize,
+}
+
+/// Whether replacing the `current` limit by `new` loosens it
+fn raises(current: &Option<QuotaLimit>, new: &Option<QuotaLimit>) -> bool {
+    match (current, new) {
+        (Some(_), None) => true,
+        (Some(current), Some(new)) => new.limit > current.limit,
+        (None, _) => false,
+    }
+}
+
+/// Sets the limits of the `path` space. Only tokens with the share-share
+/// permission may loosen the limits that apply to the space.
+fn set_quota(token: &Token, path: &Path, input: QuotaInput) -> Result<Json<QuotaInput>, Status> {
+    use crate::schema::quotas::dsl;
+
+    if !path.starts_with(token.namespace.strip_prefix("/").unwrap()) || !token.permission_write {
+        return Err(Status::Unauthorized);
+    }
+
+    let limits = [
+        input.max_atoms,
+        input.max_upload_bytes,
+        input.max_import_bytes,
+    ];
+    if limits.iter().flatten().any(|limit| *limit < 0) {
+        return Err(Status::BadRequest);
+    }
+
+    let current = effective_quota(path)?;
+    let inherited = match path.parent() {
+        Some(parent) => effective_quota(parent)?,
+        None => EffectiveQuota::default(),
+    };
+
+    let own = |limit: Option<i64>| {
+        limit.map(|limit| QuotaLimit {
+            limit,
+            namespace: namespace(path),
+        })
+    };
+    let new = EffectiveQuota {
+        max_atoms: own(input.max_atoms).or(inherited.max_atoms),
+        max_upload_bytes: own(input.max_upload_bytes).or(inherited.max_upload_bytes),
+        max_import_bytes: own(input.max_import_bytes).or(inherited.max_import_bytes),
+    };
+
+    let raised = raises(&current.max_atoms, &new.max_atoms)
+        || raises(&current.max_upload_bytes, &new.max_upload_bytes)
+        || raises(&current.max_import_bytes, &new.max_import_bytes);
+    if raised && !token.permission_share_share {
+        return Err(Status::Unauthorized);
+    }
+
+    let to_insert = QuotaInsert {
+        namespace: namespace(path),
+        max_atoms: input.max_atoms,
+        max_upload_bytes: input.max_upload_bytes,
+        max_import_bytes: input.max_import_bytes,
+        token_id: Some(token.id),
+        update_timestamp: Utc::now().naive_utc(),
+    };
+
+    let conn = &mut establish_connection();
+    let result = if limits.iter().all(Option::is_none) {
+        diesel::delete(dsl::quotas.filter(dsl::namespace.eq(&to_insert.namespace))).execute(conn)
+    } else {
+        diesel::insert_into(dsl::quotas)
+            .values(&to_insert)
+            .on_conflict(dsl::namespace)
+            .do_update()
+            .set(&to_insert)
+            .execute(conn)
+    };
+
+    match result {
+        Ok(_) => Ok(Json(input)),
+        Err(_) => Err(Status::InternalServerError),
+    }
+}
+
+/// Reports the limits that apply to the `<path..>` space, where each limit is
+/// set, and the current number of atoms
+#[utoipa::path(
+    tag = "quotas",
+    responses(
+        (status = 200, body = QuotaReport),
+        (status = 401, description = "The token can not read the space"),
+        (status = 503, description = "MORK is unavailable"),
+    ),
+)]
+#[get("/quotas/<path..>")]
+pub async fn get(
+    token: Token,
+    backend: Backend,
+    path: PathBuf,
+) -> Result<Json<QuotaReport>, Status> {
+    if !path.starts_with(token.namespace.strip_prefix("/").unwrap()) || !token.permission_read {
+        return Err(Status::Unauthorized);
+    }
+
+    let quota = effective_quota(&path)?;
+    let atoms = backend.count(&path).await?;
+
+    Ok(Json(QuotaReport {
+        namespace: namespace(&path),
+        quota,
+        atoms,
+    }))
+}
+
+#[utoipa::path(
+    tag = "quotas",
+    request_body = QuotaInput,
+    responses(
+        (status = 200, description = "The limits set on the space", body = QuotaInput),
+        (status = 400, description = "A limit is negative"),
+        (status = 401, description = "The token can not write to the space, or loosens a limit without the share-share permission"),
+    ),
+)]
+#[post("/quotas/<path..>", data = "<input>")]
+pub fn set(
+    token: Token,
+    path: PathBuf,
+    input: Json<QuotaInput>,
+) -> Result<Json<QuotaInput>, Status> {
+    set_quota(&token, &path, input.into_inner())
+}
+
+/// Removes the limits set on the `<path..>` space, so it inherits those of
+/// the enclosing spaces
+#[utoipa::path(
+    tag = "quotas",
+    responses(
+        (status = 200, description = "The limits set on the space, now none", body = QuotaInput),
+        (status = 401, description = "The token can not write to the space, or loosens a limit without the share-share permission"),
+    ),
+)]
+#[delete("/quotas/<path..>")]
+pub fn delete(token: Token, path: PathBuf) -> Result<Json<QuotaInput>, Status> {
+    set_quota(&token, &path, QuotaInput::default())
+}
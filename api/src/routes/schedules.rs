@@ -0,0 +1,295 @@
+use chrono::Utc;
+use diesel::sql_types::Integer;
+use diesel::{ExpressionMethods, QueryDsl, RunQueryDsl, SelectableHelper};
+use rocket::http::Status;
+use rocket::serde::json::{to_string, Json};
+use rocket::{delete, get, post, State};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use utoipa::ToSchema;
+
+use super::{is_reserved, is_valid_namespace, pipelines};
+use crate::db::establish_connection;
+use crate::model::{SavedQuery, Schedule, ScheduleInsert, ScheduleRun, Token};
+use crate::scheduler::{parse_cron, RunTrigger, Scheduler};
+
+/// What a schedule runs on the space at `namespace`, either a saved query or
+/// a pipeline, and when, either on a `cron` expression or when the space at
+/// `watch` changes through an upload or import
+#[derive(Serialize, Deserialize, Clone, Default, ToSchema)]
+pub struct ScheduleInput {
+    pub name: String,
+    pub namespace: String,
+    pub saved_query_id: Option<i32>,
+    pub pipeline: Option<String>,
+    /// the pipeline version to run, the latest one when the schedule is
+    /// created if not given
+    pub version: Option<i32>,
+    #[serde(default)]
+    pub params: HashMap<String, String>,
+    pub cron: Option<String>,
+    pub watch: Option<String>,
+}
+
+/// schedules created by `token` or by any of the tokens derived from it
+const VISIBLE_SCHEDULES: &str = "WITH RECURSIVE rectree AS (
+    SELECT id
+        FROM tokens
+    WHERE id = $1
+    UNION ALL
+    SELECT t.id
+        FROM tokens t
+        JOIN rectree
+        ON t.parent = rectree.id
+    ) SELECT * FROM schedules WHERE token_id IN (SELECT id FROM rectree)";
+
+fn find_visible(token: &Token, schedule_id: i32) -> Result<Schedule, Status> {
+    let conn = &mut establish_connection();
+
+    diesel::sql_query(format!("{VISIBLE_SCHEDULES} AND id = $2;"))
+        .bind::<Integer, _>(token.id)
+        .bind::<Integer, _>(schedule_id)
+        .get_result::<Schedule>(conn)
+        .map_err(|_| Status::NotFound)
+}
+
+fn validate(token: &Token, input: &ScheduleInput) -> Result<(), Status> {
+    let namespaces = [Some(&input.namespace), input.watch.as_ref()];
+    for namespace in namespaces.into_iter().flatten() {
+        if !is_valid_namespace(namespace) {
+            return Err(Status::BadRequest);
+        }
+        if !namespace.starts_with(&token.namespace) {
+            return Err(Status::Unauthorized);
+        }
+    }
+
+    if is_reserved(&PathBuf::from(input.namespace.trim_matches('/')))
+        || !token.permission_read
+        || !token.permission_write
+    {
+        return Err(Status::Unauthorized);
+    }
+
+    if input.name.trim().is_empty()
+        || input.saved_query_id.is_some() == input.pipeline.is_some()
+        || input.cron.is_some() == input.watch.is_some()
+    {
+        return Err(Status::BadRequest);
+    }
+
+    if let Some(expression) = &input.cron {
+        parse_cron(expression).map_err(|_| Status::BadRequest)?;
+    }
+
+    if let Some(query_id) = input.saved_query_id {
+        let query: SavedQuery = crate::schema::saved_queries::table
+            .find(query_id)
+            .select(SavedQuery::as_select())
+            .get_result(&mut establish_connection())
+            .map_err(|_| Status::NotFound)?;
+
+        if !query.namespace.starts_with(&token.namespace) {
+            return Err(Status::NotFound);
+        }
+    }
+
+    Ok(())
+}
+
+/// The version of the pipeline of `input` the schedule runs: the given one, or
+/// the latest one when the schedule is created
+fn pipeline_version(token: &Token, input: &ScheduleInput) -> Result<Option<i32>, Status> {
+    match &input.pipeline {
+        Some(pipeline) => {
+            let (pipeline, _) = pipelines::load(token, pipeline, input.version)?;
+            Ok(Some(pipeline.version))
+        }
+        None => Ok(None),
+    }
+}
+
+/// Lists the schedules of `token` and the tokens derived from it
+#[utoipa::path(
+    tag = "schedules",
+    responses((status = 200, body = Vec<Schedule>)),
+)]
+#[get("/schedules")]
+pub fn get_all(token: Token) -> Result<Json<Vec<Schedule>>, Status> {
+    let conn = &mut establish_connection();
+
+    let results = diesel::sql_query(format!("{VISIBLE_SCHEDULES} ORDER BY id DESC;"))
+        .bind::<Integer, _>(token.id)
+        .get_results::<Schedule>(conn);
+
+    match results {
+        Ok(results) => Ok(Json(results)),
+        Err(_) => Err(Status::InternalServerError),
+    }
+}
+
+#[utoipa::path(
+    tag = "schedules",
+    responses(
+        (status = 200, body = Schedule),
+        (status = 404, description = "No visible schedule has this id"),
+    ),
+)]
+#[get("/schedules/<schedule_id>")]
+pub fn get(token: Token, schedule_id: i32) -> Result<Json<Schedule>, Status> {
+    find_visible(&token, schedule_id).map(Json)
+}
+
+/// Creates a schedule. Its runs have the authority of `token`, so they fail
+/// once the token no longer has access to the space.
+#[utoipa::path(
+    tag = "schedules",
+    request_body = ScheduleInput,
+    responses(
+        (status = 200, body = Schedule),
+        (status = 400, description = "The input is invalid, e.g. it has no trigger or an invalid cron expression"),
+        (status = 401, description = "The token can not read and write the spaces"),
+        (status = 404, description = "The saved query or pipeline does not exist, or is not visible to the token"),
+    ),
+)]
+#[post("/schedules", data = "<input>")]
+pub fn create(token: Token, input: Json<ScheduleInput>) -> Result<Json<Schedule>, Status> {
+    validate(&token, &input)?;
+    let version = pipeline_version(&token, &input)?;
+
+    let to_insert = ScheduleInsert {
+        name: input.name.clone(),
+        namespace: input.namespace.clone(),
+        token_id: token.id,
+        saved_query_id: input.saved_query_id,
+        pipeline_name: input.pipeline.clone(),
+        pipeline_version: version,
+        params: to_string(&input.params).map_err(|_| Status::BadRequest)?,
+        cron: input.cron.clone(),
+        watch_namespace: input.watch.clone(),
+        creation_timestamp: Utc::now().naive_utc(),
+    };
+
+    diesel::insert_into(crate::schema::schedules::table)
+        .values(&to_insert)
+        .get_result(&mut establish_connection())
+        .map(Json)
+        .map_err(|_| Status::InternalServerError)
+}
+
+fn set_enabled(token: &Token, schedule_id: i32, value: bool) -> Result<Json<Schedule>, Status> {
+    use crate::schema::schedules::dsl::*;
+
+    if !token.permission_write {
+        return Err(Status::Unauthorized);
+    }
+
+    let schedule = find_visible(token, schedule_id)?;
+
+    diesel::update(schedules.filter(id.eq(schedule.id)))
+        .set(enabled.eq(value))
+        .get_result(&mut establish_connection())
+        .map(Json)
+        .map_err(|_| Status::InternalServerError)
+}
+
+#[utoipa::path(
+    tag = "schedules",
+    responses(
+        (status = 200, description = "The enabled schedule", body = Schedule),
+        (status = 401, description = "The token can not write"),
+        (status = 404, description = "No visible schedule has this id"),
+    ),
+)]
+#[post("/schedules/<schedule_id>/enable")]
+pub fn enable(token: Token, schedule_id: i32) -> Result<Json<Schedule>, Status> {
+    set_enabled(&token, schedule_id, true)
+}
+
+#[utoipa::path(
+    tag = "schedules",
+    responses(
+        (status = 200, description = "The disabled schedule", body = Schedule),
+        (status = 401, description = "The token can not write"),
+        (status = 404, description = "No visible schedule has this id"),
+    ),
+)]
+#[post("/schedules/<schedule_id>/disable")]
+pub fn disable(token: Token, schedule_id: i32) -> Result<Json<Schedule>, Status> {
+    set_enabled(&token, schedule_id, false)
+}
+
+/// Runs a schedule right away, whether it is enabled or not
+#[utoipa::path(
+    tag = "schedules",
+    responses(
+        (status = 200, description = "The finished run, which may have failed", body = ScheduleRun),
+        (status = 401, description = "The token can not write"),
+        (status = 404, description = "No visible schedule has this id"),
+    ),
+)]
+#[post("/schedules/<schedule_id>/run")]
+pub async fn run(
+    token: Token,
+    scheduler: &State<Scheduler>,
+    schedule_id: i32,
+) -> Result<Json<ScheduleRun>, Status> {
+    if !token.permission_write {
+        return Err(Status::Unauthorized);
+    }
+
+    let schedule = find_visible(&token, schedule_id)?;
+
+    scheduler.run(&schedule, RunTrigger::Manual).await.map(Json)
+}
+
+/// Lists the runs of a schedule, the latest first
+#[utoipa::path(
+    tag = "schedules",
+    responses(
+        (status = 200, body = Vec<ScheduleRun>),
+        (status = 404, description = "No visible schedule has this id"),
+    ),
+)]
+#[get("/schedules/<schedule_id>/runs")]
+pub fn get_runs(token: Token, schedule_id: i32) -> Result<Json<Vec<ScheduleRun>>, Status> {
+    use crate::schema::schedule_runs::dsl;
+
+    let schedule = find_visible(&token, schedule_id)?;
+
+    dsl::schedule_runs
+        .select(ScheduleRun::as_select())
+        .filter(dsl::schedule_id.eq(schedule.id))
+        .order(dsl::id.desc())
+        .get_results(&mut establish_connection())
+        .map(Json)
+        .map_err(|_| Status::InternalServerError)
+}
+
+#[utoipa::path(
+    tag = "schedules",
+    responses(
+        (status = 200, description = "The schedule was deleted"),
+        (status = 401, description = "The token can not write"),
+        (status = 404, description = "No visible schedule has this id"),
+    ),
+)]
+#[delete("/schedules/<schedule_id>")]
+pub fn delete(token: Token, schedule_id: i32) -> Status {
+    if !token.permission_write {
+        return Status::Unauthorized;
+    }
+
+    let schedule = match find_visible(&token, schedule_id) {
+        Ok(schedule) => schedule,
+        Err(status) => return status,
+    };
+
+    match diesel::delete(crate::schema::schedules::table.find(schedule.id))
+        .execute(&mut establish_connection())
+    {
+        Ok(_) => Status::Ok,
+        Err(_) => Status::InternalServerError,
+    }
+}
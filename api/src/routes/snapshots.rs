@@ -0,0 +1,294 @@
+use chrono::Utc;
+use diesel::result::{DatabaseErrorKind, Error};
+use diesel::{ExpressionMethods, QueryDsl, RunQueryDsl, SelectableHelper};
+use rocket::http::Status;
+use rocket::serde::json::Json;
+use rocket::{delete, get, post, State};
+use std::path::PathBuf;
+
+use super::is_reserved;
+use super::spaces::{Mm2Input, WriteResponse};
+use crate::backend::Backend;
+use crate::db::establish_connection;
+use crate::events::EventBus;
+use crate::jobs::{JobKind, JobRunner};
+use crate::model::{Job, Snapshot, SnapshotInsert, Token};
+use crate::mork_api::{CopyRequest, ExportFormat, ExportRequest, SNAPSHOT_NAMESPACE};
+
+/// Where the atoms of `snapshot` are kept in MORK. The snapshot is stored with
+/// the full path of its space, so the data tags stay the same.
+pub fn snapshot_path(snapshot: &Snapshot) -> PathBuf {
+    PathBuf::from(SNAPSHOT_NAMESPACE)
+        .join(format!("s{}", snapshot.id))
+        .join(snapshot.namespace.trim_matches('/'))
+}
+
+fn space_path(snapshot: &Snapshot) -> PathBuf {
+    PathBuf::from(snapshot.namespace.trim_matches('/'))
+}
+
+/// Loads a snapshot of a space within the namespace of `token`
+pub fn find(token: &Token, snapshot_id: i32) -> Result<Snapshot, Status> {
+    use crate::schema::snapshots::dsl::*;
+    let conn = &mut establish_connection();
+
+    let snapshot: Snapshot = snapshots
+        .select(Snapshot::as_select())
+        .filter(id.eq(snapshot_id))
+        .get_result(conn)
+        .map_err(|_| Status::NotFound)?;
+
+    if !snapshot.namespace.starts_with(&token.namespace) {
+        return Err(Status::NotFound);
+    }
+
+    Ok(snapshot)
+}
+
+/// Copies the space of a new snapshot into it. The snapshot is deleted when
+/// the copy fails.
+async fn capture(backend: &Backend, snapshot: &Snapshot) -> Result<String, Status> {
+    let steps = CopyRequest::steps(space_path(snapshot), snapshot_path(snapshot), false);
+
+    let result = backend.dispatch_all(steps).await;
+    if result.is_err() {
+        let _ = diesel::delete(crate::schema::snapshots::table.find(snapshot.id))
+            .execute(&mut establish_connection());
+    }
+
+    result
+}
+
+/// Captures a named copy of the `<path..>` space and its subspaces
+#[utoipa::path(
+    tag = "snapshots",
+    responses(
+        (status = 200, body = Snapshot),
+        (status = 202, description = "The snapshot was queued as a job", body = Job),
+        (status = 400, description = "The space is the root space, or the name is empty"),
+        (status = 401, description = "The token can not read and write the space"),
+        (status = 409, description = "The space already has a snapshot with this name"),
+        (status = 503, description = "MORK is unavailable"),
+    ),
+)]
+#[post("/spaces/snapshot/<path..>?<name>&<background>")]
+pub async fn create(
+    token: Token,
+    runner: &State<JobRunner>,
+    events: &State<EventBus>,
+    backend: Backend,
+    path: PathBuf,
+    name: String,
+    background: Option<bool>,
+) -> Result<WriteResponse<Snapshot>, Status> {
+    let token_namespace = token.namespace.strip_prefix("/").unwrap();
+    if !path.starts_with(token_namespace)
+        || is_reserved(&path)
+        || !token.permission_read
+        || !token.permission_write
+    {
+        return Err(Status::Unauthorized);
+    }
+
+    // the root space contains the snapshots themselves
+    if path.as_os_str().is_empty() || name.trim().is_empty() {
+        return Err(Status::BadRequest);
+    }
+
+    let to_insert = SnapshotInsert {
+        name,
+        namespace: format!("/{}/", path.to_string_lossy()),
+        token_id: Some(token.id),
+        creation_timestamp: Utc::now().naive_utc(),
+    };
+
+    let result = diesel::insert_into(crate::schema::snapshots::table)
+        .values(&to_insert)
+        .get_result::<Snapshot>(&mut establish_connection());
+
+    let snapshot = match result {
+        Ok(snapshot) => snapshot,
+        Err(Error::DatabaseError(DatabaseErrorKind::UniqueViolation, _)) => {
+            return Err(Status::Conflict)
+        }
+        Err(_) => return Err(Status::InternalServerError),
+    };
+
+    let operation = events.operation(&token, JobKind::Snapshot, &path);
+
+    if background.unwrap_or(false) {
+        return runner
+            .spawn(&backend, operation, move |backend| async move {
+                capture(&backend, &snapshot).await.map(|text| (text, None))
+            })
+            .map(WriteResponse::queued);
+    }
+
+    operation.started();
+    match capture(&backend, &snapshot).await {
+        Ok(_) => {
+            operation.finished();
+            Ok(WriteResponse::Done(Json(snapshot)))
+        }
+        Err(e) => {
+            operation.failed(&e.to_string());
+            Err(e)
+        }
+    }
+}
+
+/// Lists the snapshots of the `<path..>` space and its subspaces
+#[utoipa::path(
+    tag = "snapshots",
+    responses(
+        (status = 200, body = Vec<Snapshot>),
+        (status = 401, description = "The token can not read the space"),
+    ),
+)]
+#[get("/snapshots/<path..>")]
+pub fn get_all(token: Token, path: PathBuf) -> Result<Json<Vec<Snapshot>>, Status> {
+    use crate::schema::snapshots::dsl::*;
+
+    if !path.starts_with(token.namespace.strip_prefix("/").unwrap()) || !token.permission_read {
+        return Err(Status::Unauthorized);
+    }
+
+    let results = snapshots
+        .select(Snapshot::as_select())
+        .order(id.desc())
+        .get_results(&mut establish_connection());
+
+    match results {
+        Ok(results) => Ok(Json(
+            results
+                .into_iter()
+                .filter(|snapshot| space_path(snapshot).starts_with(&path))
+                .collect(),
+        )),
+        Err(_) => Err(Status::InternalServerError),
+    }
+}
+
+/// Performs an export operation on a snapshot, as `/spaces/export` does on
+/// the live space
+#[utoipa::path(
+    tag = "snapshots",
+    request_body = Mm2Input,
+    responses(
+        (status = 200, description = "The matching atoms, rewritten with the template", body = String, content_type = "application/json"),
+        (status = 401, description = "The token can not read"),
+        (status = 404, description = "No visible snapshot has this id"),
+        (status = 503, description = "MORK is unavailable"),
+    ),
+)]
+#[post("/snapshots/<snapshot_id>/export", data = "<export_input>")]
+pub async fn export(
+    token: Token,
+    backend: Backend,
+    snapshot_id: i32,
+    export_input: Json<Mm2Input>,
+) -> Result<Json<String>, Status> {
+    if !token.permission_read {
+        return Err(Status::Unauthorized);
+    }
+
+    let snapshot = find(&token, snapshot_id)?;
+
+    let request = ExportRequest::new()
+        .namespace(snapshot_path(&snapshot))
+        .pattern(export_input.pattern.clone())
+        .template(export_input.template.clone())
+        .format(ExportFormat::Metta);
+
+    backend.dispatch(request).await.map(Json)
+}
+
+/// Replaces the live space and its subspaces with the content of a snapshot.
+/// The snapshot is copied next to the space first, and the space is only
+/// replaced once that copy succeeded.
+#[utoipa::path(
+    tag = "snapshots",
+    responses(
+        (status = 200, body = bool),
+        (status = 202, description = "The restore was queued as a job", body = Job),
+        (status = 401, description = "The token can not write"),
+        (status = 404, description = "No visible snapshot has this id"),
+        (status = 503, description = "MORK is unavailable"),
+    ),
+)]
+#[post("/snapshots/<snapshot_id>/restore?<background>")]
+pub async fn restore(
+    token: Token,
+    runner: &State<JobRunner>,
+    events: &State<EventBus>,
+    backend: Backend,
+    snapshot_id: i32,
+    background: Option<bool>,
+) -> Result<WriteResponse<bool>, Status> {
+    if !token.permission_read || !token.permission_write {
+        return Err(Status::Unauthorized);
+    }
+
+    let snapshot = find(&token, snapshot_id)?;
+
+    let operation = events.operation(&token, JobKind::Restore, &space_path(&snapshot));
+
+    if background.unwrap_or(false) {
+        return runner
+            .spawn(&backend, operation, move |backend| async move {
+                backend
+                    .replace(snapshot_path(&snapshot), space_path(&snapshot))
+                    .await
+                    .map(|_| (String::new(), None))
+            })
+            .map(WriteResponse::queued);
+    }
+
+    operation.started();
+    match backend
+        .replace(snapshot_path(&snapshot), space_path(&snapshot))
+        .await
+    {
+        Ok(_) => {
+            operation.finished();
+            Ok(WriteResponse::Done(Json(true)))
+        }
+        Err(e) => {
+            operation.failed(&e.to_string());
+            Err(e)
+        }
+    }
+}
+
+/// Deletes a snapshot and the atoms stored for it
+#[utoipa::path(
+    tag = "snapshots",
+    responses(
+        (status = 200, body = bool),
+        (status = 401, description = "The token can not write"),
+        (status = 404, description = "No visible snapshot has this id"),
+        (status = 503, description = "MORK is unavailable"),
+    ),
+)]
+#[delete("/snapshots/<snapshot_id>")]
+pub async fn delete(
+    token: Token,
+    backend: Backend,
+    snapshot_id: i32,
+) -> Result<Json<bool>, Status> {
+    if !token.permission_write {
+        return Err(Status::Unauthorized);
+    }
+
+    let snapshot = find(&token, snapshot_id)?;
+
+    backend
+        .dispatch(CopyRequest::remove(snapshot_path(&snapshot)))
+        .await?;
+
+    diesel::delete(crate::schema::snapshots::table.find(snapshot.id))
+        .execute(&mut establish_connection())
+        .map_err(|_| Status::InternalServerError)?;
+
+    Ok(Json(true))
+}
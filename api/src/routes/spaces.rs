@@ -1,68 +1,193 @@
-use rocket::http::Status;
-use rocket::serde::json::Json;
-use rocket::tokio::io::AsyncReadExt;
+use diesel::connection::{AnsiTransactionManager, TransactionManager};
+use rocket::http::{ContentType, Status};
+use rocket::serde::json::{to_string, Json};
 use serde::{Deserialize, Serialize};
 use url::Url;
-
-use rocket::response::status::Custom;
-use rocket::{get, post, Data};
+use utoipa::{IntoParams, ToSchema};
+
+use rocket::response::status::Accepted;
+use rocket::response::stream::{Event, EventStream, TextStream};
+use rocket::tokio::select;
+use rocket::tokio::sync::broadcast::error::RecvError;
+use rocket::{get, post, Data, Either, FromForm, Responder, Shutdown, State};
+use std::collections::BTreeSet;
 use std::path::PathBuf;
 
-use crate::model::Token;
+use super::{is_reserved, is_valid_namespace, snapshots, tokens};
+use crate::backend::Backend;
+use crate::config::Config;
+use crate::db::establish_connection;
+use crate::errors::ApiError;
+use crate::events::{EventBus, SpaceEvent};
+use crate::jobs::{JobKind, JobRunner};
+use crate::metta::{count_atoms, split_atoms};
+use crate::model::{Job, Token};
 use crate::mork_api::{
-    ClearRequest, ExploreRequest, ExportFormat, ExportRequest, ImportRequest, MorkApiClient,
-    ReadRequest, Request, TransformDetails, TransformRequest, UploadRequest,
+    ClearRequest, CopyRequest, ExploreRequest, ExportFormat, ExportRequest, ImportRequest,
+    Namespace, ReadRequest, TransformDetails, TransformRequest, UploadRequest,
 };
 
 /// The input for a transformation operation.
 /// see mm2 operations for more    // TODO: Add links
-#[derive(Default, Serialize, Deserialize, Clone)]
+#[derive(Default, Serialize, Deserialize, Clone, ToSchema)]
 pub struct Mm2InputMulti {
     pub patterns: Vec<String>,
     pub templates: Vec<String>,
 }
 
-#[derive(Serialize, Deserialize)]
+/// A pattern matched against the space at `namespace`
+#[derive(Serialize, Deserialize, Clone, ToSchema)]
+pub struct SourcePattern {
+    pub namespace: String,
+    pub pattern: String,
+}
+
+/// The input for a transformation that reads from one or more spaces and
+/// writes into the `target` space. Several sources can be joined by sharing
+/// variables between their patterns.
+#[derive(Serialize, Deserialize, Clone, ToSchema)]
+pub struct Mm2CrossInput {
+    pub patterns: Vec<SourcePattern>,
+    pub target: String,
+    pub templates: Vec<String>,
+}
+
+/// The input for copying or moving the `source` space, including all of its
+/// subspaces, to `target`
+#[derive(Serialize, Deserialize, Clone, ToSchema)]
+pub struct RelocateInput {
+    pub source: String,
+    pub target: String,
+    /// on move, re-point the tokens derived from the caller that are scoped to
+    /// the moved space
+    #[serde(default)]
+    pub retarget_tokens: bool,
+}
+
+/// What a copy or move did before it failed, in the `details` of its error
+#[derive(Serialize, Deserialize, Default, Clone, ToSchema)]
+pub struct RelocateReport {
+    /// the whole source was copied to the target
+    pub copied: bool,
+    /// the source was removed, while the tokens were still scoped to it
+    pub source_cleared: bool,
+}
+
+/// The most atoms returned by a dry run of clear
+pub const CLEAR_PREVIEW_LIMIT: usize = 100;
+
+#[derive(FromForm, Default, IntoParams)]
+pub struct ClearOptions {
+    pub background: Option<bool>,
+    pub dry_run: Option<bool>,
+    pub expected_count: Option<usize>,
+}
+
+/// The atoms a clear would remove
+#[derive(Serialize, Deserialize, Clone, Default, ToSchema)]
+pub struct ClearPreview {
+    pub atoms: Vec<String>,
+    pub total: usize,
+}
+
+/// One line of the diff of two spaces. Atoms are given without their
+/// namespace.
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug, ToSchema)]
+#[serde(tag = "kind", rename_all = "lowercase")]
+pub enum DiffLine {
+    /// an atom of the left side only
+    Removed { atom: String },
+    /// an atom of the right side only
+    Added { atom: String },
+    /// the last line, with the number of atoms of each kind
+    Summary {
+        removed: usize,
+        added: usize,
+        shared: usize,
+    },
+}
+
+#[derive(Serialize, Deserialize, ToSchema)]
 pub struct Mm2Input {
     pub pattern: String,
     pub template: String,
 }
 
-#[derive(Default, Serialize, Deserialize, Clone)]
+#[derive(Default, Serialize, Deserialize, Clone, ToSchema)]
 pub struct ExploreInput {
     pub pattern: String,
     pub token: String,
 }
 
+/// The response of a write operation. With `?background=true` the operation is
+/// queued as a job and `202 Accepted` is returned with the job instead.
+#[derive(Responder)]
+pub enum WriteResponse<T> {
+    Done(Json<T>),
+    Queued(Accepted<Json<Job>>),
+}
+
+impl<T> WriteResponse<T> {
+    pub fn queued(job: Job) -> Self {
+        WriteResponse::Queued(Accepted(Json(job)))
+    }
+}
+
 /// Fetches the `<path..>` space content. Use cautously as it will load everything.
 /// It is recommended to use the `/spaces/<path..>?op=explore` instead for large queries
+#[utoipa::path(
+    tag = "spaces",
+    responses(
+        (status = 200, description = "The atoms of the space", body = String, content_type = "application/json"),
+        (status = 401, description = "The token can not read the space"),
+        (status = 503, description = "MORK is unavailable"),
+    ),
+)]
 #[get("/spaces/<path..>", rank = 1)]
-pub async fn read(token: Token, path: PathBuf) -> Result<Json<String>, Status> {
+pub async fn read(token: Token, backend: Backend, path: PathBuf) -> Result<Json<String>, Status> {
     if !path.starts_with(token.namespace.strip_prefix("/").unwrap()) || !token.permission_read {
         return Err(Status::Unauthorized);
     }
 
-    let mork_api_client = MorkApiClient::new();
     let request = ReadRequest::new().namespace(path);
 
-    let response = mork_api_client.dispatch(request).await.map(Json);
+    let response = backend.dispatch(request).await.map(Json);
     response
 }
 
 /// Performs a transformation operation on the `<path..>` space
-#[post("/spaces/transform/<path..>", data = "<mm2>")]
+#[utoipa::path(
+    tag = "spaces",
+    request_body = Mm2InputMulti,
+    responses(
+        (status = 200, description = "The operation completed", body = bool),
+        (status = 202, description = "The operation was queued as a job", body = Job),
+        (status = 401, description = "The token can not write to the space"),
+        (status = 503, description = "MORK is unavailable"),
+    ),
+)]
+#[post("/spaces/transform/<path..>?<background>", data = "<mm2>")]
 pub async fn transform(
     token: Token,
+    runner: &State<JobRunner>,
+    events: &State<EventBus>,
+    backend: Backend,
     path: PathBuf,
+    background: Option<bool>,
     mm2: Json<Mm2InputMulti>,
-) -> Result<Json<bool>, Status> {
+) -> Result<WriteResponse<bool>, Status> {
     let token_namespace = token.namespace.strip_prefix("/").unwrap();
 
-    if !path.starts_with(token_namespace) || !token.permission_read || !token.permission_write {
+    if !path.starts_with(token_namespace)
+        || is_reserved(&path)
+        || !token.permission_read
+        || !token.permission_write
+    {
         return Err(Status::Unauthorized);
     }
 
-    let mork_api_client = MorkApiClient::new();
+    let operation = events.operation(&token, JobKind::Transform, &path);
+
     let request = TransformRequest::new()
         .namespace(path.to_path_buf())
         .transform_input(
@@ -71,61 +196,217 @@ pub async fn transform(
                 .templates(mm2.templates.clone()),
         );
 
-    // TODO: use server sent events instead
-    match mork_api_client.dispatch(request).await {
-        Ok(_) => Ok(Json(true)),
-        Err(e) => Err(e),
+    if background.unwrap_or(false) {
+        return runner
+            .submit(&backend, operation, request)
+            .map(WriteResponse::queued);
+    }
+
+    operation.started();
+    match backend.dispatch_counted(request).await {
+        Ok((_, count)) => {
+            operation.counted(count).finished();
+            Ok(WriteResponse::Done(Json(true)))
+        }
+        Err(e) => {
+            operation.failed(&e.to_string());
+            Err(e)
+        }
+    }
+}
+
+/// Performs a transformation reading from the spaces of the patterns and
+/// writing into the target space. Requires read access to every source and
+/// write access to the target.
+#[utoipa::path(
+    tag = "spaces",
+    request_body = Mm2CrossInput,
+    responses(
+        (status = 200, description = "The operation completed", body = bool),
+        (status = 202, description = "The operation was queued as a job", body = Job),
+        (status = 400, description = "A namespace is invalid"),
+        (status = 401, description = "The token can not write to the space"),
+        (status = 503, description = "MORK is unavailable"),
+    ),
+)]
+#[post("/spaces/cross-transform?<background>", data = "<mm2>")]
+pub async fn cross_transform(
+    token: Token,
+    runner: &State<JobRunner>,
+    events: &State<EventBus>,
+    backend: Backend,
+    background: Option<bool>,
+    mm2: Json<Mm2CrossInput>,
+) -> Result<WriteResponse<bool>, Status> {
+    let token_namespace = token.namespace.strip_prefix("/").unwrap();
+
+    let namespaces = mm2
+        .patterns
+        .iter()
+        .map(|source| &source.namespace)
+        .chain([&mm2.target]);
+
+    for namespace in namespaces {
+        if !is_valid_namespace(namespace) {
+            return Err(Status::BadRequest);
+        }
+        if !namespace
+            .strip_prefix("/")
+            .unwrap()
+            .starts_with(token_namespace)
+        {
+            return Err(Status::Unauthorized);
+        }
+    }
+
+    if !token.permission_read || !token.permission_write {
+        return Err(Status::Unauthorized);
+    }
+
+    let sources: Vec<PathBuf> = mm2
+        .patterns
+        .iter()
+        .map(|source| PathBuf::from(&source.namespace))
+        .collect();
+    let target = PathBuf::from(mm2.target.trim_matches('/'));
+
+    let operation = events.operation(&token, JobKind::Transform, &target);
+
+    let request = TransformRequest::new()
+        .sources(sources)
+        .target(target)
+        .transform_input(
+            TransformDetails::new()
+                .patterns(mm2.patterns.iter().map(|p| p.pattern.clone()).collect())
+                .templates(mm2.templates.clone()),
+        );
+
+    if background.unwrap_or(false) {
+        return runner
+            .submit(&backend, operation, request)
+            .map(WriteResponse::queued);
+    }
+
+    operation.started();
+    match backend.dispatch_counted(request).await {
+        Ok((_, count)) => {
+            operation.counted(count).finished();
+            Ok(WriteResponse::Done(Json(true)))
+        }
+        Err(e) => {
+            operation.failed(&e.to_string());
+            Err(e)
+        }
     }
 }
 
 /// Upload to the `<path..>` space. Exectes mm2 on the imported data.
-#[post("/spaces/upload/<path..>", data = "<data>")]
+#[utoipa::path(
+    tag = "spaces",
+    request_body(content = String, content_type = "text/plain", description = "The MeTTa atoms to add, up to the upload limit, 20 MiB by default"),
+    responses(
+        (status = 200, description = "The operation completed", body = String),
+        (status = 202, description = "The operation was queued as a job", body = Job),
+        (status = 400, description = "The data could not be read"),
+        (status = 401, description = "The token can not write to the space"),
+        (status = 413, description = "The upload exceeds the upload limit or a quota of the space"),
+        (status = 503, description = "MORK is unavailable"),
+    ),
+)]
+#[post("/spaces/upload/<path..>?<background>", data = "<data>")]
+#[allow(clippy::too_many_arguments)]
 pub async fn upload(
     token: Token,
+    runner: &State<JobRunner>,
+    events: &State<EventBus>,
+    config: &State<Config>,
+    backend: Backend,
     path: PathBuf,
+    background: Option<bool>,
     data: Data<'_>,
-) -> Result<Json<String>, Custom<String>> {
+) -> Result<WriteResponse<String>, ApiError> {
     let token_namespace = token.namespace.strip_prefix("/").unwrap();
-    if !path.starts_with(token_namespace) || !token.permission_write {
-        return Err(Custom(Status::Unauthorized, "Unauthorized".to_string()));
+    if !path.starts_with(token_namespace) || is_reserved(&path) || !token.permission_write {
+        return Err(Status::Unauthorized.into());
     }
 
-    let mut body = String::new();
-    if let Err(e) = data
-        .open(rocket::data::ByteUnit::Mebibyte(20))
-        .read_to_string(&mut body)
-        .await
-    {
-        eprintln!("Failed to read body: {e}");
-        return Err(Custom(
-            Status::BadRequest,
-            format!("Failed to read body: {e}"),
-        ));
-    }
+    let body = match data.open(config.upload_limit).into_string().await {
+        Ok(body) if body.is_complete() => body.into_inner(),
+        Ok(_) => {
+            return Err(ApiError::new(Status::PayloadTooLarge)
+                .message(format!("The upload exceeds {}", config.upload_limit)))
+        }
+        Err(e) => {
+            tracing::warn!("Failed to read body: {e}");
+            return Err(
+                ApiError::new(Status::BadRequest).message(format!("Failed to read body: {e}"))
+            );
+        }
+    };
 
     let pattern = "$x";
     let template = "$x";
 
-    let mork_api_client = MorkApiClient::new();
+    let operation = events
+        .operation(&token, JobKind::Upload, &path)
+        .count(count_atoms(&body));
+
     let request = UploadRequest::new()
         .namespace(path)
         .pattern(pattern.to_string())
         .template(template.to_string())
         .data(body);
 
-    match mork_api_client.dispatch(request).await {
-        Ok(text) => Ok(Json(text)),
-        Err(e) => Err(Custom(
-            Status::InternalServerError,
-            format!("Failed to contact backend: {e}"),
-        )),
+    if background.unwrap_or(false) {
+        return runner
+            .submit(&backend, operation, request)
+            .map(WriteResponse::queued)
+            .map_err(|e| ApiError::new(e).message("Failed to queue job"));
+    }
+
+    operation.started();
+    match backend.dispatch(request).await {
+        Ok(text) => {
+            operation.finished();
+            Ok(WriteResponse::Done(Json(text)))
+        }
+        Err(e) if e == Status::PayloadTooLarge => {
+            operation.failed(&e.to_string());
+            Err(ApiError::new(e).message("The upload exceeds the quota"))
+        }
+        Err(e) => {
+            operation.failed(&e.to_string());
+            Err(ApiError::new(e).message(format!("Failed to contact backend: {e}")))
+        }
     }
 }
 
 /// Imports data from `<uri>` into the `<path..>` space. Exectes mm2 on the imported data.
-#[post("/spaces/import/<path..>?<uri>")]
-pub async fn import(token: Token, path: PathBuf, uri: String) -> Result<Json<bool>, Status> {
-    if !path.starts_with(token.namespace.strip_prefix("/").unwrap()) || !token.permission_write {
+#[utoipa::path(
+    tag = "spaces",
+    responses(
+        (status = 200, description = "The operation completed", body = bool),
+        (status = 202, description = "The operation was queued as a job", body = Job),
+        (status = 400, description = "The URI is invalid"),
+        (status = 401, description = "The token can not write to the space"),
+        (status = 413, description = "The import exceeds a quota of the space"),
+        (status = 503, description = "MORK is unavailable"),
+    ),
+)]
+#[post("/spaces/import/<path..>?<uri>&<background>")]
+pub async fn import(
+    token: Token,
+    runner: &State<JobRunner>,
+    events: &State<EventBus>,
+    backend: Backend,
+    path: PathBuf,
+    uri: String,
+    background: Option<bool>,
+) -> Result<WriteResponse<bool>, Status> {
+    if !path.starts_with(token.namespace.strip_prefix("/").unwrap())
+        || is_reserved(&path)
+        || !token.permission_write
+    {
         return Err(Status::Unauthorized);
     }
 
@@ -134,20 +415,44 @@ pub async fn import(token: Token, path: PathBuf, uri: String) -> Result<Json<boo
         return Err(Status::BadRequest);
     }
 
-    let mork_api_client = MorkApiClient::new();
-    let request = ImportRequest::new().namespace(path).uri(uri);
+    let operation = events.operation(&token, JobKind::Import, &path);
+
+    let request = ImportRequest::new().namespace(path.clone()).uri(uri);
 
-    match mork_api_client.dispatch(request).await {
-        Ok(_) => Ok(Json(true)),
-        Err(e) => Err(e),
+    if background.unwrap_or(false) {
+        return runner
+            .submit(&backend, operation, request)
+            .map(WriteResponse::queued);
+    }
+
+    operation.started();
+    match backend.dispatch_counted(request).await {
+        Ok((_, count)) => {
+            operation.counted(count).finished();
+            Ok(WriteResponse::Done(Json(true)))
+        }
+        Err(e) => {
+            operation.failed(&e.to_string());
+            Err(e)
+        }
     }
 }
 
 /// Performs an explore operation on the `<path..>` space. Get the result that
 /// matches the `<pattern>` by incrementally traversing the resulting space.
+#[utoipa::path(
+    tag = "spaces",
+    request_body = ExploreInput,
+    responses(
+        (status = 200, description = "The atoms found and the token to continue exploring", body = String, content_type = "application/json"),
+        (status = 401, description = "The token can not read the space"),
+        (status = 503, description = "MORK is unavailable"),
+    ),
+)]
 #[post("/spaces/explore/<path..>", data = "<explore_input>")]
 pub async fn explore(
     token: Token,
+    backend: Backend,
     path: PathBuf,
     explore_input: Json<ExploreInput>,
 ) -> Result<Json<String>, Status> {
@@ -155,61 +460,500 @@ pub async fn explore(
         return Err(Status::Unauthorized);
     }
 
-    let mork_api_client = MorkApiClient::new();
     let request = ExploreRequest::new()
         .namespace(path)
         .pattern(explore_input.pattern.clone())
         .token(explore_input.token.clone());
 
-    println!("explore path: {:?}", request.path());
-
-    let response = mork_api_client.dispatch(request).await.map(Json);
-    println!("explore response: {response:?}");
-    response
+    backend.dispatch(request).await.map(Json)
 }
 
 /// Performs an export operation on the `<path..>` space. Get the result that
 /// matches the `<pattern>` by incrementally traversing the resulting space.
-#[post("/spaces/export/<path..>", data = "<export_input>")]
+///
+/// With `?format=<format>` the atoms are exported as JSON, CSV or raw text
+/// instead of MeTTa.
+#[utoipa::path(
+    tag = "spaces",
+    params(("format" = Option<ExportFormat>, Query, description = "The format of the export, `Metta` by default")),
+    request_body = Mm2Input,
+    responses(
+        (status = 200, description = "The matching atoms, rewritten with the template", body = String, content_type = "application/json"),
+        (status = 401, description = "The token can not read the space"),
+        (status = 503, description = "MORK is unavailable"),
+    ),
+)]
+#[post("/spaces/export/<path..>?<format>", data = "<export_input>")]
 pub async fn export(
     token: Token,
+    backend: Backend,
     path: PathBuf,
+    format: Option<ExportFormat>,
     export_input: Json<Mm2Input>,
 ) -> Result<Json<String>, Status> {
     if !path.starts_with(token.namespace.strip_prefix("/").unwrap()) || !token.permission_read {
         return Err(Status::Unauthorized);
     }
 
-    let mork_api_client = MorkApiClient::new();
     let request = ExportRequest::new()
         .namespace(path)
         .pattern(export_input.pattern.clone())
         .template(export_input.template.clone())
-        .format(ExportFormat::Metta);
+        .format(format.unwrap_or_default());
+
+    let data = backend.dispatch(request).await?;
+    tracing::debug!(bytes = data.len(), "exported from MORK");
 
-    println!("Dispatching export request to Mork: {}", request.path());
+    Ok(Json(data))
+}
 
-    match mork_api_client.dispatch(request).await {
-        Ok(data) => {
-            println!("Received export response from Mork: {data:?}");
-            Ok(Json(data))
+/// Removes the atoms matching `<expr>` from the `<path..>` space.
+///
+/// With `?dry_run=true` nothing is removed, and up to `CLEAR_PREVIEW_LIMIT` of
+/// the matching atoms are returned with their total count instead. With
+/// `?expected_count=<n>` the clear is aborted with `412 Precondition Failed`
+/// unless exactly `n` atoms match.
+#[utoipa::path(
+    tag = "spaces",
+    params(ClearOptions),
+    responses(
+        (status = 200, description = "`true`, or the preview of a dry run", body = ClearPreview),
+        (status = 202, description = "The operation was queued as a job", body = Job),
+        (status = 401, description = "The token can not write to the space, or can not read it for a dry run or an expected count"),
+        (status = 412, description = "The number of matching atoms is not the expected one"),
+        (status = 503, description = "MORK is unavailable"),
+    ),
+)]
+#[post("/spaces/clear/<path..>?<expr>&<options..>")]
+pub async fn clear(
+    token: Token,
+    runner: &State<JobRunner>,
+    events: &State<EventBus>,
+    backend: Backend,
+    path: PathBuf,
+    expr: String,
+    options: ClearOptions,
+) -> Result<Either<Json<ClearPreview>, WriteResponse<bool>>, Status> {
+    let token_namespace = token.namespace.strip_prefix("/").unwrap();
+    if !path.starts_with(token_namespace) || is_reserved(&path) || !token.permission_write {
+        return Err(Status::Unauthorized);
+    }
+    // previewing or counting the matching atoms reads them
+    let dry_run = options.dry_run.unwrap_or(false);
+    if (dry_run || options.expected_count.is_some()) && !token.permission_read {
+        return Err(Status::Unauthorized);
+    }
+
+    if dry_run {
+        let atoms = export_atoms(&backend, path, &expr).await?;
+
+        return Ok(Either::Left(Json(ClearPreview {
+            total: atoms.len(),
+            atoms: atoms.into_iter().take(CLEAR_PREVIEW_LIMIT).collect(),
+        })));
+    }
+
+    if let Some(expected_count) = options.expected_count {
+        if export_atoms(&backend, path.clone(), &expr).await?.len() != expected_count {
+            return Err(Status::PreconditionFailed);
+        }
+    }
+
+    let operation = events.operation(&token, JobKind::Clear, &path);
+
+    let request = ClearRequest::new().namespace(path).expr(expr);
+
+    if options.background.unwrap_or(false) {
+        return runner
+            .submit(&backend, operation, request)
+            .map(|job| Either::Right(WriteResponse::queued(job)));
+    }
+
+    operation.started();
+    match backend.dispatch_counted(request).await {
+        Ok((_, count)) => {
+            operation.counted(count).finished();
+            Ok(Either::Right(WriteResponse::Done(Json(true))))
+        }
+        Err(e) => {
+            operation.failed(&e.to_string());
+            Err(e)
         }
-        Err(e) => Err(e),
     }
 }
 
-#[post("/spaces/clear/<path..>?<expr>")]
-pub async fn clear(token: Token, path: PathBuf, expr: String) -> Result<Json<bool>, Status> {
+/// Streams started/progress/finished/failed events of the operations on the
+/// `<path..>` space and its subspaces as server-sent events
+#[utoipa::path(
+    tag = "spaces",
+    responses(
+        (status = 200, description = "A stream of server-sent events", body = SpaceEvent, content_type = "text/event-stream"),
+        (status = 401, description = "The token can not read the space"),
+    ),
+)]
+#[get("/spaces/_events/<path..>")]
+pub fn events(
+    token: Token,
+    events: &State<EventBus>,
+    path: PathBuf,
+    mut shutdown: Shutdown,
+) -> Result<EventStream![], Status> {
+    if !path.starts_with(token.namespace.strip_prefix("/").unwrap()) || !token.permission_read {
+        return Err(Status::Unauthorized);
+    }
+
+    let mut receiver = events.subscribe();
+
+    Ok(EventStream! {
+        loop {
+            let event = select! {
+                message = receiver.recv() => match message {
+                    Ok(event) => event,
+                    Err(RecvError::Closed) => break,
+                    Err(RecvError::Lagged(_)) => continue,
+                },
+                _ = &mut shutdown => break,
+            };
+
+            if event.is_within(&path) {
+                yield Event::json(&event).event(event.kind.as_str());
+            }
+        }
+    })
+}
+
+async fn relocate(
+    token: &Token,
+    events: &EventBus,
+    backend: &Backend,
+    input: &RelocateInput,
+    kind: JobKind,
+) -> Result<Json<bool>, ApiError> {
     let token_namespace = token.namespace.strip_prefix("/").unwrap();
-    if !path.starts_with(token_namespace) || !token.permission_write {
+
+    for namespace in [&input.source, &input.target] {
+        if !is_valid_namespace(namespace) {
+            return Err(Status::BadRequest.into());
+        }
+        if !namespace
+            .strip_prefix("/")
+            .unwrap()
+            .starts_with(token_namespace)
+        {
+            return Err(Status::Unauthorized.into());
+        }
+    }
+
+    if !token.permission_read || !token.permission_write {
+        return Err(Status::Unauthorized.into());
+    }
+
+    let source = PathBuf::from(input.source.trim_matches('/'));
+    let target = PathBuf::from(input.target.trim_matches('/'));
+
+    // a space can not be relocated into itself, and the root space contains everything
+    let source_namespace = Namespace::from(source.clone());
+    let target_namespace = Namespace::from(target.clone());
+    if source_namespace.is_root()
+        || source_namespace.is_within(&target_namespace)
+        || target_namespace.is_within(&source_namespace)
+    {
+        return Err(Status::BadRequest.into());
+    }
+
+    let operation = events.operation(token, kind, &target);
+    operation.started();
+
+    let mut report = RelocateReport::default();
+    let fail = |status: Status, message: String, report: RelocateReport| {
+        operation.failed(&message);
+        Err(ApiError::new(status).message(message).details(report))
+    };
+
+    let copy = CopyRequest::steps(source.clone(), target, false);
+    for (i, request) in copy.into_iter().enumerate() {
+        if let Err(e) = backend.dispatch(request).await {
+            let message = match i {
+                0 => format!("Failed to copy the source: {e}"),
+                _ => format!("Failed to copy the source, the target holds a partial copy: {e}"),
+            };
+            return fail(e, message, report);
+        }
+    }
+    report.copied = true;
+
+    if !matches!(kind, JobKind::Move) {
+        operation.finished();
+        return Ok(Json(true));
+    }
+
+    operation.progress("clearing source");
+
+    if !input.retarget_tokens {
+        if let Err(e) = backend.dispatch(CopyRequest::remove(source)).await {
+            return fail(
+                e,
+                format!("Copied, but failed to clear the source: {e}"),
+                report,
+            );
+        }
+        operation.finished();
+        return Ok(Json(true));
+    }
+
+    // the tokens are re-pointed in a transaction that only commits once the
+    // source is cleared, so they never point to a space that is gone or not
+    // yet there
+    let conn = &mut establish_connection();
+    let retargeted = AnsiTransactionManager::begin_transaction(&mut **conn)
+        .and_then(|_| tokens::retarget(conn, token, &input.source, &input.target));
+
+    if let Err(e) = retargeted {
+        tracing::error!("Failed to retarget tokens: {e}");
+        let _ = AnsiTransactionManager::rollback_transaction(&mut **conn);
+        return fail(
+            Status::InternalServerError,
+            "Copied, but failed to retarget the tokens, so the source was kept".to_string(),
+            report,
+        );
+    }
+
+    if let Err(e) = backend.dispatch(CopyRequest::remove(source)).await {
+        if let Err(e) = AnsiTransactionManager::rollback_transaction(&mut **conn) {
+            tracing::error!("Failed to roll back the retargeted tokens: {e}");
+        }
+        return fail(
+            e,
+            format!("Copied, but failed to clear the source, so the tokens were kept: {e}"),
+            report,
+        );
+    }
+    report.source_cleared = true;
+
+    if let Err(e) = AnsiTransactionManager::commit_transaction(&mut **conn) {
+        tracing::error!("Failed to commit the retargeted tokens: {e}");
+        return fail(
+            Status::InternalServerError,
+            "Moved, but failed to retarget the tokens".to_string(),
+            report,
+        );
+    }
+    operation.finished();
+    Ok(Json(true))
+}
+
+/// Copies the source space and all of its subspaces to the target space
+#[utoipa::path(
+    tag = "spaces",
+    request_body = RelocateInput,
+    responses(
+        (status = 200, body = bool),
+        (status = 400, description = "A namespace is invalid, or the spaces overlap"),
+        (status = 401, description = "The token can not read and write both spaces"),
+        (status = 503, description = "MORK is unavailable, with what was copied in `details`"),
+    ),
+)]
+#[post("/spaces/copy", data = "<input>")]
+pub async fn copy(
+    token: Token,
+    events: &State<EventBus>,
+    backend: Backend,
+    input: Json<RelocateInput>,
+) -> Result<Json<bool>, ApiError> {
+    relocate(&token, events, &backend, &input, JobKind::Copy).await
+}
+
+/// Moves the source space and all of its subspaces to the target space,
+/// optionally re-pointing the tokens scoped to the moved space
+#[utoipa::path(
+    tag = "spaces",
+    request_body = RelocateInput,
+    responses(
+        (status = 200, body = bool),
+        (status = 400, description = "A namespace is invalid, or the spaces overlap"),
+        (status = 401, description = "The token can not read and write both spaces"),
+        (status = 500, description = "The tokens could not be retargeted, with what was moved in `details`"),
+        (status = 503, description = "MORK is unavailable, with what was moved in `details`"),
+    ),
+)]
+#[post("/spaces/move", data = "<input>")]
+pub async fn move_space(
+    token: Token,
+    events: &State<EventBus>,
+    backend: Backend,
+    input: Json<RelocateInput>,
+) -> Result<Json<bool>, ApiError> {
+    relocate(&token, events, &backend, &input, JobKind::Move).await
+}
+
+/// Resolves one side of a diff, either a namespace such as `/space/` or a
+/// snapshot as `snapshot:<id>`, to where its atoms are stored
+fn diff_side(token: &Token, side: &str) -> Result<PathBuf, Status> {
+    if let Some(snapshot_id) = side.strip_prefix("snapshot:") {
+        let snapshot_id = snapshot_id.parse().map_err(|_| Status::BadRequest)?;
+        let snapshot = snapshots::find(token, snapshot_id)?;
+        return Ok(snapshots::snapshot_path(&snapshot));
+    }
+
+    if !is_valid_namespace(side) {
+        return Err(Status::BadRequest);
+    }
+    if !side.starts_with(&token.namespace) {
         return Err(Status::Unauthorized);
     }
 
-    let mork_api_client = MorkApiClient::new();
-    let request = ClearRequest::new().namespace(path).expr(expr);
+    Ok(PathBuf::from(side.trim_matches('/')))
+}
+
+/// Exports the atoms of the space at `path` that match `pattern`, with the
+/// namespace stripped
+async fn export_atoms(
+    backend: &Backend,
+    path: PathBuf,
+    pattern: &str,
+) -> Result<BTreeSet<String>, Status> {
+    let request = ExportRequest::new()
+        .namespace(path)
+        .pattern(pattern.to_string())
+        .template(pattern.to_string())
+        .format(ExportFormat::Metta);
+
+    let data = backend.dispatch(request).await?;
+
+    Ok(split_atoms(&data)
+        .into_iter()
+        .map(|atom| atom.to_string())
+        .collect())
+}
 
-    match mork_api_client.dispatch(request).await {
-        Ok(_) => Ok(Json(true)),
-        Err(e) => Err(e),
+/// How many lines of a diff are sent at once
+pub const DIFF_PAGE: usize = 1_000;
+
+/// Exports the atoms of one side of a diff, sorted and without duplicates
+async fn sorted_atoms(backend: &Backend, path: PathBuf) -> Result<Vec<String>, Status> {
+    let request = ExportRequest::new()
+        .namespace(path)
+        .pattern("$x".to_string())
+        .template("$x".to_string())
+        .format(ExportFormat::Metta);
+
+    let data = backend.dispatch(request).await?;
+    let mut atoms: Vec<String> = split_atoms(&data)
+        .into_iter()
+        .map(|atom| atom.to_string())
+        .collect();
+    atoms.sort_unstable();
+    atoms.dedup();
+
+    Ok(atoms)
+}
+
+/// Walks two sorted lists of atoms in step, yielding the atoms only one of
+/// them has and then the summary
+struct SortedDiff {
+    left: std::iter::Peekable<std::vec::IntoIter<String>>,
+    right: std::iter::Peekable<std::vec::IntoIter<String>>,
+    removed: usize,
+    added: usize,
+    shared: usize,
+    done: bool,
+}
+
+impl SortedDiff {
+    fn new(left: Vec<String>, right: Vec<String>) -> Self {
+        SortedDiff {
+            left: left.into_iter().peekable(),
+            right: right.into_iter().peekable(),
+            removed: 0,
+            added: 0,
+            shared: 0,
+            done: false,
+        }
     }
 }
+
+impl Iterator for SortedDiff {
+    type Item = DiffLine;
+
+    fn next(&mut self) -> Option<DiffLine> {
+        use std::cmp::Ordering;
+
+        loop {
+            let order = match (self.left.peek(), self.right.peek()) {
+                (Some(left), Some(right)) => left.cmp(right),
+                (Some(_), None) => Ordering::Less,
+                (None, Some(_)) => Ordering::Greater,
+                (None, None) if self.done => return None,
+                (None, None) => {
+                    self.done = true;
+                    return Some(DiffLine::Summary {
+                        removed: self.removed,
+                        added: self.added,
+                        shared: self.shared,
+                    });
+                }
+            };
+
+            match order {
+                Ordering::Less => {
+                    self.removed += 1;
+                    return self.left.next().map(|atom| DiffLine::Removed { atom });
+                }
+                Ordering::Greater => {
+                    self.added += 1;
+                    return self.right.next().map(|atom| DiffLine::Added { atom });
+                }
+                Ordering::Equal => {
+                    self.shared += 1;
+                    self.left.next();
+                    self.right.next();
+                }
+            }
+        }
+    }
+}
+
+/// Compares the atoms of two spaces or snapshots. Both sides are exported
+/// sorted and walked in step, and the atoms removed and added from left to
+/// right are streamed as newline-delimited JSON, `DIFF_PAGE` lines at a time.
+/// The last line is the summary.
+#[utoipa::path(
+    tag = "spaces",
+    responses(
+        (status = 200, description = "One `DiffLine` per line", body = DiffLine, content_type = "application/x-ndjson"),
+        (status = 400, description = "A side is neither a namespace nor a snapshot"),
+        (status = 401, description = "The token can not read one of the sides"),
+        (status = 503, description = "MORK is unavailable"),
+    ),
+)]
+#[get("/spaces/_diff?<left>&<right>")]
+pub async fn diff(
+    token: Token,
+    backend: Backend,
+    left: String,
+    right: String,
+) -> Result<(ContentType, TextStream![String]), Status> {
+    if !token.permission_read {
+        return Err(Status::Unauthorized);
+    }
+
+    let left = sorted_atoms(&backend, diff_side(&token, &left)?).await?;
+    let right = sorted_atoms(&backend, diff_side(&token, &right)?).await?;
+    let mut lines = SortedDiff::new(left, right);
+
+    let stream = TextStream! {
+        loop {
+            let page: String = lines
+                .by_ref()
+                .take(DIFF_PAGE)
+                .map(|line| format!("{}\n", to_string(&line).unwrap()))
+                .collect();
+            if page.is_empty() {
+                break;
+            }
+            yield page;
+        }
+    };
+
+    Ok((ContentType::new("application", "x-ndjson"), stream))
+}
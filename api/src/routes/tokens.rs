@@ -1,14 +1,19 @@
 use chrono::Utc;
-use diesel::sql_types::Integer;
-use diesel::{ExpressionMethods, QueryDsl, RunQueryDsl};
-use regex::Regex;
+use diesel::pg::PgConnection;
+use diesel::sql_types::{Integer, Text};
+use diesel::{ExpressionMethods, QueryDsl, QueryResult, RunQueryDsl};
 use rocket::http::Status;
 use rocket::serde::json::Json;
 use rocket::{delete, get, post};
 use uuid::Uuid;
 
+use super::is_valid_namespace;
 use crate::{db::establish_connection, model::Token, model::TokenInsert};
 
+#[utoipa::path(
+    tag = "tokens",
+    responses((status = 200, description = "The token and every token derived from it", body = Vec<Token>)),
+)]
 #[get("/tokens")]
 pub fn get_all(token: Token) -> Result<Json<Vec<Token>>, Status> {
     let conn = &mut establish_connection();
@@ -44,11 +49,64 @@ pub fn get_all(token: Token) -> Result<Json<Vec<Token>>, Status> {
     }
 }
 
+/// Re-points the tokens derived from `token` that are scoped to `source` or one
+/// of its subspaces to the same place under `target`. Runs on `conn`, so it can
+/// be part of a transaction.
+pub fn retarget(
+    conn: &mut PgConnection,
+    token: &Token,
+    source: &str,
+    target: &str,
+) -> QueryResult<usize> {
+    diesel::sql_query(
+        "WITH RECURSIVE rectree AS (
+        SELECT id
+            FROM tokens
+        WHERE parent = $1
+        UNION ALL
+        SELECT t.id
+            FROM tokens t
+            JOIN rectree
+            ON t.parent = rectree.id
+        ) UPDATE tokens
+            SET namespace = $3 || substring(namespace from char_length($2) + 1)
+        WHERE id IN (SELECT id FROM rectree)
+            AND left(namespace, char_length($2)) = $2;",
+    )
+    .bind::<Integer, _>(token.id)
+    .bind::<Text, _>(source)
+    .bind::<Text, _>(target)
+    .execute(conn)
+}
+
+#[utoipa::path(
+    tag = "tokens",
+    responses((status = 200, description = "The token of the request", body = Token)),
+)]
 #[get("/token")]
 pub fn get(token: Token) -> Result<Json<Token>, Status> {
     Ok(Json(token))
 }
 
+/// The limit of a new token. It can not exceed the limit of its parent, which
+/// it inherits when none is given.
+fn derived_limit(parent_limit: Option<i32>, requested: Option<i32>) -> Result<Option<i32>, Status> {
+    match (parent_limit, requested) {
+        (_, Some(limit)) if limit <= 0 => Err(Status::BadRequest),
+        (Some(parent_limit), Some(limit)) if limit > parent_limit => Err(Status::BadRequest),
+        (_, Some(limit)) => Ok(Some(limit)),
+        (parent_limit, None) => Ok(parent_limit),
+    }
+}
+
+#[utoipa::path(
+    tag = "tokens",
+    request_body(content = Token, description = "The token to derive. Its id, code, parent and timestamp are ignored."),
+    responses(
+        (status = 200, description = "The new token, with its code", body = Token),
+        (status = 400, description = "The token would have more rights than its parent"),
+    ),
+)]
 #[post("/tokens", data = "<new_token>")]
 pub fn create(token: Token, new_token: Json<Token>) -> Result<Json<Token>, Status> {
     use crate::schema::tokens::dsl::*;
@@ -58,30 +116,27 @@ pub fn create(token: Token, new_token: Json<Token>) -> Result<Json<Token>, Statu
     // permission"
 
     if !token.permission_share_write && new_token.permission_write {
-        println!("User tried to create write token without share_write permission");
+        tracing::info!("User tried to create write token without share_write permission");
         return Err(Status::BadRequest);
     }
 
     if !token.permission_share_read && new_token.permission_read {
-        println!("User tried to create read token without share_read permission");
+        tracing::info!("User tried to create read token without share_read permission");
         return Err(Status::BadRequest);
     }
 
     if !new_token.namespace.starts_with(&token.namespace) {
-        println!("User tried to create token for invalid namespace");
+        tracing::info!("User tried to create token for invalid namespace");
         return Err(Status::BadRequest);
     }
 
     if !new_token.namespace.ends_with("/") {
-        println!("User tried to create token for invalid namespace (missing trailing '/')");
+        tracing::info!("User tried to create token for invalid namespace (missing trailing '/')");
         return Err(Status::BadRequest);
     }
 
-    let namespace_regex =
-        Regex::new(r"^/(([a-zA-Z0-9])+([a-zA-Z0-9]|\-|_)*([a-zA-Z0-9])/)*$").unwrap();
-
-    if !namespace_regex.is_match(&new_token.namespace) {
-        println!("User tried to create token for invalid namespace (invalid characters)");
+    if !is_valid_namespace(&new_token.namespace) {
+        tracing::info!("User tried to create token for invalid namespace (invalid characters)");
         return Err(Status::BadRequest);
     }
 
@@ -98,6 +153,24 @@ pub fn create(token: Token, new_token: Json<Token>) -> Result<Json<Token>, Statu
         permission_share_write: new_token.permission_share_write,
         permission_share_share: false,
         parent: Some(token.id),
+        rate_limit_read: derived_limit(token.rate_limit_read, new_token.rate_limit_read)?,
+        rate_limit_write: derived_limit(token.rate_limit_write, new_token.rate_limit_write)?,
+        rate_limit_translate: derived_limit(
+            token.rate_limit_translate,
+            new_token.rate_limit_translate,
+        )?,
+        concurrency_limit_read: derived_limit(
+            token.concurrency_limit_read,
+            new_token.concurrency_limit_read,
+        )?,
+        concurrency_limit_write: derived_limit(
+            token.concurrency_limit_write,
+            new_token.concurrency_limit_write,
+        )?,
+        concurrency_limit_translate: derived_limit(
+            token.concurrency_limit_translate,
+            new_token.concurrency_limit_translate,
+        )?,
     };
 
     let result = diesel::insert_into(tokens)
@@ -110,6 +183,11 @@ pub fn create(token: Token, new_token: Json<Token>) -> Result<Json<Token>, Statu
     }
 }
 
+#[utoipa::path(
+    tag = "tokens",
+    request_body(content = Vec<i32>, description = "The ids of tokens derived from the token of the request"),
+    responses((status = 200, description = "The number of deleted tokens", body = i32)),
+)]
 #[delete("/tokens", data = "<token_ids>")]
 pub fn delete_batch(token: Token, token_ids: Json<Vec<i32>>) -> Result<Json<i32>, Status> {
     use crate::schema::tokens::dsl::*;
@@ -130,6 +208,13 @@ pub fn delete_batch(token: Token, token_ids: Json<Vec<i32>>) -> Result<Json<i32>
     }
 }
 
+#[utoipa::path(
+    tag = "tokens",
+    responses(
+        (status = 200, description = "The token with a new code", body = Token),
+        (status = 404, description = "No child token has this id"),
+    ),
+)]
 #[post("/tokens/<token_id>")]
 pub fn update(token: Token, token_id: i32) -> Result<Json<Token>, Status> {
     use crate::schema::tokens::dsl::*;
@@ -159,6 +244,10 @@ pub fn update(token: Token, token_id: i32) -> Result<Json<Token>, Status> {
 }
 
 /// delete child token
+#[utoipa::path(
+    tag = "tokens",
+    responses((status = 200, description = "The token was deleted")),
+)]
 #[delete("/tokens/<token_id>")]
 pub fn delete(token: Token, token_id: i32) -> Status {
     use crate::schema::tokens::dsl::*;
@@ -1,13 +1,18 @@
 use rocket::form::{FromForm, FromFormField};
 use rocket::fs::TempFile;
 use rocket::http::Status;
-use rocket::post;
 use rocket::serde::json::Json;
+use rocket::{post, State};
 use std::fs;
 use std::process::Command;
+use std::time::Instant;
+use utoipa::{IntoParams, ToSchema};
 use uuid::Uuid;
 
-#[derive(FromFormField, Copy, Clone)]
+use crate::config::Config;
+use crate::metrics::metrics;
+
+#[derive(FromFormField, Copy, Clone, ToSchema)]
 pub enum CSVParseDirection {
     Row = 1,
     Column = 2,
@@ -15,25 +20,25 @@ pub enum CSVParseDirection {
     CellLabeled = 4,
 }
 
-#[derive(FromForm, Clone)]
+#[derive(FromForm, Clone, IntoParams)]
 pub struct CSVParserParameters {
     pub direction: CSVParseDirection,
     pub delimiter: String,
 }
 
-#[derive(FromForm, Clone)]
+#[derive(FromForm, Clone, IntoParams)]
 pub struct NTParserParameters {
     // TODO: figure out how to deal with this normally empty struct
     pub dummy: String,
 }
 
-#[derive(FromForm, Clone)]
+#[derive(FromForm, Clone, IntoParams)]
 pub struct N3ParserParameters {
     // TODO: figure out how to deal with this normally empty struct
     pub dummy: String,
 }
 
-#[derive(FromForm, Clone)]
+#[derive(FromForm, Clone, IntoParams)]
 pub struct JSONLDParserParameters {
     // TODO: figure out how to deal with this normally empty struct
     pub dummy: String,
@@ -48,6 +53,7 @@ pub struct ParserParameters {
 }
 
 pub async fn create(
+    config: &Config,
     ext: &str,
     mut file: TempFile<'_>,
     parse_parameters: ParserParameters,
@@ -62,9 +68,11 @@ pub async fn create(
 
     match result {
         Ok(_) => (),
-        Err(err) => println!("{err}"),
+        Err(err) => tracing::error!("Failed to store the file to translate: {err}"),
     }
 
+    let started = Instant::now();
+
     let status = match parse_parameters {
         ParserParameters {
             csv_parameters: Some(parameters),
@@ -75,8 +83,8 @@ pub async fn create(
             let direction = (parameters.direction as u8).to_string();
             let delimiter = parameters.delimiter;
 
-            Command::new("./venv/bin/python")
-                .arg("translations/src/csv_to_metta_run.py")
+            Command::new(&config.python)
+                .arg(config.translation_script("csv_to_metta_run.py"))
                 .arg(&path)
                 .arg(&direction)
                 .arg(&delimiter)
@@ -87,8 +95,8 @@ pub async fn create(
             nt_parameters: Some(_parameters),
             jsonld_parameters: None,
             n3_parameters: None,
-        } => Command::new("./venv/bin/python")
-            .arg("translations/src/nt_to_metta_run.py")
+        } => Command::new(&config.python)
+            .arg(config.translation_script("nt_to_metta_run.py"))
             .arg(&path)
             .status(),
         ParserParameters {
@@ -96,8 +104,8 @@ pub async fn create(
             nt_parameters: None,
             jsonld_parameters: Some(_parameters),
             n3_parameters: None,
-        } => Command::new("./venv/bin/python")
-            .arg("translations/src/jsonld_to_metta_run.py")
+        } => Command::new(&config.python)
+            .arg(config.translation_script("jsonld_to_metta_run.py"))
             .arg(&path)
             .status(),
         ParserParameters {
@@ -105,16 +113,21 @@ pub async fn create(
             nt_parameters: None,
             jsonld_parameters: None,
             n3_parameters: Some(_parameters),
-        } => Command::new("./venv/bin/python")
-            .arg("translations/src/n3_to_metta_run.py")
+        } => Command::new(&config.python)
+            .arg(config.translation_script("n3_to_metta_run.py"))
             .arg(&path)
             .status(),
         _ => {
-            println!("Parse failed");
+            tracing::warn!("Parse failed");
             return Err(Status::InternalServerError);
         }
     };
 
+    let contents = fs::read_to_string(format!("{path}-output.metta"));
+
+    let succeeded = matches!(status, Ok(status) if status.success()) && contents.is_ok();
+    metrics().translation(ext, started.elapsed(), succeeded);
+
     // TODO: better error handling
     match status {
         Ok(_) => (),
@@ -123,20 +136,30 @@ pub async fn create(
         }
     };
 
-    let contents = fs::read_to_string(format!("{path}-output.metta"));
-
     match contents {
         Ok(contents) => Ok(contents),
         Err(_) => Err(Status::InternalServerError),
     }
 }
 
+#[utoipa::path(
+    tag = "translations",
+    params(CSVParserParameters),
+    request_body(content = String, content_type = "text/csv", description = "The CSV file"),
+    responses(
+        (status = 200, description = "The file translated to MeTTa", body = String, content_type = "application/json"),
+        (status = 500, description = "The translation failed"),
+    ),
+    security(()),
+)]
 #[post("/translations/csv?<parse_parameters..>", data = "<file>")]
 pub async fn create_from_csv(
+    config: &State<Config>,
     file: TempFile<'_>,
     parse_parameters: CSVParserParameters,
 ) -> Result<Json<String>, Status> {
     create(
+        config,
         "csv",
         file,
         ParserParameters {
@@ -150,12 +173,24 @@ pub async fn create_from_csv(
     .map(Json)
 }
 
+#[utoipa::path(
+    tag = "translations",
+    params(NTParserParameters),
+    request_body(content = String, content_type = "application/n-triples", description = "The N-Triples file"),
+    responses(
+        (status = 200, description = "The file translated to MeTTa", body = String, content_type = "application/json"),
+        (status = 500, description = "The translation failed"),
+    ),
+    security(()),
+)]
 #[post("/translations/nt?<parse_parameters..>", data = "<file>")]
 pub async fn create_from_nt(
+    config: &State<Config>,
     file: TempFile<'_>,
     parse_parameters: NTParserParameters,
 ) -> Result<Json<String>, Status> {
     create(
+        config,
         "nt",
         file,
         ParserParameters {
@@ -169,12 +204,24 @@ pub async fn create_from_nt(
     .map(Json)
 }
 
+#[utoipa::path(
+    tag = "translations",
+    params(JSONLDParserParameters),
+    request_body(content = String, content_type = "application/ld+json", description = "The JSON-LD file"),
+    responses(
+        (status = 200, description = "The file translated to MeTTa", body = String, content_type = "application/json"),
+        (status = 500, description = "The translation failed"),
+    ),
+    security(()),
+)]
 #[post("/translations/jsonld?<parse_parameters..>", data = "<file>")]
 pub async fn create_from_jsonld(
+    config: &State<Config>,
     file: TempFile<'_>,
     parse_parameters: JSONLDParserParameters,
 ) -> Result<Json<String>, Status> {
     create(
+        config,
         "jsonld",
         file,
         ParserParameters {
@@ -188,12 +235,24 @@ pub async fn create_from_jsonld(
     .map(Json)
 }
 
+#[utoipa::path(
+    tag = "translations",
+    params(N3ParserParameters),
+    request_body(content = String, content_type = "text/n3", description = "The Notation3 file"),
+    responses(
+        (status = 200, description = "The file translated to MeTTa", body = String, content_type = "application/json"),
+        (status = 500, description = "The translation failed"),
+    ),
+    security(()),
+)]
 #[post("/translations/n3?<parse_parameters..>", data = "<file>")]
 pub async fn create_from_n3(
+    config: &State<Config>,
     file: TempFile<'_>,
     parse_parameters: N3ParserParameters,
 ) -> Result<Json<String>, Status> {
     create(
+        config,
         "n3",
         file,
         ParserParameters {
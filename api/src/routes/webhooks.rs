@@ -0,0 +1,159 @@
+use chrono::Utc;
+use diesel::sql_types::Integer;
+use diesel::{ExpressionMethods, QueryDsl, RunQueryDsl, SelectableHelper};
+use rocket::http::Status;
+use rocket::serde::json::Json;
+use rocket::{delete, get, post, State};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use super::is_valid_namespace;
+use crate::config::Config;
+use crate::db::establish_connection;
+use crate::model::{Token, Webhook, WebhookDelivery, WebhookInsert};
+use crate::webhooks::is_allowed_url;
+
+/// A URL notified of the changes to the space at `namespace` and its subspaces
+#[derive(Serialize, Deserialize, Clone, ToSchema)]
+pub struct WebhookInput {
+    pub namespace: String,
+    pub url: String,
+}
+
+/// webhooks registered by `token` or by any of the tokens derived from it
+const VISIBLE_WEBHOOKS: &str = "WITH RECURSIVE rectree AS (
+    SELECT id
+        FROM tokens
+    WHERE id = $1
+    UNION ALL
+    SELECT t.id
+        FROM tokens t
+        JOIN rectree
+        ON t.parent = rectree.id
+    ) SELECT * FROM webhooks WHERE token_id IN (SELECT id FROM rectree)";
+
+fn find_visible(token: &Token, webhook_id: i32) -> Result<Webhook, Status> {
+    let conn = &mut establish_connection();
+
+    diesel::sql_query(format!("{VISIBLE_WEBHOOKS} AND id = $2;"))
+        .bind::<Integer, _>(token.id)
+        .bind::<Integer, _>(webhook_id)
+        .get_result::<Webhook>(conn)
+        .map_err(|_| Status::NotFound)
+}
+
+#[utoipa::path(
+    tag = "webhooks",
+    responses((status = 200, body = Vec<Webhook>)),
+)]
+#[get("/webhooks")]
+pub fn get_all(token: Token) -> Result<Json<Vec<Webhook>>, Status> {
+    let conn = &mut establish_connection();
+
+    let results = diesel::sql_query(format!("{VISIBLE_WEBHOOKS} ORDER BY id DESC;"))
+        .bind::<Integer, _>(token.id)
+        .get_results::<Webhook>(conn);
+
+    match results {
+        Ok(results) => Ok(Json(results)),
+        Err(_) => Err(Status::InternalServerError),
+    }
+}
+
+/// Registers a webhook. After every successful upload, import, transform or
+/// clear in the space, the URL receives a POST signed with `METTA_KG_SECRET`.
+/// The URL must resolve to public addresses, unless its host is one of the
+/// allowed webhook hosts.
+#[utoipa::path(
+    tag = "webhooks",
+    request_body = WebhookInput,
+    responses(
+        (status = 200, body = Webhook),
+        (status = 400, description = "The namespace or URL is invalid, or the URL is not public"),
+        (status = 401, description = "The token can not read the space"),
+    ),
+)]
+#[post("/webhooks", data = "<input>")]
+pub async fn create(
+    token: Token,
+    config: &State<Config>,
+    input: Json<WebhookInput>,
+) -> Result<Json<Webhook>, Status> {
+    if !is_valid_namespace(&input.namespace) {
+        return Err(Status::BadRequest);
+    }
+
+    if !input.namespace.starts_with(&token.namespace)
+        || !token.permission_read
+        || !token.permission_write
+    {
+        return Err(Status::Unauthorized);
+    }
+
+    if !is_allowed_url(&input.url, &config.webhook_allowed_hosts).await {
+        return Err(Status::BadRequest);
+    }
+
+    let to_insert = WebhookInsert {
+        namespace: input.namespace.clone(),
+        url: input.url.clone(),
+        token_id: token.id,
+        creation_timestamp: Utc::now().naive_utc(),
+    };
+
+    diesel::insert_into(crate::schema::webhooks::table)
+        .values(&to_insert)
+        .get_result(&mut establish_connection())
+        .map(Json)
+        .map_err(|_| Status::InternalServerError)
+}
+
+/// Lists the delivery log of a webhook, the latest first
+#[utoipa::path(
+    tag = "webhooks",
+    responses(
+        (status = 200, body = Vec<WebhookDelivery>),
+        (status = 404, description = "No visible webhook has this id"),
+    ),
+)]
+#[get("/webhooks/<webhook_id>/deliveries")]
+pub fn get_deliveries(token: Token, webhook_id: i32) -> Result<Json<Vec<WebhookDelivery>>, Status> {
+    use crate::schema::webhook_deliveries::dsl;
+
+    let webhook = find_visible(&token, webhook_id)?;
+
+    dsl::webhook_deliveries
+        .select(WebhookDelivery::as_select())
+        .filter(dsl::webhook_id.eq(webhook.id))
+        .order(dsl::id.desc())
+        .get_results(&mut establish_connection())
+        .map(Json)
+        .map_err(|_| Status::InternalServerError)
+}
+
+#[utoipa::path(
+    tag = "webhooks",
+    responses(
+        (status = 200, description = "The webhook was deleted"),
+        (status = 401, description = "The token can not write"),
+        (status = 404, description = "No visible webhook has this id"),
+    ),
+)]
+#[delete("/webhooks/<webhook_id>")]
+pub fn delete(token: Token, webhook_id: i32) -> Status {
+    if !token.permission_write {
+        return Status::Unauthorized;
+    }
+
+    let webhook = match find_visible(&token, webhook_id) {
+        Ok(webhook) => webhook,
+        Err(status) => return status,
+    };
+
+    match diesel::delete(crate::schema::webhooks::table.find(webhook.id))
+        .execute(&mut establish_connection())
+    {
+        Ok(_) => Status::Ok,
+        Err(_) => Status::InternalServerError,
+    }
+}
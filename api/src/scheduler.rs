@@ -0,0 +1,304 @@
+use chrono::{DateTime, Utc};
+use cron::Schedule as CronSchedule;
+use diesel::{ExpressionMethods, QueryDsl, RunQueryDsl, SelectableHelper};
+use rocket::http::Status;
+use rocket::serde::json::from_str;
+use rocket::tokio::sync::broadcast::error::RecvError;
+use rocket::tokio::{self, select, time};
+use rocket::Shutdown;
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::time::Duration;
+use uuid::Uuid;
+
+use crate::backend::Backend;
+use crate::db::establish_connection;
+use crate::events::{EventBus, SpaceEvent, SpaceEventKind};
+use crate::jobs::{JobKind, JobStatus};
+use crate::model::{SavedQuery, Schedule, ScheduleRun, ScheduleRunInsert, Token};
+use crate::mork_api::{TransformDetails, TransformRequest};
+use crate::routes::{is_reserved, pipelines};
+use crate::telemetry::RequestId;
+
+/// How often the cron expressions of the schedules are checked
+const TICK: Duration = Duration::from_secs(10);
+
+#[derive(Clone, Copy)]
+pub enum RunTrigger {
+    Cron,
+    Watch,
+    Manual,
+}
+
+impl RunTrigger {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RunTrigger::Cron => "cron",
+            RunTrigger::Watch => "watch",
+            RunTrigger::Manual => "manual",
+        }
+    }
+}
+
+/// Parses a cron expression with seconds, e.g. `0 */15 * * * *`
+pub fn parse_cron(expression: &str) -> Result<CronSchedule, String> {
+    CronSchedule::from_str(expression).map_err(|e| e.to_string())
+}
+
+/// Runs the saved queries and pipelines of the schedules, when their cron
+/// expression fires or when a watched space changes. Managed as Rocket state
+/// and started on liftoff.
+#[derive(Clone)]
+pub struct Scheduler {
+    events: EventBus,
+    backend: Backend,
+}
+
+impl Scheduler {
+    pub fn new(events: EventBus, backend: Backend) -> Self {
+        Scheduler { events, backend }
+    }
+
+    /// Spawns the tasks watching the clock and the space events
+    pub fn start(&self, shutdown: Shutdown) {
+        let scheduler = self.clone();
+        let cron_shutdown = shutdown.clone();
+        tokio::spawn(async move { scheduler.watch_clock(cron_shutdown).await });
+
+        let scheduler = self.clone();
+        tokio::spawn(async move { scheduler.watch_events(shutdown).await });
+    }
+
+    async fn watch_clock(&self, mut shutdown: Shutdown) {
+        let mut last_check = Utc::now();
+
+        loop {
+            select! {
+                _ = time::sleep(TICK) => (),
+                _ = &mut shutdown => break,
+            }
+
+            let now = Utc::now();
+            for schedule in due_schedules(last_check, now) {
+                self.spawn(schedule, RunTrigger::Cron);
+            }
+            last_check = now;
+        }
+    }
+
+    async fn watch_events(&self, mut shutdown: Shutdown) {
+        let mut receiver = self.events.subscribe();
+
+        loop {
+            let event = select! {
+                event = receiver.recv() => event,
+                _ = &mut shutdown => break,
+            };
+
+            match event {
+                Ok(event) => {
+                    for schedule in watching_schedules(&event) {
+                        self.spawn(schedule, RunTrigger::Watch);
+                    }
+                }
+                Err(RecvError::Lagged(_)) => continue,
+                Err(RecvError::Closed) => break,
+            }
+        }
+    }
+
+    fn spawn(&self, schedule: Schedule, trigger: RunTrigger) {
+        let scheduler = self.clone();
+        tokio::spawn(async move {
+            if let Err(e) = scheduler.run(&schedule, trigger).await {
+                tracing::error!(schedule_id = schedule.id, "Failed to run schedule: {e}");
+            }
+        });
+    }
+
+    /// Runs a schedule with the authority of the token that created it, and
+    /// records the run
+    pub async fn run(
+        &self,
+        schedule: &Schedule,
+        run_trigger: RunTrigger,
+    ) -> Result<ScheduleRun, Status> {
+        use crate::schema::schedule_runs::dsl::*;
+        use crate::schema::schedules::dsl as schedules;
+
+        let to_insert = ScheduleRunInsert {
+            schedule_id: schedule.id,
+            trigger: run_trigger.as_str().to_string(),
+            status: JobStatus::Running.as_str().to_string(),
+            start_timestamp: Utc::now().naive_utc(),
+        };
+
+        let run: ScheduleRun = diesel::insert_into(schedule_runs)
+            .values(&to_insert)
+            .get_result(&mut establish_connection())
+            .map_err(|_| Status::InternalServerError)?;
+
+        let result = self.execute(schedule).await;
+        let (new_status, new_error) = match result {
+            Ok(()) => (JobStatus::Succeeded, None),
+            Err(e) => (JobStatus::Failed, Some(e)),
+        };
+
+        let conn = &mut establish_connection();
+        let finished_at = Utc::now().naive_utc();
+
+        diesel::update(schedules::schedules.filter(schedules::id.eq(schedule.id)))
+            .set((
+                schedules::last_run_timestamp.eq(finished_at),
+                schedules::last_error.eq(&new_error),
+            ))
+            .execute(conn)
+            .map_err(|_| Status::InternalServerError)?;
+
+        diesel::update(schedule_runs.filter(id.eq(run.id)))
+            .set((
+                status.eq(new_status.as_str()),
+                finish_timestamp.eq(finished_at),
+                error.eq(new_error),
+            ))
+            .get_result(conn)
+            .map_err(|_| Status::InternalServerError)
+    }
+
+    async fn execute(&self, schedule: &Schedule) -> Result<(), String> {
+        // every run is traced as a request of its own
+        let backend = self
+            .backend
+            .with_request_id(RequestId(Uuid::new_v4().to_string()));
+
+        let token: Token = crate::schema::tokens::table
+            .find(schedule.token_id)
+            .select(Token::as_select())
+            .get_result(&mut establish_connection())
+            .map_err(|_| "the token of the schedule no longer exists".to_string())?;
+
+        let path = PathBuf::from(schedule.namespace.trim_matches('/'));
+
+        if let Some(pipeline_name) = &schedule.pipeline_name {
+            let params: HashMap<String, String> =
+                from_str(&schedule.params).map_err(|e| e.to_string())?;
+
+            return pipelines::execute(
+                &token,
+                &self.events,
+                &backend,
+                pipeline_name,
+                schedule.pipeline_version,
+                path,
+                &params,
+            )
+            .await
+            .map(|_| ())
+            .map_err(|(status, report)| report.error.unwrap_or(status.to_string()));
+        }
+
+        let query_id = schedule
+            .saved_query_id
+            .ok_or("the schedule has nothing to run")?;
+        let query: SavedQuery = crate::schema::saved_queries::table
+            .find(query_id)
+            .select(SavedQuery::as_select())
+            .get_result(&mut establish_connection())
+            .map_err(|_| "the saved query of the schedule no longer exists".to_string())?;
+
+        // the token may have changed since the schedule was created
+        let token_namespace = token.namespace.strip_prefix("/").unwrap();
+        if !path.starts_with(token_namespace)
+            || is_reserved(&path)
+            || !token.permission_read
+            || !token.permission_write
+        {
+            return Err(Status::Unauthorized.to_string());
+        }
+
+        let operation = self.events.operation(&token, JobKind::Transform, &path);
+        operation.started();
+
+        let request = TransformRequest::new().namespace(path).transform_input(
+            TransformDetails::new()
+                .patterns(query.patterns)
+                .templates(query.templates),
+        );
+
+        match backend.dispatch_counted(request).await {
+            Ok((_, count)) => {
+                operation.counted(count).finished();
+                Ok(())
+            }
+            Err(e) => {
+                operation.failed(&e.to_string());
+                Err(e.to_string())
+            }
+        }
+    }
+}
+
+/// The enabled schedules with a cron expression firing in `(since, now]`
+fn due_schedules(since: DateTime<Utc>, now: DateTime<Utc>) -> Vec<Schedule> {
+    use crate::schema::schedules::dsl;
+
+    let results = dsl::schedules
+        .select(Schedule::as_select())
+        .filter(dsl::enabled.eq(true))
+        .filter(dsl::cron.is_not_null())
+        .get_results(&mut establish_connection());
+
+    let results: Vec<Schedule> = match results {
+        Ok(results) => results,
+        Err(e) => {
+            tracing::error!("Failed to load schedules: {e}");
+            return vec![];
+        }
+    };
+
+    results
+        .into_iter()
+        .filter(|schedule| {
+            let expression = schedule.cron.as_deref().unwrap_or_default();
+            parse_cron(expression).is_ok_and(|parsed| {
+                parsed
+                    .after(&since)
+                    .next()
+                    .is_some_and(|fire_time| fire_time <= now)
+            })
+        })
+        .collect()
+}
+
+/// The enabled schedules watching the space changed by `event`. Only finished
+/// uploads and imports count as changes.
+fn watching_schedules(event: &SpaceEvent) -> Vec<Schedule> {
+    use crate::schema::schedules::dsl;
+
+    if !matches!(event.kind, SpaceEventKind::Finished)
+        || !matches!(event.operation, JobKind::Upload | JobKind::Import)
+    {
+        return vec![];
+    }
+
+    let results = dsl::schedules
+        .select(Schedule::as_select())
+        .filter(dsl::enabled.eq(true))
+        .filter(dsl::watch_namespace.is_not_null())
+        .get_results(&mut establish_connection());
+
+    match results {
+        Ok(results) => results
+            .into_iter()
+            .filter(|schedule| {
+                let watched = schedule.watch_namespace.as_deref().unwrap_or_default();
+                event.is_within(&PathBuf::from(watched.trim_matches('/')))
+            })
+            .collect(),
+        Err(e) => {
+            tracing::error!("Failed to load schedules: {e}");
+            vec![]
+        }
+    }
+}
@@ -1,5 +1,108 @@
 // @generated automatically by Diesel CLI.
 
+diesel::table! {
+    jobs (id) {
+        id -> Int4,
+        token_id -> Int4,
+        kind -> Varchar,
+        namespace -> Varchar,
+        status -> Varchar,
+        creation_timestamp -> Timestamp,
+        start_timestamp -> Nullable<Timestamp>,
+        finish_timestamp -> Nullable<Timestamp>,
+        response -> Nullable<Text>,
+        error -> Nullable<Text>,
+    }
+}
+
+diesel::table! {
+    pipeline_steps (id) {
+        id -> Int4,
+        pipeline_id -> Int4,
+        position -> Int4,
+        patterns -> Array<Text>,
+        templates -> Array<Text>,
+    }
+}
+
+diesel::table! {
+    pipelines (id) {
+        id -> Int4,
+        name -> Varchar,
+        version -> Int4,
+        description -> Varchar,
+        token_id -> Nullable<Int4>,
+        creation_timestamp -> Timestamp,
+        namespace -> Varchar,
+    }
+}
+
+diesel::table! {
+    quotas (id) {
+        id -> Int4,
+        namespace -> Varchar,
+        max_atoms -> Nullable<Int8>,
+        max_upload_bytes -> Nullable<Int8>,
+        max_import_bytes -> Nullable<Int8>,
+        token_id -> Nullable<Int4>,
+        update_timestamp -> Timestamp,
+    }
+}
+
+diesel::table! {
+    saved_queries (id) {
+        id -> Int4,
+        namespace -> Varchar,
+        name -> Varchar,
+        patterns -> Array<Text>,
+        templates -> Array<Text>,
+        description -> Varchar,
+        token_id -> Nullable<Int4>,
+        creation_timestamp -> Timestamp,
+    }
+}
+
+diesel::table! {
+    schedule_runs (id) {
+        id -> Int4,
+        schedule_id -> Int4,
+        trigger -> Varchar,
+        status -> Varchar,
+        start_timestamp -> Timestamp,
+        finish_timestamp -> Nullable<Timestamp>,
+        error -> Nullable<Text>,
+    }
+}
+
+diesel::table! {
+    schedules (id) {
+        id -> Int4,
+        name -> Varchar,
+        namespace -> Varchar,
+        token_id -> Int4,
+        saved_query_id -> Nullable<Int4>,
+        pipeline_name -> Nullable<Varchar>,
+        pipeline_version -> Nullable<Int4>,
+        params -> Text,
+        cron -> Nullable<Varchar>,
+        watch_namespace -> Nullable<Varchar>,
+        enabled -> Bool,
+        last_run_timestamp -> Nullable<Timestamp>,
+        last_error -> Nullable<Text>,
+        creation_timestamp -> Timestamp,
+    }
+}
+
+diesel::table! {
+    snapshots (id) {
+        id -> Int4,
+        name -> Varchar,
+        namespace -> Varchar,
+        token_id -> Nullable<Int4>,
+        creation_timestamp -> Timestamp,
+    }
+}
+
 diesel::table! {
     tokens (id) {
         id -> Int4,
@@ -13,5 +116,63 @@ diesel::table! {
         permission_share_read -> Bool,
         permission_share_write -> Bool,
         parent -> Nullable<Int4>,
+        rate_limit_read -> Nullable<Int4>,
+        rate_limit_write -> Nullable<Int4>,
+        rate_limit_translate -> Nullable<Int4>,
+        concurrency_limit_read -> Nullable<Int4>,
+        concurrency_limit_write -> Nullable<Int4>,
+        concurrency_limit_translate -> Nullable<Int4>,
     }
 }
+
+diesel::table! {
+    webhook_deliveries (id) {
+        id -> Int4,
+        webhook_id -> Int4,
+        operation -> Varchar,
+        namespace -> Varchar,
+        payload -> Text,
+        status -> Varchar,
+        attempts -> Int4,
+        response_status -> Nullable<Int4>,
+        error -> Nullable<Text>,
+        creation_timestamp -> Timestamp,
+        last_attempt_timestamp -> Nullable<Timestamp>,
+    }
+}
+
+diesel::table! {
+    webhooks (id) {
+        id -> Int4,
+        namespace -> Varchar,
+        url -> Varchar,
+        token_id -> Int4,
+        creation_timestamp -> Timestamp,
+    }
+}
+
+diesel::joinable!(jobs -> tokens (token_id));
+diesel::joinable!(pipeline_steps -> pipelines (pipeline_id));
+diesel::joinable!(pipelines -> tokens (token_id));
+diesel::joinable!(quotas -> tokens (token_id));
+diesel::joinable!(saved_queries -> tokens (token_id));
+diesel::joinable!(schedule_runs -> schedules (schedule_id));
+diesel::joinable!(schedules -> saved_queries (saved_query_id));
+diesel::joinable!(schedules -> tokens (token_id));
+diesel::joinable!(snapshots -> tokens (token_id));
+diesel::joinable!(webhook_deliveries -> webhooks (webhook_id));
+diesel::joinable!(webhooks -> tokens (token_id));
+
+diesel::allow_tables_to_appear_in_same_query!(
+    jobs,
+    pipeline_steps,
+    pipelines,
+    quotas,
+    saved_queries,
+    schedule_runs,
+    schedules,
+    snapshots,
+    tokens,
+    webhook_deliveries,
+    webhooks,
+);
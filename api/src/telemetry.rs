@@ -0,0 +1,180 @@
+use rocket::fairing::{Fairing, Info, Kind};
+use rocket::http::Header;
+use rocket::request::{self, FromRequest};
+use rocket::route::{self, Handler, Route};
+use rocket::{Data, Request, Response};
+use std::time::Instant;
+use tracing::field::Empty;
+use tracing::{info_span, Instrument, Span};
+use tracing_subscriber::EnvFilter;
+use uuid::Uuid;
+
+use crate::config::{Config, LogFormat};
+use crate::model::Token;
+
+pub const REQUEST_ID_HEADER: &str = "X-Request-Id";
+
+/// Sets up the tracing subscriber, writing the logs in the configured format
+/// and filtering them with the configured filter, e.g. `info` or `api=debug`
+pub fn init(config: &Config) {
+    let filter = EnvFilter::try_new(&config.log).unwrap_or_else(|_| EnvFilter::new("info"));
+    let builder = tracing_subscriber::fmt().with_env_filter(filter);
+
+    // the subscriber may already be set, e.g. when several instances are built
+    // in one process
+    let _ = match config.log_format {
+        LogFormat::Json => builder.json().try_init(),
+        LogFormat::Text => builder.try_init(),
+    };
+}
+
+/// Whether an inherited request id is safe to log and forward
+fn is_valid_request_id(id: &str) -> bool {
+    !id.is_empty()
+        && id.len() <= 64
+        && id
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+/// The id of a request, inherited from its `X-Request-Id` header or generated
+#[derive(Clone, Debug)]
+pub struct RequestId(pub String);
+
+/// The span of a request, kept in the request local cache
+struct RequestSpan {
+    span: Span,
+    started: Instant,
+}
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for RequestId {
+    type Error = ();
+
+    async fn from_request(request: &'r Request<'_>) -> request::Outcome<Self, Self::Error> {
+        request::Outcome::Success(request_id(request))
+    }
+}
+
+/// The id of `request`, as assigned by `RequestTracing`
+pub fn request_id(request: &Request<'_>) -> RequestId {
+    request
+        .local_cache(|| {
+            let id = request
+                .headers()
+                .get_one(REQUEST_ID_HEADER)
+                .filter(|id| is_valid_request_id(id))
+                .map(str::to_string)
+                .unwrap_or_else(|| Uuid::new_v4().to_string());
+
+            RequestId(id)
+        })
+        .clone()
+}
+
+/// Records the token of `request` on its span. Only the token id is recorded,
+/// never its code.
+pub fn record_token(request: &Request<'_>, token: &Token) {
+    if let Some(span) = &request.local_cache(|| None::<RequestSpan>) {
+        span.span.record("token_id", token.id);
+        span.span
+            .record("token_namespace", token.namespace.as_str());
+    }
+}
+
+/// A route handler running in the span of the request, so the logs of the
+/// handler, and of the MORK client it calls, carry the request id
+#[derive(Clone)]
+struct Traced(Box<dyn Handler>);
+
+#[rocket::async_trait]
+impl Handler for Traced {
+    async fn handle<'r>(&self, request: &'r Request<'_>, data: Data<'r>) -> route::Outcome<'r> {
+        let span = match request.local_cache(|| None::<RequestSpan>) {
+            Some(RequestSpan { span, .. }) => span.clone(),
+            None => Span::none(),
+        };
+
+        self.0.handle(request, data).instrument(span).await
+    }
+}
+
+/// Runs the handlers of `routes` in the span of their request
+pub fn traced(routes: Vec<Route>) -> Vec<Route> {
+    routes
+        .into_iter()
+        .map(|mut route| {
+            route.handler = Box::new(Traced(route.handler));
+            route
+        })
+        .collect()
+}
+
+/// Gives every request an id, returned in the `X-Request-Id` response header,
+/// and logs its outcome in a span
+pub struct RequestTracing;
+
+#[rocket::async_trait]
+impl Fairing for RequestTracing {
+    fn info(&self) -> Info {
+        Info {
+            name: "Request tracing",
+            kind: Kind::Request | Kind::Response,
+        }
+    }
+
+    async fn on_request(&self, request: &mut Request<'_>, _: &mut Data<'_>) {
+        let RequestId(id) = request_id(request);
+
+        let span = info_span!(
+            "request",
+            request_id = %id,
+            method = %request.method(),
+            route = Empty,
+            token_id = Empty,
+            token_namespace = Empty,
+            status = Empty,
+            outcome = Empty,
+            latency_ms = Empty,
+        );
+
+        request.local_cache(|| {
+            Some(RequestSpan {
+                span,
+                started: Instant::now(),
+            })
+        });
+    }
+
+    async fn on_response<'r>(&self, request: &'r Request<'_>, response: &mut Response<'r>) {
+        let RequestId(id) = request_id(request);
+        response.set_header(Header::new(REQUEST_ID_HEADER, id));
+
+        let Some(RequestSpan { span, started }) = request.local_cache(|| None::<RequestSpan>)
+        else {
+            return;
+        };
+
+        let status = response.status();
+        let outcome = match status.code {
+            500.. => "server_error",
+            400.. => "client_error",
+            _ => "success",
+        };
+
+        if let Some(route) = request.route() {
+            span.record("route", route.uri.to_string());
+        }
+        span.record("status", status.code);
+        span.record("outcome", outcome);
+        span.record("latency_ms", started.elapsed().as_millis() as u64);
+
+        span.in_scope(|| {
+            if status.code >= 500 {
+                tracing::error!("request failed");
+            } else {
+                tracing::info!("request finished");
+            }
+        });
+    }
+}
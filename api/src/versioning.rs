@@ -0,0 +1,60 @@
+use rocket::fairing::{Fairing, Info, Kind};
+use rocket::http::{Header, Method};
+use rocket::{Request, Response, Route};
+use std::collections::HashSet;
+
+/// Where the current version of the API is mounted
+pub const API_BASE: &str = "/v1";
+
+/// `path` without the version prefix, e.g. `/spaces/x` for `/v1/spaces/x`
+pub fn unversioned(path: &str) -> &str {
+    match path.strip_prefix(API_BASE) {
+        Some(rest) if rest.is_empty() || rest.starts_with('/') => rest,
+        _ => path,
+    }
+}
+
+/// Marks the responses of the unversioned aliases of the API routes as
+/// deprecated, pointing to their `/v1` successor
+pub struct DeprecatedAliases {
+    aliases: HashSet<(Method, String)>,
+}
+
+impl DeprecatedAliases {
+    /// The aliases of `routes`, as mounted at `/`
+    pub fn new(routes: &[Route]) -> Self {
+        DeprecatedAliases {
+            aliases: routes
+                .iter()
+                .map(|route| (route.method, route.uri.to_string()))
+                .collect(),
+        }
+    }
+}
+
+#[rocket::async_trait]
+impl Fairing for DeprecatedAliases {
+    fn info(&self) -> Info {
+        Info {
+            name: "Deprecated aliases",
+            kind: Kind::Response,
+        }
+    }
+
+    async fn on_response<'r>(&self, request: &'r Request<'_>, response: &mut Response<'r>) {
+        let Some(route) = request.route() else {
+            return;
+        };
+
+        if self
+            .aliases
+            .contains(&(route.method, route.uri.to_string()))
+        {
+            response.set_header(Header::new("Deprecation", "true"));
+            response.set_header(Header::new(
+                "Link",
+                format!("<{API_BASE}{}>; rel=\"successor-version\"", request.uri()),
+            ));
+        }
+    }
+}
@@ -0,0 +1,369 @@
+use chrono::{NaiveDateTime, Utc};
+use diesel::sql_types::Text;
+use diesel::{ExpressionMethods, QueryDsl, RunQueryDsl};
+use hmac::{Hmac, Mac};
+use reqwest::dns::{Addrs, Name, Resolve, Resolving};
+use reqwest::redirect::Policy;
+use reqwest::Client;
+use rocket::serde::json::to_string;
+use rocket::tokio::net::lookup_host;
+use rocket::tokio::sync::broadcast::error::RecvError;
+use rocket::tokio::{self, select, time};
+use rocket::Shutdown;
+use serde::Serialize;
+use sha2::Sha256;
+use std::net::{IpAddr, SocketAddr};
+use std::sync::Arc;
+use std::time::Duration;
+use subtle::ConstantTimeEq;
+use url::{Host, Url};
+
+use crate::config::Config;
+use crate::db::establish_connection;
+use crate::events::{EventBus, SpaceEvent, SpaceEventKind};
+use crate::jobs::{JobKind, JobStatus};
+use crate::model::{Webhook, WebhookDelivery, WebhookDeliveryInsert};
+
+/// Deliveries are given up after this many attempts
+const MAX_ATTEMPTS: i32 = 5;
+
+const DELIVERY_TIMEOUT: Duration = Duration::from_secs(10);
+
+pub const SIGNATURE_HEADER: &str = "X-MettaKG-Signature";
+pub const DELIVERY_HEADER: &str = "X-MettaKG-Delivery";
+
+/// The body POSTed to a webhook
+#[derive(Serialize)]
+pub struct WebhookPayload<'a> {
+    pub webhook_id: i32,
+    pub timestamp: NaiveDateTime,
+    pub event: &'a SpaceEvent,
+}
+
+/// The hex encoded HMAC-SHA256 of `payload`, sent as `sha256=<signature>`
+pub fn sign(secret: &str, payload: &str) -> String {
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC accepts keys of any size");
+    mac.update(payload.as_bytes());
+
+    mac.finalize()
+        .into_bytes()
+        .iter()
+        .map(|byte| format!("{byte:02x}"))
+        .collect()
+}
+
+/// Whether `signature`, the hex without `sha256=`, is the signature of
+/// `payload`, as a receiver checks a delivery
+pub fn verify(secret: &str, payload: &str, signature: &str) -> bool {
+    secrets_match(&sign(secret, payload), signature)
+}
+
+/// Compares secrets, such as signatures and bearer tokens, in constant time, so
+/// the time it takes does not tell how much of them matched
+pub fn secrets_match(expected: &str, given: &str) -> bool {
+    expected.as_bytes().ct_eq(given.as_bytes()).into()
+}
+
+/// Whether `ip` is an address of the public internet, rather than e.g. a
+/// loopback, private or link-local one, like that of a cloud metadata service
+fn is_public(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => {
+            let [first, second, ..] = ip.octets();
+            let shared = first == 100 && second & 0xc0 == 64;
+
+            !(ip.is_loopback()
+                || ip.is_private()
+                || ip.is_link_local()
+                || ip.is_unspecified()
+                || ip.is_broadcast()
+                || ip.is_documentation()
+                || ip.is_multicast()
+                || first == 0
+                || shared)
+        }
+        IpAddr::V6(ip) => match ip.to_ipv4_mapped() {
+            Some(ip) => is_public(IpAddr::V4(ip)),
+            None => {
+                !(ip.is_loopback()
+                    || ip.is_unspecified()
+                    || ip.is_multicast()
+                    || ip.is_unique_local()
+                    || ip.is_unicast_link_local())
+            }
+        },
+    }
+}
+
+/// Whether deliveries may be sent to `url`: an http or https URL whose host
+/// is in `allowed_hosts`, or resolves to public addresses only
+pub async fn is_allowed_url(url: &str, allowed_hosts: &[String]) -> bool {
+    let Ok(url) = Url::parse(url) else {
+        return false;
+    };
+
+    if url.scheme() != "http" && url.scheme() != "https" {
+        return false;
+    }
+
+    if url
+        .host_str()
+        .is_some_and(|host| allowed_hosts.iter().any(|allowed| allowed == host))
+    {
+        return true;
+    }
+
+    match url.host() {
+        Some(Host::Ipv4(ip)) => is_public(ip.into()),
+        Some(Host::Ipv6(ip)) => is_public(ip.into()),
+        Some(Host::Domain(domain)) => {
+            let port = url.port_or_known_default().unwrap_or(80);
+            match lookup_host((domain, port)).await {
+                Ok(addrs) => {
+                    let addrs: Vec<SocketAddr> = addrs.collect();
+                    !addrs.is_empty() && addrs.iter().all(|addr| is_public(addr.ip()))
+                }
+                Err(_) => false,
+            }
+        }
+        None => false,
+    }
+}
+
+/// Resolves the hosts of the webhooks to their public addresses only, so a
+/// host can not be pointed at a private address once its URL was checked
+struct PublicResolver {
+    allowed_hosts: Vec<String>,
+}
+
+impl Resolve for PublicResolver {
+    fn resolve(&self, name: Name) -> Resolving {
+        let host = name.as_str().to_string();
+        let allowed = self.allowed_hosts.contains(&host);
+
+        Box::pin(async move {
+            let addrs: Vec<SocketAddr> = lookup_host((host.as_str(), 0))
+                .await?
+                .filter(|addr| allowed || is_public(addr.ip()))
+                .collect();
+
+            if addrs.is_empty() {
+                return Err(format!("{host} has no public address").into());
+            }
+
+            Ok(Box::new(addrs.into_iter()) as Addrs)
+        })
+    }
+}
+
+/// Whether `event` is a change worth notifying, i.e. a finished upload,
+/// import, transform or clear
+fn is_change(event: &SpaceEvent) -> bool {
+    matches!(event.kind, SpaceEventKind::Finished)
+        && matches!(
+            event.operation,
+            JobKind::Upload | JobKind::Import | JobKind::Transform | JobKind::Clear
+        )
+}
+
+/// An HTTP client that only connects to public addresses or `allowed_hosts`.
+/// Redirects are not followed, as they could lead to a private address.
+pub fn public_client(allowed_hosts: Vec<String>) -> Client {
+    Client::builder()
+        .dns_resolver(Arc::new(PublicResolver { allowed_hosts }))
+        .redirect(Policy::none())
+        .build()
+        .expect("the client configuration is valid")
+}
+
+/// POSTs signed notifications of space changes to the registered webhooks,
+/// and logs every delivery. Managed as Rocket state and started on liftoff.
+#[derive(Clone)]
+pub struct WebhookDispatcher {
+    events: EventBus,
+    client: Client,
+    /// the key signing the deliveries
+    secret: Option<String>,
+    /// the delay before the first retry, doubled for every next one
+    retry_delay: Duration,
+    /// the hosts deliveries may be sent to even if they are not public
+    allowed_hosts: Vec<String>,
+}
+
+impl WebhookDispatcher {
+    pub fn new(events: EventBus, config: &Config) -> Self {
+        WebhookDispatcher {
+            events,
+            client: public_client(config.webhook_allowed_hosts.clone()),
+            secret: config.secret.clone(),
+            retry_delay: config.webhook_retry_delay(),
+            allowed_hosts: config.webhook_allowed_hosts.clone(),
+        }
+    }
+
+    /// Spawns the task watching the space events
+    pub fn start(&self, shutdown: Shutdown) {
+        let dispatcher = self.clone();
+        tokio::spawn(async move { dispatcher.watch_events(shutdown).await });
+    }
+
+    async fn watch_events(&self, mut shutdown: Shutdown) {
+        let mut receiver = self.events.subscribe();
+
+        loop {
+            let event = select! {
+                event = receiver.recv() => event,
+                _ = &mut shutdown => break,
+            };
+
+            match event {
+                Ok(event) if is_change(&event) => self.notify(&event),
+                Ok(_) | Err(RecvError::Lagged(_)) => continue,
+                Err(RecvError::Closed) => break,
+            }
+        }
+    }
+
+    /// Records a delivery for every webhook of a space containing the changed
+    /// one, and sends them in the background
+    fn notify(&self, event: &SpaceEvent) {
+        for webhook in subscribed_webhooks(event) {
+            let payload = WebhookPayload {
+                webhook_id: webhook.id,
+                timestamp: Utc::now().naive_utc(),
+                event,
+            };
+
+            let delivery = match record_delivery(&webhook, event, &payload) {
+                Ok(delivery) => delivery,
+                Err(e) => {
+                    tracing::error!(webhook_id = webhook.id, "Failed to record delivery: {e}");
+                    continue;
+                }
+            };
+
+            let dispatcher = self.clone();
+            tokio::spawn(async move { dispatcher.deliver(webhook, delivery).await });
+        }
+    }
+
+    async fn deliver(&self, webhook: Webhook, delivery: WebhookDelivery) {
+        use crate::schema::webhook_deliveries::dsl::*;
+
+        let fail = |message: &str| {
+            let _ = diesel::update(webhook_deliveries.filter(id.eq(delivery.id)))
+                .set((status.eq(JobStatus::Failed.as_str()), error.eq(message)))
+                .execute(&mut establish_connection());
+        };
+
+        let signature = match &self.secret {
+            Some(secret) => sign(secret, &delivery.payload),
+            None => return fail("METTA_KG_SECRET is not set"),
+        };
+
+        // the host may have been pointed elsewhere since the webhook was
+        // registered
+        if !is_allowed_url(&webhook.url, &self.allowed_hosts).await {
+            return fail("the webhook URL does not resolve to a public address");
+        }
+
+        let mut delay = self.retry_delay;
+
+        for attempt in 1..=MAX_ATTEMPTS {
+            let result = self
+                .client
+                .post(&webhook.url)
+                .header("Content-Type", "application/json")
+                .header(SIGNATURE_HEADER, format!("sha256={signature}"))
+                .header(DELIVERY_HEADER, delivery.id.to_string())
+                .body(delivery.payload.clone())
+                .timeout(DELIVERY_TIMEOUT)
+                .send()
+                .await;
+
+            let (new_response_status, new_error) = match result {
+                Ok(response) if response.status().is_success() => {
+                    (Some(response.status().as_u16() as i32), None)
+                }
+                Ok(response) => (
+                    Some(response.status().as_u16() as i32),
+                    Some(format!("webhook responded with {}", response.status())),
+                ),
+                Err(e) => (None, Some(e.to_string())),
+            };
+
+            let new_status = match new_error {
+                None => JobStatus::Succeeded,
+                Some(_) if attempt == MAX_ATTEMPTS => JobStatus::Failed,
+                Some(_) => JobStatus::Pending,
+            };
+
+            let updated = diesel::update(webhook_deliveries.filter(id.eq(delivery.id)))
+                .set((
+                    status.eq(new_status.as_str()),
+                    attempts.eq(attempt),
+                    response_status.eq(new_response_status),
+                    error.eq(new_error),
+                    last_attempt_timestamp.eq(Utc::now().naive_utc()),
+                ))
+                .execute(&mut establish_connection());
+
+            if let Err(e) = updated {
+                tracing::error!(delivery_id = delivery.id, "Failed to log delivery: {e}");
+            }
+
+            if new_status != JobStatus::Pending {
+                return;
+            }
+
+            time::sleep(delay).await;
+            delay *= 2;
+        }
+    }
+}
+
+fn record_delivery(
+    webhook: &Webhook,
+    event: &SpaceEvent,
+    payload: &WebhookPayload,
+) -> Result<WebhookDelivery, String> {
+    let to_insert = WebhookDeliveryInsert {
+        webhook_id: webhook.id,
+        operation: event.operation.as_str().to_string(),
+        namespace: event.namespace.clone(),
+        payload: to_string(payload).map_err(|e| e.to_string())?,
+        status: JobStatus::Pending.as_str().to_string(),
+        attempts: 0,
+        creation_timestamp: Utc::now().naive_utc(),
+    };
+
+    diesel::insert_into(crate::schema::webhook_deliveries::table)
+        .values(&to_insert)
+        .get_result(&mut establish_connection())
+        .map_err(|e| e.to_string())
+}
+
+/// The webhooks of the spaces containing the space of `event`, whose token
+/// can still read that space
+fn subscribed_webhooks(event: &SpaceEvent) -> Vec<Webhook> {
+    // as webhook namespaces are stored, e.g. `/a/b/`
+    let namespace = format!("{}/", event.namespace.trim_end_matches('/'));
+
+    let results = diesel::sql_query(
+        "SELECT webhooks.*
+            FROM webhooks
+            JOIN tokens
+            ON tokens.id = webhooks.token_id
+        WHERE tokens.permission_read
+            AND left(webhooks.namespace, char_length(tokens.namespace)) = tokens.namespace
+            AND left($1, char_length(webhooks.namespace)) = webhooks.namespace;",
+    )
+    .bind::<Text, _>(namespace)
+    .get_results::<Webhook>(&mut establish_connection());
+
+    results.unwrap_or_else(|e| {
+        tracing::error!("Failed to load webhooks: {e}");
+        vec![]
+    })
+}
@@ -34,7 +34,70 @@ pub fn drop_tokens_table() {
         .expect("Failed to drop migrations table");
 }
 
+pub fn drop_jobs_table() {
+    let conn = &mut establish_connection();
+    let sql = r#"DROP TABLE IF EXISTS jobs"#;
+    diesel::sql_query(sql)
+        .execute(conn)
+        .expect("Failed to drop jobs table");
+}
+
+pub fn drop_snapshots_table() {
+    let conn = &mut establish_connection();
+    let sql = r#"DROP TABLE IF EXISTS snapshots"#;
+    diesel::sql_query(sql)
+        .execute(conn)
+        .expect("Failed to drop snapshots table");
+}
+
+pub fn drop_saved_queries_table() {
+    let conn = &mut establish_connection();
+    let sql = r#"DROP TABLE IF EXISTS saved_queries"#;
+    diesel::sql_query(sql)
+        .execute(conn)
+        .expect("Failed to drop saved_queries table");
+}
+
+pub fn drop_quotas_table() {
+    let conn = &mut establish_connection();
+    let sql = r#"DROP TABLE IF EXISTS quotas"#;
+    diesel::sql_query(sql)
+        .execute(conn)
+        .expect("Failed to drop quotas table");
+}
+
+pub fn drop_webhooks_tables() {
+    let conn = &mut establish_connection();
+    let sql = r#"DROP TABLE IF EXISTS webhook_deliveries, webhooks"#;
+    diesel::sql_query(sql)
+        .execute(conn)
+        .expect("Failed to drop webhooks tables");
+}
+
+pub fn drop_schedules_tables() {
+    let conn = &mut establish_connection();
+    let sql = r#"DROP TABLE IF EXISTS schedule_runs, schedules"#;
+    diesel::sql_query(sql)
+        .execute(conn)
+        .expect("Failed to drop schedules tables");
+}
+
+pub fn drop_pipelines_tables() {
+    let conn = &mut establish_connection();
+    let sql = r#"DROP TABLE IF EXISTS pipeline_steps, pipelines"#;
+    diesel::sql_query(sql)
+        .execute(conn)
+        .expect("Failed to drop pipelines tables");
+}
+
 pub fn teardown_database() {
+    drop_jobs_table();
+    drop_quotas_table();
+    drop_webhooks_tables();
+    drop_schedules_tables();
+    drop_pipelines_tables();
+    drop_saved_queries_table();
+    drop_snapshots_table();
     drop_tokens_table();
 }
 
@@ -58,6 +121,12 @@ pub fn create_test_token(namespace: &str, permission_read: bool, permission_writ
         permission_share_read: true,
         permission_share_write: true,
         parent: None,
+        rate_limit_read: None,
+        rate_limit_write: None,
+        rate_limit_translate: None,
+        concurrency_limit_read: None,
+        concurrency_limit_write: None,
+        concurrency_limit_translate: None,
     };
 
     diesel::insert_into(tokens::table)
@@ -1,11 +1,32 @@
 mod common;
+mod test_admin;
+mod test_batch;
 mod test_clear;
+mod test_config;
+mod test_copy;
+mod test_diff;
+mod test_errors;
+mod test_events;
 mod test_explore;
 mod test_export;
+mod test_health;
 mod test_import;
+mod test_jobs;
+mod test_memory_backend;
+mod test_metrics;
+mod test_mork_client;
+mod test_openapi;
+mod test_pipelines;
+mod test_queries;
+mod test_quotas;
+mod test_rate_limits;
 mod test_read;
+mod test_schedules;
+mod test_snapshots;
+mod test_tracing;
 mod test_transform;
 mod test_upload;
+mod test_webhooks;
 
 #[tokio::test]
 async fn test_integration_is_working() {
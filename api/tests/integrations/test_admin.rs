@@ -0,0 +1,137 @@
+use api::admin::{self, ROOT_TOKEN_ID};
+use api::config::Config;
+use api::model::TokenInsert;
+use api::mork_api::MorkApiClient;
+use chrono::Utc;
+use httpmock::prelude::*;
+use httpmock::Regex;
+use serial_test::serial;
+
+use crate::integrations::common;
+
+fn new_token(namespace: &str, parent: i32) -> TokenInsert {
+    TokenInsert {
+        code: String::new(),
+        description: "Created offline".to_string(),
+        namespace: namespace.to_string(),
+        creation_timestamp: Utc::now().naive_utc(),
+        permission_read: true,
+        permission_write: true,
+        permission_share_share: false,
+        permission_share_read: true,
+        permission_share_write: false,
+        parent: Some(parent),
+        rate_limit_read: None,
+        rate_limit_write: None,
+        rate_limit_translate: None,
+        concurrency_limit_read: None,
+        concurrency_limit_write: None,
+        concurrency_limit_translate: None,
+    }
+}
+
+#[test]
+#[serial]
+fn test_root_token_rotation() {
+    if !common::is_database_running() {
+        eprintln!("Warning: Database not running, skipping test");
+        return;
+    }
+    common::setup("http://127.0.0.1:1");
+
+    let root = admin::root_token().expect("root token");
+    assert_eq!(root.id, ROOT_TOKEN_ID);
+    assert_eq!(root.namespace, "/");
+
+    let rotated = admin::rotate_root_token().expect("rotated root token");
+    assert_ne!(rotated.code, root.code);
+    assert_eq!(admin::root_token().unwrap().code, rotated.code);
+
+    assert!(admin::revoke_token(ROOT_TOKEN_ID).is_err());
+
+    common::teardown_database();
+}
+
+#[test]
+#[serial]
+fn test_tokens_offline() {
+    if !common::is_database_running() {
+        eprintln!("Warning: Database not running, skipping test");
+        return;
+    }
+    common::setup("http://127.0.0.1:1");
+
+    assert!(admin::create_token(new_token("no-slashes", ROOT_TOKEN_ID)).is_err());
+
+    let parent = admin::create_token(new_token("/team/", ROOT_TOKEN_ID)).expect("token");
+    let child = admin::create_token(new_token("/team/data/", parent.id)).expect("token");
+    assert!(!parent.code.is_empty());
+
+    let exported = admin::list_tokens().unwrap();
+    assert_eq!(exported.len(), 3);
+
+    // revoking a token revokes the tokens derived from it
+    assert_eq!(admin::revoke_token(parent.id), Ok(2));
+    assert!(admin::revoke_token(parent.id).is_err());
+    assert_eq!(admin::list_tokens().unwrap().len(), 1);
+
+    // importing restores the missing tokens with their codes
+    assert_eq!(admin::import_tokens(&exported), Ok(2));
+    assert_eq!(admin::import_tokens(&exported), Ok(0));
+
+    let restored = admin::list_tokens().unwrap();
+    assert!(restored
+        .iter()
+        .any(|token| token.id == child.id && token.code == child.code));
+
+    // new tokens do not collide with the imported ones
+    let next = admin::create_token(new_token("/other/", ROOT_TOKEN_ID)).expect("token");
+    assert!(next.id > child.id);
+
+    common::teardown_database();
+}
+
+#[test]
+#[serial]
+fn test_migrations_offline() {
+    if !common::is_database_running() {
+        eprintln!("Warning: Database not running, skipping test");
+        return;
+    }
+    common::setup("http://127.0.0.1:1");
+
+    assert_eq!(admin::run_migrations(), Ok(vec![]));
+
+    let reverted = admin::revert_migration().expect("reverted migration");
+    assert_eq!(admin::run_migrations(), Ok(vec![reverted]));
+
+    common::teardown_database();
+}
+
+#[tokio::test]
+#[serial]
+async fn test_check_mork() {
+    let server = MockServer::start();
+    server.mock(|when, then| {
+        when.method(GET)
+            .path_matches(Regex::new(r"/export/.*").unwrap());
+        then.status(200).body("");
+    });
+
+    let config = Config {
+        mork_url: server.base_url(),
+        ..Config::default()
+    };
+    assert!(admin::check_mork(&MorkApiClient::new(&config))
+        .await
+        .is_ok());
+
+    let config = Config {
+        mork_url: "http://127.0.0.1:1".to_string(),
+        mork_retry_delay_ms: 10,
+        ..Config::default()
+    };
+    assert!(admin::check_mork(&MorkApiClient::new(&config))
+        .await
+        .is_err());
+}
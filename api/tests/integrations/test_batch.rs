@@ -0,0 +1,270 @@
+use api::errors::ErrorBody;
+use api::rocket;
+use api::routes::batch::{BatchOperation, BatchReport};
+use api::routes::quotas::QuotaInput;
+use httpmock::prelude::*;
+use httpmock::Regex;
+use rocket::http::{Header, Status};
+use rocket::local::asynchronous::Client;
+use rocket::serde::json::from_value;
+use rocket::tokio::io::AsyncReadExt;
+use serial_test::serial;
+use std::env;
+use std::time::Duration;
+
+use crate::integrations::common;
+
+fn etl_operations(staging: &str) -> Vec<BatchOperation> {
+    vec![
+        BatchOperation::Upload {
+            namespace: staging.to_string(),
+            data: "(raw 1) (raw 2)".to_string(),
+        },
+        BatchOperation::Transform {
+            namespace: staging.to_string(),
+            patterns: vec!["(raw $x)".to_string()],
+            templates: vec!["(clean $x)".to_string()],
+        },
+        BatchOperation::Clear {
+            namespace: staging.to_string(),
+            expr: "(raw $x)".to_string(),
+        },
+    ]
+}
+
+#[tokio::test]
+#[serial]
+async fn test_batch_success() {
+    if !common::is_database_running() {
+        eprintln!("Warning: Database not running, skipping test");
+        return;
+    }
+    let server = MockServer::start();
+    common::setup(&server.base_url());
+
+    let token = common::create_test_token("/test/", true, true);
+
+    let upload_mock = server.mock(|when, then| {
+        when.method(POST)
+            .path_matches(Regex::new(r"/upload/.*").unwrap());
+        then.status(200).body("Upload successful");
+    });
+    // the backup and the transform of the batch
+    let transform_mock = server.mock(|when, then| {
+        when.method(POST).path("/transform");
+        then.status(200).body("Transform successful");
+    });
+    // the clear of the batch and the removal of the backup
+    let clear_mock = server.mock(|when, then| {
+        when.method(GET)
+            .path_matches(Regex::new(r"/clear/.*").unwrap());
+        then.status(200).body("Clear successful");
+    });
+
+    let client = Client::tracked(rocket())
+        .await
+        .expect("valid rocket instance");
+
+    let response = client
+        .post("/spaces/batch")
+        .header(Header::new("authorization", token.code.clone()))
+        .json(&etl_operations("/test/staging/"))
+        .dispatch()
+        .await;
+
+    assert_eq!(response.status(), Status::Ok);
+    let report: BatchReport = response.into_json().await.expect("report");
+    assert_eq!(report.completed, 3);
+    assert!(report.error.is_none());
+
+    upload_mock.assert_hits(1);
+    transform_mock.assert_hits(2);
+    clear_mock.assert_hits(2);
+
+    common::teardown_database();
+}
+
+#[tokio::test]
+#[serial]
+async fn test_batch_authorized_up_front() {
+    if !common::is_database_running() {
+        eprintln!("Warning: Database not running, skipping test");
+        return;
+    }
+    let server = MockServer::start();
+    common::setup(&server.base_url());
+
+    let token = common::create_test_token("/test/", true, true);
+
+    let any_mock = server.mock(|when, then| {
+        when.path_matches(Regex::new(r".*").unwrap());
+        then.status(200).body("OK");
+    });
+
+    let client = Client::tracked(rocket())
+        .await
+        .expect("valid rocket instance");
+
+    let mut operations = etl_operations("/test/staging/");
+    operations.push(BatchOperation::Clear {
+        namespace: "/other/space/".to_string(),
+        expr: "$x".to_string(),
+    });
+
+    let response = client
+        .post("/spaces/batch")
+        .header(Header::new("authorization", token.code.clone()))
+        .json(&operations)
+        .dispatch()
+        .await;
+
+    assert_eq!(response.status(), Status::Unauthorized);
+    let error: ErrorBody = response.into_json().await.expect("error");
+    let report: BatchReport = from_value(error.details.expect("report")).unwrap();
+    assert_eq!(report.completed, 0);
+
+    // nothing ran, not even the backup
+    any_mock.assert_hits(0);
+
+    common::teardown_database();
+}
+
+#[tokio::test]
+#[serial]
+async fn test_batch_rolled_back_events() {
+    if !common::is_database_running() {
+        eprintln!("Warning: Database not running, skipping test");
+        return;
+    }
+    // the atoms are counted by the memory backend
+    common::setup("http://127.0.0.1:1");
+    env::set_var("METTA_KG_SPACE_BACKEND", "memory");
+
+    let token = common::create_test_token("/test/", true, true);
+    let auth = || Header::new("authorization", token.code.clone());
+
+    let client = Client::tracked(rocket())
+        .await
+        .expect("valid rocket instance");
+
+    let response = client
+        .post("/quotas/test")
+        .header(auth())
+        .json(&QuotaInput {
+            max_atoms: Some(2),
+            ..Default::default()
+        })
+        .dispatch()
+        .await;
+    assert_eq!(response.status(), Status::Ok);
+
+    let mut events = client
+        .get("/spaces/_events/test")
+        .header(auth())
+        .dispatch()
+        .await;
+    assert_eq!(events.status(), Status::Ok);
+
+    // the second upload exceeds the quota
+    let operations = vec![
+        BatchOperation::Upload {
+            namespace: "/test/staging/".to_string(),
+            data: "(raw 1) (raw 2)".to_string(),
+        },
+        BatchOperation::Upload {
+            namespace: "/test/staging/".to_string(),
+            data: "(raw 3)".to_string(),
+        },
+    ];
+
+    let response = client
+        .post("/spaces/batch")
+        .header(auth())
+        .json(&operations)
+        .dispatch()
+        .await;
+    assert_eq!(response.status(), Status::PayloadTooLarge);
+    let error: ErrorBody = response.into_json().await.expect("error");
+    let report: BatchReport = from_value(error.details.expect("report")).unwrap();
+    assert_eq!(report.completed, 1);
+    assert!(report.rolled_back);
+
+    // the completed upload is reported as failed, not finished
+    let mut received = String::new();
+    let mut buffer = [0u8; 1024];
+    while !received.contains("Rolled back") {
+        let read = tokio::time::timeout(Duration::from_secs(5), events.read(&mut buffer))
+            .await
+            .expect("event before timeout")
+            .expect("readable stream");
+        received.push_str(&String::from_utf8_lossy(&buffer[..read]));
+    }
+    assert_eq!(received.matches("event:failed").count(), 2);
+    assert!(!received.contains("event:finished"));
+
+    let response = client
+        .get("/spaces/test/staging")
+        .header(auth())
+        .dispatch()
+        .await;
+    let data: String = response.into_json().await.expect("atoms");
+    assert!(data.is_empty());
+
+    env::remove_var("METTA_KG_SPACE_BACKEND");
+    common::teardown_database();
+}
+
+#[tokio::test]
+#[serial]
+async fn test_batch_keeps_backup_when_rollback_fails() {
+    if !common::is_database_running() {
+        eprintln!("Warning: Database not running, skipping test");
+        return;
+    }
+    let server = MockServer::start();
+    common::setup(&server.base_url());
+    env::set_var("METTA_KG_MORK_TIMEOUT_UPLOAD_MS", "100");
+    env::set_var("METTA_KG_MORK_TIMEOUT_COPY_MS", "100");
+
+    let token = common::create_test_token("/test/", true, true);
+
+    server.mock(|when, then| {
+        when.method(POST).path("/transform");
+        then.status(200).body("Transform successful");
+    });
+    server.mock(|when, then| {
+        when.method(POST)
+            .path_matches(Regex::new(r"/upload/.*").unwrap());
+        then.delay(Duration::from_secs(1));
+    });
+    // the restore can not clear the spaces
+    server.mock(|when, then| {
+        when.method(GET)
+            .path_matches(Regex::new(r"/clear/.*").unwrap());
+        then.delay(Duration::from_secs(1));
+    });
+
+    let client = Client::tracked(rocket())
+        .await
+        .expect("valid rocket instance");
+
+    let response = client
+        .post("/spaces/batch")
+        .header(Header::new("authorization", token.code.clone()))
+        .json(&etl_operations("/test/staging/"))
+        .dispatch()
+        .await;
+
+    env::remove_var("METTA_KG_MORK_TIMEOUT_UPLOAD_MS");
+    env::remove_var("METTA_KG_MORK_TIMEOUT_COPY_MS");
+
+    assert_eq!(response.status(), Status::InternalServerError);
+    let error: ErrorBody = response.into_json().await.expect("error");
+    let report: BatchReport = from_value(error.details.expect("report")).unwrap();
+    assert_eq!(report.completed, 0);
+    assert!(!report.rolled_back);
+    let backup = report.backup.expect("the kept backup");
+    assert!(backup.starts_with("/_snapshots/b"));
+
+    common::teardown_database();
+}
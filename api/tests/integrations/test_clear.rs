@@ -6,6 +6,7 @@ use rocket::local::asynchronous::Client;
 use serial_test::serial;
 
 use crate::integrations::common;
+use api::routes::spaces::ClearPreview;
 
 #[tokio::test]
 #[serial]
@@ -212,3 +213,138 @@ async fn test_namespace_mismatch() {
 
     common::teardown_database();
 }
+
+#[tokio::test]
+#[serial]
+async fn test_clear_dry_run() {
+    if !common::is_database_running() {
+        eprintln!("Warning: Database not running, skipping test");
+        return;
+    }
+    let server = MockServer::start();
+    common::setup(&server.base_url());
+
+    let token = common::create_test_token("/test/", true, true);
+
+    server.mock(|when, then| {
+        when.method(GET)
+            .path_matches(Regex::new(r"/export/.*").unwrap());
+        then.status(200).body("(staging 1)\n(staging 2)\n");
+    });
+    let clear_mock = server.mock(|when, then| {
+        when.method(GET)
+            .path_matches(Regex::new(r"/clear/.*").unwrap());
+        then.status(200).body("Clear successful");
+    });
+
+    let client = Client::tracked(rocket())
+        .await
+        .expect("valid rocket instance");
+
+    let response = client
+        .post("/spaces/clear/test/space?expr=(staging%20$x)&dry_run=true")
+        .header(Header::new("authorization", token.code.clone()))
+        .dispatch()
+        .await;
+
+    assert_eq!(response.status(), Status::Ok);
+    let preview: ClearPreview = response.into_json().await.expect("preview");
+    assert_eq!(preview.total, 2);
+    assert_eq!(preview.atoms, vec!["(staging 1)", "(staging 2)"]);
+    clear_mock.assert_hits(0);
+
+    common::teardown_database();
+}
+
+#[tokio::test]
+#[serial]
+async fn test_clear_dry_run_needs_read() {
+    if !common::is_database_running() {
+        eprintln!("Warning: Database not running, skipping test");
+        return;
+    }
+    let server = MockServer::start();
+    common::setup(&server.base_url());
+
+    let token = common::create_test_token("/test/", false, true);
+
+    let export_mock = server.mock(|when, then| {
+        when.method(GET)
+            .path_matches(Regex::new(r"/export/.*").unwrap());
+        then.status(200).body("(staging 1)\n(staging 2)\n");
+    });
+    let clear_mock = server.mock(|when, then| {
+        when.method(GET)
+            .path_matches(Regex::new(r"/clear/.*").unwrap());
+        then.status(200).body("Clear successful");
+    });
+
+    let client = Client::tracked(rocket())
+        .await
+        .expect("valid rocket instance");
+
+    let response = client
+        .post("/spaces/clear/test/space?expr=(staging%20$x)&dry_run=true")
+        .header(Header::new("authorization", token.code.clone()))
+        .dispatch()
+        .await;
+    assert_eq!(response.status(), Status::Unauthorized);
+
+    let response = client
+        .post("/spaces/clear/test/space?expr=$x&expected_count=2")
+        .header(Header::new("authorization", token.code.clone()))
+        .dispatch()
+        .await;
+    assert_eq!(response.status(), Status::Unauthorized);
+
+    export_mock.assert_hits(0);
+    clear_mock.assert_hits(0);
+
+    common::teardown_database();
+}
+
+#[tokio::test]
+#[serial]
+async fn test_clear_expected_count() {
+    if !common::is_database_running() {
+        eprintln!("Warning: Database not running, skipping test");
+        return;
+    }
+    let server = MockServer::start();
+    common::setup(&server.base_url());
+
+    let token = common::create_test_token("/test/", true, true);
+
+    server.mock(|when, then| {
+        when.method(GET)
+            .path_matches(Regex::new(r"/export/.*").unwrap());
+        then.status(200).body("(staging 1)\n(staging 2)\n");
+    });
+    let clear_mock = server.mock(|when, then| {
+        when.method(GET)
+            .path_matches(Regex::new(r"/clear/.*").unwrap());
+        then.status(200).body("Clear successful");
+    });
+
+    let client = Client::tracked(rocket())
+        .await
+        .expect("valid rocket instance");
+
+    let response = client
+        .post("/spaces/clear/test/space?expr=$x&expected_count=1")
+        .header(Header::new("authorization", token.code.clone()))
+        .dispatch()
+        .await;
+    assert_eq!(response.status(), Status::PreconditionFailed);
+    clear_mock.assert_hits(0);
+
+    let response = client
+        .post("/spaces/clear/test/space?expr=$x&expected_count=2")
+        .header(Header::new("authorization", token.code.clone()))
+        .dispatch()
+        .await;
+    assert_eq!(response.status(), Status::Ok);
+    clear_mock.assert_hits(1);
+
+    common::teardown_database();
+}
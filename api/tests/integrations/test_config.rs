@@ -0,0 +1,112 @@
+use api::config::Config;
+use api::errors::ErrorBody;
+use api::rocket;
+use httpmock::prelude::*;
+use httpmock::Regex;
+use rocket::http::{Header, Status};
+use rocket::local::asynchronous::Client;
+use serial_test::serial;
+use std::env;
+use std::time::Duration;
+
+use crate::integrations::common;
+
+#[tokio::test]
+#[serial]
+async fn test_upload_limit_from_env() {
+    if !common::is_database_running() {
+        eprintln!("Warning: Database not running, skipping test");
+        return;
+    }
+    let server = MockServer::start();
+    common::setup(&server.base_url());
+    env::set_var("METTA_KG_UPLOAD_LIMIT", "1 KiB");
+
+    let token = common::create_test_token("/test/", true, true);
+
+    let upload = server.mock(|when, then| {
+        when.method(POST)
+            .path_matches(Regex::new(r"/upload/.*").unwrap());
+        then.status(200).body("Upload successful");
+    });
+
+    let client = Client::tracked(rocket())
+        .await
+        .expect("valid rocket instance");
+
+    let response = client
+        .post("/spaces/upload/test/space")
+        .header(Header::new("authorization", token.code.clone()))
+        .body("(test atom)\n".repeat(100))
+        .dispatch()
+        .await;
+
+    assert_eq!(response.status(), Status::PayloadTooLarge);
+    let error: ErrorBody = response.into_json().await.expect("error body");
+    assert_eq!(error.code, "payload_too_large");
+    upload.assert_hits(0);
+
+    let response = client
+        .post("/spaces/upload/test/space")
+        .header(Header::new("authorization", token.code.clone()))
+        .body("(test atom)")
+        .dispatch()
+        .await;
+
+    assert_eq!(response.status(), Status::Ok);
+    upload.assert_hits(1);
+
+    env::remove_var("METTA_KG_UPLOAD_LIMIT");
+    common::teardown_database();
+}
+
+#[test]
+#[serial]
+fn test_invalid_config_is_rejected() {
+    let config = Config::load().expect("the defaults are valid");
+    assert_eq!(config.db_port, 5432);
+
+    env::set_var("METTA_KG_DB_PORT", "6543");
+    assert_eq!(Config::load().unwrap().db_port, 6543);
+    env::remove_var("METTA_KG_DB_PORT");
+
+    env::set_var("METTA_KG_MORK_TIMEOUT_UPLOAD_MS", "100");
+    let config = Config::load().unwrap();
+    assert_eq!(
+        config.mork_operation_timeout("upload"),
+        Duration::from_millis(100)
+    );
+    assert_eq!(
+        config.mork_operation_timeout("import"),
+        Duration::from_secs(120)
+    );
+    env::remove_var("METTA_KG_MORK_TIMEOUT_UPLOAD_MS");
+
+    for (name, value) in [
+        ("METTA_KG_MORK_URL", "not a url"),
+        ("METTA_KG_MORK_URL", "ftp://mork:8001"),
+        ("METTA_KG_CORS_ORIGINS", "[\"localhost\"]"),
+        ("METTA_KG_DB_POOL_SIZE", "0"),
+        ("METTA_KG_DB_PORT", "port"),
+        ("METTA_KG_MORK_TIMEOUT_READ_MS", "0"),
+        ("METTA_KG_MORK_TIMEOUT_FETCH_MS", "100"),
+        ("METTA_KG_WEBHOOK_RETRY_DELAY_MS", "0"),
+        ("METTA_KG_SECRET", ""),
+        ("METTA_KG_METRICS_TOKEN", ""),
+        ("METTA_KG_WEBHOOK_ALLOWED_HOSTS", "[\"\"]"),
+        ("METTA_KG_RATE_LIMIT_WRITE", "0"),
+        ("METTA_KG_CONCURRENCY_LIMIT_READ", "-1"),
+        ("METTA_KG_LOG_FORMAT", "xml"),
+        ("METTA_KG_LOG", "api=loud"),
+    ] {
+        let previous = env::var(name).ok();
+        env::set_var(name, value);
+
+        assert!(Config::load().is_err(), "{name}={value}");
+
+        match previous {
+            Some(previous) => env::set_var(name, previous),
+            None => env::remove_var(name),
+        }
+    }
+}
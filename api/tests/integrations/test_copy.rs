@@ -0,0 +1,277 @@
+use api::db::establish_connection;
+use api::errors::ErrorBody;
+use api::model::{Token, TokenInsert};
+use api::rocket;
+use api::routes::spaces::{RelocateInput, RelocateReport};
+use api::schema::tokens;
+use chrono::Utc;
+use diesel::prelude::*;
+use httpmock::prelude::*;
+use httpmock::Regex;
+use rocket::http::{Header, Status};
+use rocket::local::asynchronous::Client;
+use rocket::serde::json::from_value;
+use serial_test::serial;
+use std::env;
+use std::time::Duration;
+
+use crate::integrations::common;
+
+fn create_child_token(parent: &Token, namespace: &str) -> Token {
+    let conn = &mut establish_connection();
+
+    let token_insert = TokenInsert {
+        code: format!("child_token_{}", Utc::now().timestamp_nanos_opt().unwrap()),
+        description: "Child token".to_string(),
+        namespace: namespace.to_string(),
+        creation_timestamp: Utc::now().naive_utc(),
+        permission_read: true,
+        permission_write: false,
+        permission_share_share: false,
+        permission_share_read: false,
+        permission_share_write: false,
+        parent: Some(parent.id),
+        rate_limit_read: None,
+        rate_limit_write: None,
+        rate_limit_translate: None,
+        concurrency_limit_read: None,
+        concurrency_limit_write: None,
+        concurrency_limit_translate: None,
+    };
+
+    diesel::insert_into(tokens::table)
+        .values(&token_insert)
+        .get_result(conn)
+        .expect("Failed to insert child token")
+}
+
+fn relocate_input(source: &str, target: &str, retarget_tokens: bool) -> RelocateInput {
+    RelocateInput {
+        source: source.to_string(),
+        target: target.to_string(),
+        retarget_tokens,
+    }
+}
+
+#[tokio::test]
+#[serial]
+async fn test_copy_success() {
+    if !common::is_database_running() {
+        eprintln!("Warning: Database not running, skipping test");
+        return;
+    }
+    let server = MockServer::start();
+    common::setup(&server.base_url());
+
+    let token = common::create_test_token("/test/", true, true);
+
+    let copy_mock = server.mock(|when, then| {
+        when.method(POST)
+            .path("/transform")
+            .body("(transform (, (test (raw $x))) (, (test (curated $x))))");
+        then.status(200).body("Transform successful");
+    });
+    let retag_mock = server.mock(|when, then| {
+        when.method(POST).path("/transform").body(
+            "(transform (, (test (curated (rawa727d4f9-836a-4e4c-9480 $x)))) \
+             (, (test (curated (curateda727d4f9-836a-4e4c-9480 $x)))))",
+        );
+        then.status(200).body("Transform successful");
+    });
+    let clear_mock = server.mock(|when, then| {
+        when.method(GET)
+            .path_matches(Regex::new(r"/clear/.*").unwrap());
+        then.status(200).body("Clear successful");
+    });
+
+    let client = Client::tracked(rocket())
+        .await
+        .expect("valid rocket instance");
+
+    let response = client
+        .post("/spaces/copy")
+        .header(Header::new("authorization", token.code.clone()))
+        .json(&relocate_input("/test/raw/", "/test/curated/", false))
+        .dispatch()
+        .await;
+
+    assert_eq!(response.status(), Status::Ok);
+    copy_mock.assert();
+    retag_mock.assert();
+    clear_mock.assert_hits(1);
+
+    common::teardown_database();
+}
+
+#[tokio::test]
+#[serial]
+async fn test_move_retargets_tokens() {
+    if !common::is_database_running() {
+        eprintln!("Warning: Database not running, skipping test");
+        return;
+    }
+    let server = MockServer::start();
+    common::setup(&server.base_url());
+
+    let token = common::create_test_token("/test/", true, true);
+    let child = create_child_token(&token, "/test/old/sub/");
+    let unrelated = create_child_token(&token, "/test/older/");
+
+    server.mock(|when, then| {
+        when.method(POST).path("/transform");
+        then.status(200).body("Transform successful");
+    });
+    let clear_mock = server.mock(|when, then| {
+        when.method(GET)
+            .path_matches(Regex::new(r"/clear/.*").unwrap());
+        then.status(200).body("Clear successful");
+    });
+
+    let client = Client::tracked(rocket())
+        .await
+        .expect("valid rocket instance");
+
+    let response = client
+        .post("/spaces/move")
+        .header(Header::new("authorization", token.code.clone()))
+        .json(&relocate_input("/test/old/", "/test/new/", true))
+        .dispatch()
+        .await;
+
+    assert_eq!(response.status(), Status::Ok);
+    // the stale data tag and the source are cleared
+    clear_mock.assert_hits(2);
+
+    let conn = &mut establish_connection();
+    let namespace_of = |id: i32, conn: &mut PgConnection| {
+        tokens::table
+            .find(id)
+            .select(tokens::namespace)
+            .first::<String>(conn)
+            .unwrap()
+    };
+    assert_eq!(namespace_of(child.id, conn), "/test/new/sub/");
+    assert_eq!(namespace_of(unrelated.id, conn), "/test/older/");
+
+    common::teardown_database();
+}
+
+#[tokio::test]
+#[serial]
+async fn test_move_keeps_tokens_when_source_is_not_cleared() {
+    if !common::is_database_running() {
+        eprintln!("Warning: Database not running, skipping test");
+        return;
+    }
+    let server = MockServer::start();
+    common::setup(&server.base_url());
+
+    let token = common::create_test_token("/test/", true, true);
+    let child = create_child_token(&token, "/test/old/space/");
+
+    server.mock(|when, then| {
+        when.method(POST).path("/transform");
+        then.status(200).body("Transform successful");
+    });
+    // the spaces share their name, so the only clear is the one of the source,
+    // which times out
+    server.mock(|when, then| {
+        when.method(GET)
+            .path_matches(Regex::new(r"/clear/.*").unwrap());
+        then.status(200)
+            .delay(Duration::from_millis(500))
+            .body("Clear successful");
+    });
+
+    env::set_var("METTA_KG_MORK_TIMEOUT_COPY_MS", "100");
+    env::set_var("METTA_KG_MORK_RETRY_DELAY_MS", "10");
+    let client = Client::tracked(rocket())
+        .await
+        .expect("valid rocket instance");
+    env::remove_var("METTA_KG_MORK_TIMEOUT_COPY_MS");
+    env::remove_var("METTA_KG_MORK_RETRY_DELAY_MS");
+
+    let response = client
+        .post("/spaces/move")
+        .header(Header::new("authorization", token.code.clone()))
+        .json(&relocate_input(
+            "/test/old/space/",
+            "/test/new/space/",
+            true,
+        ))
+        .dispatch()
+        .await;
+
+    assert_eq!(response.status(), Status::ServiceUnavailable);
+    let error: ErrorBody = response.into_json().await.expect("error body");
+    assert!(error.message.contains("failed to clear the source"));
+    let report: RelocateReport = from_value(error.details.expect("report")).unwrap();
+    assert!(report.copied);
+    assert!(!report.source_cleared);
+
+    let conn = &mut establish_connection();
+    let namespace = tokens::table
+        .find(child.id)
+        .select(tokens::namespace)
+        .first::<String>(conn)
+        .unwrap();
+    assert_eq!(namespace, "/test/old/space/");
+
+    common::teardown_database();
+}
+
+#[tokio::test]
+#[serial]
+async fn test_copy_into_itself() {
+    if !common::is_database_running() {
+        eprintln!("Warning: Database not running, skipping test");
+        return;
+    }
+    let server = MockServer::start();
+    common::setup(&server.base_url());
+
+    let token = common::create_test_token("/test/", true, true);
+
+    let client = Client::tracked(rocket())
+        .await
+        .expect("valid rocket instance");
+
+    let response = client
+        .post("/spaces/copy")
+        .header(Header::new("authorization", token.code.clone()))
+        .json(&relocate_input("/test/raw/", "/test/raw/copy/", false))
+        .dispatch()
+        .await;
+
+    assert_eq!(response.status(), Status::BadRequest);
+
+    common::teardown_database();
+}
+
+#[tokio::test]
+#[serial]
+async fn test_move_namespace_mismatch() {
+    if !common::is_database_running() {
+        eprintln!("Warning: Database not running, skipping test");
+        return;
+    }
+    let server = MockServer::start();
+    common::setup(&server.base_url());
+
+    let token = common::create_test_token("/test/", true, true);
+
+    let client = Client::tracked(rocket())
+        .await
+        .expect("valid rocket instance");
+
+    let response = client
+        .post("/spaces/move")
+        .header(Header::new("authorization", token.code.clone()))
+        .json(&relocate_input("/test/raw/", "/other/raw/", false))
+        .dispatch()
+        .await;
+
+    assert_eq!(response.status(), Status::Unauthorized);
+
+    common::teardown_database();
+}
@@ -0,0 +1,157 @@
+use api::rocket;
+use api::routes::spaces::{DiffLine, DIFF_PAGE};
+use httpmock::prelude::*;
+use rocket::http::{Header, Status};
+use rocket::local::asynchronous::Client;
+use rocket::serde::json::from_str;
+use serial_test::serial;
+
+use crate::integrations::common;
+
+fn parse_lines(body: &str) -> Vec<DiffLine> {
+    body.lines()
+        .map(|line| from_str(line).expect("diff line"))
+        .collect()
+}
+
+fn mock_exports(server: &MockServer) {
+    server.mock(|when, then| {
+        when.method(GET).path_contains("left");
+        then.status(200).body("(a 1)\n(b \"x y\")\n(c (d 2))\n");
+    });
+    server.mock(|when, then| {
+        when.method(GET).path_contains("right");
+        then.status(200).body("(b \"x y\")\n(c (d 2))\n(e 3)\n");
+    });
+}
+
+#[tokio::test]
+#[serial]
+async fn test_diff_success() {
+    if !common::is_database_running() {
+        eprintln!("Warning: Database not running, skipping test");
+        return;
+    }
+    let server = MockServer::start();
+    common::setup(&server.base_url());
+
+    let token = common::create_test_token("/test/", true, false);
+    mock_exports(&server);
+
+    let client = Client::tracked(rocket())
+        .await
+        .expect("valid rocket instance");
+
+    let response = client
+        .get("/spaces/_diff?left=/test/left/&right=/test/right/")
+        .header(Header::new("authorization", token.code.clone()))
+        .dispatch()
+        .await;
+
+    assert_eq!(response.status(), Status::Ok);
+    assert_eq!(
+        response
+            .content_type()
+            .map(|content_type| content_type.to_string()),
+        Some("application/x-ndjson".to_string())
+    );
+    let body = response.into_string().await.expect("body");
+    assert_eq!(
+        parse_lines(&body),
+        vec![
+            DiffLine::Removed {
+                atom: "(a 1)".to_string()
+            },
+            DiffLine::Added {
+                atom: "(e 3)".to_string()
+            },
+            DiffLine::Summary {
+                removed: 1,
+                added: 1,
+                shared: 2
+            },
+        ]
+    );
+
+    common::teardown_database();
+}
+
+#[tokio::test]
+#[serial]
+async fn test_diff_large_spaces() {
+    if !common::is_database_running() {
+        eprintln!("Warning: Database not running, skipping test");
+        return;
+    }
+    let server = MockServer::start();
+    common::setup(&server.base_url());
+
+    let token = common::create_test_token("/test/", true, false);
+
+    // the sides overlap on half of their atoms, and differ on several pages
+    let size = 3 * DIFF_PAGE;
+    let left: String = (0..size).map(|i| format!("(a {i})\n")).collect();
+    let right: String = (size / 2..size + size / 2)
+        .map(|i| format!("(a {i})\n"))
+        .collect();
+    server.mock(|when, then| {
+        when.method(GET).path_contains("left");
+        then.status(200).body(left);
+    });
+    server.mock(|when, then| {
+        when.method(GET).path_contains("right");
+        then.status(200).body(right);
+    });
+
+    let client = Client::tracked(rocket())
+        .await
+        .expect("valid rocket instance");
+
+    let response = client
+        .get("/spaces/_diff?left=/test/left/&right=/test/right/")
+        .header(Header::new("authorization", token.code.clone()))
+        .dispatch()
+        .await;
+
+    assert_eq!(response.status(), Status::Ok);
+    let body = response.into_string().await.expect("body");
+    let lines = parse_lines(&body);
+    assert_eq!(lines.len(), size + 1);
+    assert_eq!(
+        lines.last(),
+        Some(&DiffLine::Summary {
+            removed: size / 2,
+            added: size / 2,
+            shared: size / 2
+        })
+    );
+
+    common::teardown_database();
+}
+
+#[tokio::test]
+#[serial]
+async fn test_diff_namespace_mismatch() {
+    if !common::is_database_running() {
+        eprintln!("Warning: Database not running, skipping test");
+        return;
+    }
+    let server = MockServer::start();
+    common::setup(&server.base_url());
+
+    let token = common::create_test_token("/test/", true, false);
+
+    let client = Client::tracked(rocket())
+        .await
+        .expect("valid rocket instance");
+
+    let response = client
+        .get("/spaces/_diff?left=/test/left/&right=/other/right/")
+        .header(Header::new("authorization", token.code.clone()))
+        .dispatch()
+        .await;
+
+    assert_eq!(response.status(), Status::Unauthorized);
+
+    common::teardown_database();
+}
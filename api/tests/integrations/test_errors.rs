@@ -0,0 +1,86 @@
+use api::errors::ErrorBody;
+use api::rocket;
+use rocket::http::{Header, Status};
+use rocket::local::asynchronous::Client;
+use serial_test::serial;
+
+use crate::integrations::common;
+
+#[tokio::test]
+#[serial]
+async fn test_errors_are_json() {
+    if !common::is_database_running() {
+        eprintln!("Warning: Database not running, skipping test");
+        return;
+    }
+    common::setup("http://127.0.0.1:1");
+
+    let client = Client::tracked(rocket())
+        .await
+        .expect("valid rocket instance");
+
+    let response = client.get("/v1/tokens").dispatch().await;
+    assert_eq!(response.status(), Status::Unauthorized);
+    let error: ErrorBody = response.into_json().await.expect("error body");
+    assert_eq!(error.code, "missing_token");
+
+    let response = client
+        .get("/v1/tokens")
+        .header(Header::new("authorization", "not-a-token"))
+        .dispatch()
+        .await;
+    assert_eq!(response.status(), Status::Unauthorized);
+    let error: ErrorBody = response.into_json().await.expect("error body");
+    assert_eq!(error.code, "invalid_token");
+
+    let response = client.get("/v1/nothing-here").dispatch().await;
+    assert_eq!(response.status(), Status::NotFound);
+    let error: ErrorBody = response.into_json().await.expect("error body");
+    assert_eq!(error.code, "not_found");
+    assert!(error.details.is_none());
+
+    common::teardown_database();
+}
+
+#[tokio::test]
+#[serial]
+async fn test_unversioned_aliases_are_deprecated() {
+    if !common::is_database_running() {
+        eprintln!("Warning: Database not running, skipping test");
+        return;
+    }
+    common::setup("http://127.0.0.1:1");
+
+    let token = common::create_test_token("/test/", true, true);
+
+    let client = Client::tracked(rocket())
+        .await
+        .expect("valid rocket instance");
+
+    let response = client
+        .get("/v1/token")
+        .header(Header::new("authorization", token.code.clone()))
+        .dispatch()
+        .await;
+    assert_eq!(response.status(), Status::Ok);
+    assert!(response.headers().get_one("Deprecation").is_none());
+
+    let response = client
+        .get("/token")
+        .header(Header::new("authorization", token.code.clone()))
+        .dispatch()
+        .await;
+    assert_eq!(response.status(), Status::Ok);
+    assert_eq!(response.headers().get_one("Deprecation"), Some("true"));
+    assert_eq!(
+        response.headers().get_one("Link"),
+        Some("</v1/token>; rel=\"successor-version\"")
+    );
+
+    // health checks are not versioned
+    let response = client.get("/healthz").dispatch().await;
+    assert_eq!(response.status(), Status::Ok);
+    assert!(response.headers().get_one("Deprecation").is_none());
+
+    common::teardown_database();
+}
@@ -0,0 +1,243 @@
+use api::rocket;
+use httpmock::prelude::*;
+use httpmock::Regex;
+use rocket::http::{Header, Status};
+use rocket::local::asynchronous::{Client, LocalResponse};
+use rocket::tokio::io::AsyncReadExt;
+use serial_test::serial;
+use std::env;
+use std::time::Duration;
+
+use crate::integrations::common;
+
+/// Reads the event stream until `needle` shows up
+async fn read_until(response: &mut LocalResponse<'_>, needle: &str) -> String {
+    let mut received = String::new();
+    let mut buffer = [0u8; 1024];
+
+    while !received.contains(needle) {
+        let read = tokio::time::timeout(Duration::from_secs(5), response.read(&mut buffer))
+            .await
+            .expect("event before timeout")
+            .expect("readable stream");
+        received.push_str(&String::from_utf8_lossy(&buffer[..read]));
+    }
+
+    received
+}
+
+#[tokio::test]
+#[serial]
+async fn test_events_for_upload() {
+    if !common::is_database_running() {
+        eprintln!("Warning: Database not running, skipping test");
+        return;
+    }
+    let server = MockServer::start();
+    common::setup(&server.base_url());
+
+    let token = common::create_test_token("/test/", true, true);
+
+    server.mock(|when, then| {
+        when.method(POST)
+            .path_matches(Regex::new(r"/upload/.*").unwrap());
+        then.status(200).body("Upload successful");
+    });
+
+    let client = Client::tracked(rocket())
+        .await
+        .expect("valid rocket instance");
+
+    let mut events = client
+        .get("/spaces/_events/test")
+        .header(Header::new("authorization", token.code.clone()))
+        .dispatch()
+        .await;
+    assert_eq!(events.status(), Status::Ok);
+
+    let response = client
+        .post("/spaces/upload/test/space")
+        .header(Header::new("authorization", token.code.clone()))
+        .body("(a b) (c d)")
+        .dispatch()
+        .await;
+    assert_eq!(response.status(), Status::Ok);
+
+    let received = read_until(&mut events, "event:finished").await;
+    assert!(received.contains("event:started"));
+    assert!(received.contains("\"operation\":\"upload\""));
+    assert!(received.contains("\"namespace\":\"/test/space\""));
+    assert!(received.contains(&format!("\"token_id\":{}", token.id)));
+    assert!(received.contains("\"count\":2"));
+
+    common::teardown_database();
+}
+
+#[tokio::test]
+#[serial]
+async fn test_events_skip_other_namespaces() {
+    if !common::is_database_running() {
+        eprintln!("Warning: Database not running, skipping test");
+        return;
+    }
+    let server = MockServer::start();
+    common::setup(&server.base_url());
+
+    let token = common::create_test_token("/", true, true);
+
+    server.mock(|when, then| {
+        when.method(GET)
+            .path_matches(Regex::new(r"/clear/.*").unwrap());
+        then.status(200).body("Clear successful");
+    });
+
+    let client = Client::tracked(rocket())
+        .await
+        .expect("valid rocket instance");
+
+    let mut events = client
+        .get("/spaces/_events/ns1")
+        .header(Header::new("authorization", token.code.clone()))
+        .dispatch()
+        .await;
+
+    for path in [
+        "/spaces/clear/ns2/space?expr=$x",
+        "/spaces/clear/ns1/space?expr=$x",
+    ] {
+        let response = client
+            .post(path)
+            .header(Header::new("authorization", token.code.clone()))
+            .dispatch()
+            .await;
+        assert_eq!(response.status(), Status::Ok);
+    }
+
+    let received = read_until(&mut events, "event:finished").await;
+    assert!(received.contains("\"namespace\":\"/ns1/space\""));
+    assert!(!received.contains("/ns2/"));
+
+    common::teardown_database();
+}
+
+#[tokio::test]
+#[serial]
+async fn test_events_namespace_mismatch() {
+    if !common::is_database_running() {
+        eprintln!("Warning: Database not running, skipping test");
+        return;
+    }
+    let server = MockServer::start();
+    common::setup(&server.base_url());
+
+    let token = common::create_test_token("/test/", true, true);
+
+    let client = Client::tracked(rocket())
+        .await
+        .expect("valid rocket instance");
+
+    let response = client
+        .get("/spaces/_events/other")
+        .header(Header::new("authorization", token.code.clone()))
+        .dispatch()
+        .await;
+
+    assert_eq!(response.status(), Status::Unauthorized);
+
+    common::teardown_database();
+}
+
+#[tokio::test]
+#[serial]
+async fn test_events_count_of_mork_writes() {
+    if !common::is_database_running() {
+        eprintln!("Warning: Database not running, skipping test");
+        return;
+    }
+    let server = MockServer::start();
+    common::setup(&server.base_url());
+
+    let token = common::create_test_token("/test/", true, true);
+
+    server.mock(|when, then| {
+        when.method(GET)
+            .path_matches(Regex::new(r"/clear/.*").unwrap());
+        then.status(200).body("Clear successful");
+    });
+    // MORK does not report the atoms removed, so the space is counted
+    // before and after
+    let count = server.mock(|when, then| {
+        when.method(GET)
+            .path_matches(Regex::new(r"/count/.*").unwrap());
+        then.status(200).body("2");
+    });
+
+    let client = Client::tracked(rocket())
+        .await
+        .expect("valid rocket instance");
+
+    let mut events = client
+        .get("/spaces/_events/test")
+        .header(Header::new("authorization", token.code.clone()))
+        .dispatch()
+        .await;
+
+    let response = client
+        .post("/spaces/clear/test/space?expr=$x")
+        .header(Header::new("authorization", token.code.clone()))
+        .dispatch()
+        .await;
+    assert_eq!(response.status(), Status::Ok);
+
+    let received = read_until(&mut events, "\"kind\":\"finished\"").await;
+    assert!(received.contains("\"operation\":\"clear\""));
+    assert!(received.contains("\"count\":0"));
+    count.assert_hits(2);
+
+    common::teardown_database();
+}
+
+#[tokio::test]
+#[serial]
+async fn test_events_count_reported_by_backend() {
+    if !common::is_database_running() {
+        eprintln!("Warning: Database not running, skipping test");
+        return;
+    }
+    // no MORK is running
+    common::setup("http://127.0.0.1:1");
+    env::set_var("METTA_KG_SPACE_BACKEND", "memory");
+
+    let token = common::create_test_token("/test/", true, true);
+
+    let client = Client::tracked(rocket())
+        .await
+        .expect("valid rocket instance");
+    env::remove_var("METTA_KG_SPACE_BACKEND");
+
+    let response = client
+        .post("/spaces/upload/test/space")
+        .header(Header::new("authorization", token.code.clone()))
+        .body("(a 1)\n(a 2)\n(b 3)")
+        .dispatch()
+        .await;
+    assert_eq!(response.status(), Status::Ok);
+
+    let mut events = client
+        .get("/spaces/_events/test")
+        .header(Header::new("authorization", token.code.clone()))
+        .dispatch()
+        .await;
+
+    let response = client
+        .post("/spaces/clear/test/space?expr=(a%20$x)")
+        .header(Header::new("authorization", token.code.clone()))
+        .dispatch()
+        .await;
+    assert_eq!(response.status(), Status::Ok);
+
+    let received = read_until(&mut events, "\"kind\":\"finished\"").await;
+    assert!(received.contains("\"count\":2"));
+
+    common::teardown_database();
+}
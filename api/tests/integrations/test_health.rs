@@ -0,0 +1,101 @@
+use api::rocket;
+use api::routes::health::Readiness;
+use httpmock::prelude::*;
+use httpmock::Regex;
+use rocket::http::Status;
+use rocket::local::asynchronous::Client;
+use rocket::tokio::time::Duration;
+use serial_test::serial;
+use std::env;
+
+use crate::integrations::common;
+
+#[tokio::test]
+#[serial]
+async fn test_healthz() {
+    if !common::is_database_running() {
+        eprintln!("Warning: Database not running, skipping test");
+        return;
+    }
+    let server = MockServer::start();
+    common::setup(&server.base_url());
+
+    let client = Client::tracked(rocket())
+        .await
+        .expect("valid rocket instance");
+
+    let response = client.get("/healthz").dispatch().await;
+
+    assert_eq!(response.status(), Status::Ok);
+    assert_eq!(response.into_string().await.unwrap(), "OK");
+
+    common::teardown_database();
+}
+
+#[tokio::test]
+#[serial]
+async fn test_readyz() {
+    if !common::is_database_running() {
+        eprintln!("Warning: Database not running, skipping test");
+        return;
+    }
+    let server = MockServer::start();
+    common::setup(&server.base_url());
+
+    server.mock(|when, then| {
+        when.method(GET)
+            .path_matches(Regex::new(r"/export/.*").unwrap());
+        then.status(200).body("");
+    });
+
+    let client = Client::tracked(rocket())
+        .await
+        .expect("valid rocket instance");
+
+    let response = client.get("/readyz").dispatch().await;
+
+    assert_eq!(response.status(), Status::Ok);
+    let readiness: Readiness = response.into_json().await.unwrap();
+    assert!(readiness.ready);
+    assert!(readiness.database.up);
+    assert!(readiness.migrations.up);
+    assert!(readiness.mork.up);
+
+    common::teardown_database();
+}
+
+#[tokio::test]
+#[serial]
+async fn test_readyz_mork_down() {
+    if !common::is_database_running() {
+        eprintln!("Warning: Database not running, skipping test");
+        return;
+    }
+    let server = MockServer::start();
+    common::setup(&server.base_url());
+    env::set_var("METTA_KG_MORK_TIMEOUT_PROBE_MS", "100");
+
+    // MORK answers slower than the probe timeout
+    server.mock(|when, then| {
+        when.method(GET)
+            .path_matches(Regex::new(r"/export/.*").unwrap());
+        then.status(200).body("").delay(Duration::from_millis(500));
+    });
+
+    let client = Client::tracked(rocket())
+        .await
+        .expect("valid rocket instance");
+
+    let response = client.get("/readyz").dispatch().await;
+
+    assert_eq!(response.status(), Status::ServiceUnavailable);
+    let readiness: Readiness = response.into_json().await.unwrap();
+    assert!(!readiness.ready);
+    assert!(readiness.database.up);
+    assert!(readiness.migrations.up);
+    assert!(!readiness.mork.up);
+    assert!(readiness.mork.error.is_some());
+
+    env::remove_var("METTA_KG_MORK_TIMEOUT_PROBE_MS");
+    common::teardown_database();
+}
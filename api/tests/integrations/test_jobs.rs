@@ -0,0 +1,351 @@
+use api::db::establish_connection;
+use api::model::{Job, JobInsert, Snapshot};
+use api::rocket;
+use chrono::Utc;
+use diesel::RunQueryDsl;
+use httpmock::prelude::*;
+use httpmock::Regex;
+use rocket::http::{Header, Status};
+use rocket::local::asynchronous::Client;
+use serial_test::serial;
+use std::env;
+use std::time::Duration;
+
+use crate::integrations::common;
+use api::routes::spaces::Mm2InputMulti;
+
+async fn wait_for_job(client: &Client, code: &str, id: i32) -> Job {
+    for _ in 0..50 {
+        let response = client
+            .get(format!("/jobs/{id}"))
+            .header(Header::new("authorization", code.to_string()))
+            .dispatch()
+            .await;
+        assert_eq!(response.status(), Status::Ok);
+
+        let job: Job = response.into_json().await.expect("job");
+        if job.status != "pending" && job.status != "running" {
+            return job;
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+    panic!("job {id} did not finish");
+}
+
+#[tokio::test]
+#[serial]
+async fn test_background_transform_success() {
+    if !common::is_database_running() {
+        eprintln!("Warning: Database not running, skipping test");
+        return;
+    }
+    let server = MockServer::start();
+    common::setup(&server.base_url());
+
+    let token = common::create_test_token("/test/", true, true);
+
+    server.mock(|when, then| {
+        when.method(POST).path("/transform");
+        then.status(200).body("Transform successful");
+    });
+
+    let client = Client::tracked(rocket())
+        .await
+        .expect("valid rocket instance");
+
+    let mm2_input = Mm2InputMulti {
+        patterns: vec!["$x".to_string()],
+        templates: vec!["($x)".to_string()],
+    };
+
+    let response = client
+        .post("/spaces/transform/test/space?background=true")
+        .header(Header::new("authorization", token.code.clone()))
+        .json(&mm2_input)
+        .dispatch()
+        .await;
+
+    assert_eq!(response.status(), Status::Accepted);
+    let job: Job = response.into_json().await.expect("job");
+    assert_eq!(job.kind, "transform");
+    assert_eq!(job.namespace, "/test/space");
+
+    let job = wait_for_job(&client, &token.code, job.id).await;
+    assert_eq!(job.status, "succeeded");
+    assert_eq!(job.response.as_deref(), Some("Transform successful"));
+    assert!(job.finish_timestamp.is_some());
+
+    common::teardown_database();
+}
+
+#[tokio::test]
+#[serial]
+async fn test_background_snapshot_and_restore() {
+    if !common::is_database_running() {
+        eprintln!("Warning: Database not running, skipping test");
+        return;
+    }
+    let server = MockServer::start();
+    common::setup(&server.base_url());
+
+    let token = common::create_test_token("/test/", true, true);
+
+    server.mock(|when, then| {
+        when.method(POST).path("/transform");
+        then.status(200).body("Transform successful");
+    });
+    server.mock(|when, then| {
+        when.method(GET)
+            .path_matches(Regex::new(r"/clear/.*").unwrap());
+        then.status(200).body("Clear successful");
+    });
+
+    let client = Client::tracked(rocket())
+        .await
+        .expect("valid rocket instance");
+
+    let response = client
+        .post("/spaces/snapshot/test/space?name=v1&background=true")
+        .header(Header::new("authorization", token.code.clone()))
+        .dispatch()
+        .await;
+
+    assert_eq!(response.status(), Status::Accepted);
+    let job: Job = response.into_json().await.expect("job");
+    assert_eq!(job.kind, "snapshot");
+    let job = wait_for_job(&client, &token.code, job.id).await;
+    assert_eq!(job.status, "succeeded");
+
+    let response = client
+        .get("/snapshots/test")
+        .header(Header::new("authorization", token.code.clone()))
+        .dispatch()
+        .await;
+    let snapshots: Vec<Snapshot> = response.into_json().await.expect("snapshots");
+    assert_eq!(snapshots.len(), 1);
+
+    let response = client
+        .post(format!(
+            "/snapshots/{}/restore?background=true",
+            snapshots[0].id
+        ))
+        .header(Header::new("authorization", token.code.clone()))
+        .dispatch()
+        .await;
+
+    assert_eq!(response.status(), Status::Accepted);
+    let job: Job = response.into_json().await.expect("job");
+    assert_eq!(job.kind, "restore");
+    let job = wait_for_job(&client, &token.code, job.id).await;
+    assert_eq!(job.status, "succeeded");
+
+    common::teardown_database();
+}
+
+#[tokio::test]
+#[serial]
+async fn test_background_clear_failure() {
+    if !common::is_database_running() {
+        eprintln!("Warning: Database not running, skipping test");
+        return;
+    }
+    // nothing listens on this address, so the job fails
+    common::setup("http://127.0.0.1:1");
+
+    let token = common::create_test_token("/test/", true, true);
+
+    let client = Client::tracked(rocket())
+        .await
+        .expect("valid rocket instance");
+
+    let response = client
+        .post("/spaces/clear/test/space?expr=$x&background=true")
+        .header(Header::new("authorization", token.code.clone()))
+        .dispatch()
+        .await;
+
+    assert_eq!(response.status(), Status::Accepted);
+    let job: Job = response.into_json().await.expect("job");
+
+    let job = wait_for_job(&client, &token.code, job.id).await;
+    assert_eq!(job.status, "failed");
+    assert!(job.error.is_some());
+
+    common::teardown_database();
+}
+
+#[tokio::test]
+#[serial]
+async fn test_job_not_visible_to_other_token() {
+    if !common::is_database_running() {
+        eprintln!("Warning: Database not running, skipping test");
+        return;
+    }
+    let server = MockServer::start();
+    common::setup(&server.base_url());
+
+    let token1 = common::create_test_token("/ns1/", true, true);
+    let token2 = common::create_test_token("/ns2/", true, true);
+
+    server.mock(|when, then| {
+        when.method(GET)
+            .path_matches(Regex::new(r"/clear/.*").unwrap());
+        then.status(200).body("Clear successful");
+    });
+
+    let client = Client::tracked(rocket())
+        .await
+        .expect("valid rocket instance");
+
+    let response = client
+        .post("/spaces/clear/ns1/space?expr=$x&background=true")
+        .header(Header::new("authorization", token1.code.clone()))
+        .dispatch()
+        .await;
+    assert_eq!(response.status(), Status::Accepted);
+    let job: Job = response.into_json().await.expect("job");
+
+    let response = client
+        .get(format!("/jobs/{}", job.id))
+        .header(Header::new("authorization", token2.code.clone()))
+        .dispatch()
+        .await;
+    assert_eq!(response.status(), Status::NotFound);
+
+    let response = client
+        .delete(format!("/jobs/{}", job.id))
+        .header(Header::new("authorization", token2.code.clone()))
+        .dispatch()
+        .await;
+    assert_eq!(response.status(), Status::NotFound);
+
+    common::teardown_database();
+}
+
+#[tokio::test]
+#[serial]
+async fn test_cancel_finished_job() {
+    if !common::is_database_running() {
+        eprintln!("Warning: Database not running, skipping test");
+        return;
+    }
+    let server = MockServer::start();
+    common::setup(&server.base_url());
+
+    let token = common::create_test_token("/test/", true, true);
+
+    server.mock(|when, then| {
+        when.method(GET)
+            .path_matches(Regex::new(r"/clear/.*").unwrap());
+        then.status(200).body("Clear successful");
+    });
+
+    let client = Client::tracked(rocket())
+        .await
+        .expect("valid rocket instance");
+
+    let response = client
+        .post("/spaces/clear/test/space?expr=$x&background=true")
+        .header(Header::new("authorization", token.code.clone()))
+        .dispatch()
+        .await;
+    let job: Job = response.into_json().await.expect("job");
+    wait_for_job(&client, &token.code, job.id).await;
+
+    let response = client
+        .delete(format!("/jobs/{}", job.id))
+        .header(Header::new("authorization", token.code.clone()))
+        .dispatch()
+        .await;
+    assert_eq!(response.status(), Status::Conflict);
+
+    common::teardown_database();
+}
+
+#[tokio::test]
+#[serial]
+async fn test_jobs_interrupted_by_restart_fail() {
+    if !common::is_database_running() {
+        eprintln!("Warning: Database not running, skipping test");
+        return;
+    }
+    let server = MockServer::start();
+    common::setup(&server.base_url());
+
+    let token = common::create_test_token("/test/", true, true);
+
+    // a job left running by a previous process
+    let job: Job = diesel::insert_into(api::schema::jobs::table)
+        .values(&JobInsert {
+            token_id: token.id,
+            kind: "transform".to_string(),
+            namespace: "/test/space/".to_string(),
+            status: "running".to_string(),
+            creation_timestamp: Utc::now().naive_utc(),
+        })
+        .get_result(&mut establish_connection())
+        .expect("job");
+
+    let client = Client::tracked(rocket())
+        .await
+        .expect("valid rocket instance");
+
+    let response = client
+        .get(format!("/jobs/{}", job.id))
+        .header(Header::new("authorization", token.code.clone()))
+        .dispatch()
+        .await;
+    assert_eq!(response.status(), Status::Ok);
+
+    let job: Job = response.into_json().await.expect("job");
+    assert_eq!(job.status, "failed");
+    assert_eq!(job.error.as_deref(), Some("interrupted by restart"));
+    assert!(job.finish_timestamp.is_some());
+
+    common::teardown_database();
+}
+
+#[tokio::test]
+#[serial]
+async fn test_job_timeout_from_config() {
+    if !common::is_database_running() {
+        eprintln!("Warning: Database not running, skipping test");
+        return;
+    }
+    let server = MockServer::start();
+    common::setup(&server.base_url());
+    env::set_var("METTA_KG_JOB_TIMEOUT_MS", "100");
+
+    let token = common::create_test_token("/test/", true, true);
+
+    server.mock(|when, then| {
+        when.method(POST).path("/transform");
+        then.status(200)
+            .delay(Duration::from_millis(500))
+            .body("Transform successful");
+    });
+
+    let client = Client::tracked(rocket())
+        .await
+        .expect("valid rocket instance");
+    env::remove_var("METTA_KG_JOB_TIMEOUT_MS");
+
+    let mm2_input = Mm2InputMulti {
+        patterns: vec!["$x".to_string()],
+        templates: vec!["($x)".to_string()],
+    };
+
+    let response = client
+        .post("/spaces/transform/test/space?background=true")
+        .header(Header::new("authorization", token.code.clone()))
+        .json(&mm2_input)
+        .dispatch()
+        .await;
+    assert_eq!(response.status(), Status::Accepted);
+
+    let job: Job = response.into_json().await.expect("job");
+    let job = wait_for_job(&client, &token.code, job.id).await;
+    assert_eq!(job.status, "failed");
+
+    common::teardown_database();
+}
@@ -0,0 +1,145 @@
+use api::rocket;
+use api::routes::spaces::{Mm2Input, Mm2InputMulti, RelocateInput};
+use rocket::http::{Header, Status};
+use rocket::local::asynchronous::Client;
+use serial_test::serial;
+use std::collections::BTreeSet;
+use std::env;
+
+use crate::integrations::common;
+
+/// The atoms of a space, as read through the API
+async fn read(client: &Client, token: &str, path: &str) -> BTreeSet<String> {
+    let response = client
+        .get(format!("/spaces/{path}"))
+        .header(Header::new("authorization", token.to_string()))
+        .dispatch()
+        .await;
+    assert_eq!(response.status(), Status::Ok);
+
+    let data: String = response.into_json().await.expect("atoms");
+    data.lines().map(|atom| atom.to_string()).collect()
+}
+
+fn atoms(atoms: &[&str]) -> BTreeSet<String> {
+    atoms.iter().map(|atom| atom.to_string()).collect()
+}
+
+#[tokio::test]
+#[serial]
+async fn test_memory_backend() {
+    if !common::is_database_running() {
+        eprintln!("Warning: Database not running, skipping test");
+        return;
+    }
+    // no MORK is running
+    common::setup("http://127.0.0.1:1");
+    env::set_var("METTA_KG_SPACE_BACKEND", "memory");
+
+    let token = common::create_test_token("/test/", true, true);
+    let auth = || Header::new("authorization", token.code.clone());
+
+    let client = Client::tracked(rocket())
+        .await
+        .expect("valid rocket instance");
+
+    let response = client
+        .post("/spaces/upload/test/raw")
+        .header(auth())
+        .body("(person alice)\n(person bob)\n; a comment\n(likes alice \"bob b\")")
+        .dispatch()
+        .await;
+    assert_eq!(response.status(), Status::Ok);
+
+    let response = client
+        .post("/spaces/upload/test/raw/sub")
+        .header(auth())
+        .body("(person carol)")
+        .dispatch()
+        .await;
+    assert_eq!(response.status(), Status::Ok);
+
+    // the atoms of a subspace are not those of the space
+    assert_eq!(
+        read(&client, &token.code, "test/raw").await,
+        atoms(&["(likes alice \"bob b\")", "(person alice)", "(person bob)"])
+    );
+    assert_eq!(
+        read(&client, &token.code, "test/raw/sub").await,
+        atoms(&["(person carol)"])
+    );
+
+    let response = client
+        .post("/spaces/transform/test/raw")
+        .header(auth())
+        .json(&Mm2InputMulti {
+            patterns: vec!["(person $x)".to_string(), "(likes $x $y)".to_string()],
+            templates: vec!["(friends $y $x)".to_string()],
+        })
+        .dispatch()
+        .await;
+    assert_eq!(response.status(), Status::Ok);
+    assert!(read(&client, &token.code, "test/raw")
+        .await
+        .contains("(friends \"bob b\" alice)"));
+
+    let response = client
+        .post("/spaces/export/test/raw?format=Json")
+        .header(auth())
+        .json(&Mm2Input {
+            pattern: "(person $x)".to_string(),
+            template: "(name $x)".to_string(),
+        })
+        .dispatch()
+        .await;
+    assert_eq!(response.status(), Status::Ok);
+    let exported: String = response.into_json().await.expect("export");
+    assert_eq!(exported, r#"["(name alice)","(name bob)"]"#);
+
+    // the copied atoms of the space are retagged for the target
+    let response = client
+        .post("/spaces/copy")
+        .header(auth())
+        .json(&RelocateInput {
+            source: "/test/raw/".to_string(),
+            target: "/test/curated/".to_string(),
+            retarget_tokens: false,
+        })
+        .dispatch()
+        .await;
+    assert_eq!(response.status(), Status::Ok);
+    assert_eq!(
+        read(&client, &token.code, "test/curated").await,
+        read(&client, &token.code, "test/raw").await
+    );
+    assert_eq!(
+        read(&client, &token.code, "test/curated/sub").await,
+        atoms(&["(person carol)"])
+    );
+
+    let response = client
+        .post("/spaces/clear/test/raw?expr=(person%20$x)")
+        .header(auth())
+        .dispatch()
+        .await;
+    assert_eq!(response.status(), Status::Ok);
+    assert_eq!(
+        read(&client, &token.code, "test/raw").await,
+        atoms(&["(friends \"bob b\" alice)", "(likes alice \"bob b\")"])
+    );
+    assert_eq!(read(&client, &token.code, "test/curated").await.len(), 4);
+
+    let response = client
+        .post("/spaces/upload/test/raw")
+        .header(auth())
+        .body("(unbalanced")
+        .dispatch()
+        .await;
+    assert_eq!(response.status(), Status::BadRequest);
+
+    let response = client.get("/readyz").dispatch().await;
+    assert_eq!(response.status(), Status::Ok);
+
+    env::remove_var("METTA_KG_SPACE_BACKEND");
+    common::teardown_database();
+}
@@ -0,0 +1,99 @@
+use api::rocket;
+use httpmock::prelude::*;
+use httpmock::Regex;
+use rocket::http::{Header, Status};
+use rocket::local::asynchronous::Client;
+use serial_test::serial;
+use std::env;
+
+use crate::integrations::common;
+
+#[tokio::test]
+#[serial]
+async fn test_metrics() {
+    if !common::is_database_running() {
+        eprintln!("Warning: Database not running, skipping test");
+        return;
+    }
+    let server = MockServer::start();
+    common::setup(&server.base_url());
+    env::set_var("METTA_KG_METRICS_TOKEN", "scrape-token");
+
+    let token = common::create_test_token("/test/", true, true);
+
+    server.mock(|when, then| {
+        when.method(GET)
+            .path_matches(Regex::new(r"/export/.*").unwrap());
+        then.status(200).body("(a b)");
+    });
+
+    let client = Client::tracked(rocket())
+        .await
+        .expect("valid rocket instance");
+
+    let response = client
+        .get("/spaces/test/secret_space")
+        .header(Header::new("authorization", token.code.clone()))
+        .dispatch()
+        .await;
+    assert_eq!(response.status(), Status::Ok);
+
+    let response = client.get("/metrics").dispatch().await;
+    assert_eq!(response.status(), Status::Unauthorized);
+
+    let response = client
+        .get("/metrics")
+        .header(Header::new("authorization", token.code.clone()))
+        .dispatch()
+        .await;
+    assert_eq!(response.status(), Status::Unauthorized);
+
+    let response = client
+        .get("/metrics")
+        .header(Header::new("authorization", "Bearer scrape-token"))
+        .dispatch()
+        .await;
+    assert_eq!(response.status(), Status::Ok);
+
+    let body = response.into_string().await.unwrap();
+    assert!(body.contains(
+        r#"mettakg_http_requests_total{method="GET",route="/spaces/<path..>",status="200"}"#
+    ));
+    assert!(body.contains("mettakg_http_request_duration_seconds_bucket"));
+    assert!(body.contains(
+        r#"mettakg_mork_request_duration_seconds_count{operation="read",outcome="success"}"#
+    ));
+    assert!(body.contains(r#"mettakg_db_pool_connections{state="active"}"#));
+    assert!(body.contains("mettakg_tokens "));
+
+    // no namespaces or token codes in the labels
+    assert!(!body.contains("secret_space"));
+    assert!(!body.contains(&token.code));
+
+    env::remove_var("METTA_KG_METRICS_TOKEN");
+    common::teardown_database();
+}
+
+#[tokio::test]
+#[serial]
+async fn test_metrics_disabled_without_token() {
+    if !common::is_database_running() {
+        eprintln!("Warning: Database not running, skipping test");
+        return;
+    }
+    let server = MockServer::start();
+    common::setup(&server.base_url());
+
+    let client = Client::tracked(rocket())
+        .await
+        .expect("valid rocket instance");
+
+    let response = client
+        .get("/metrics")
+        .header(Header::new("authorization", "Bearer anything"))
+        .dispatch()
+        .await;
+    assert_eq!(response.status(), Status::NotFound);
+
+    common::teardown_database();
+}
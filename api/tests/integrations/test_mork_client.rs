@@ -0,0 +1,144 @@
+use api::rocket;
+use httpmock::prelude::*;
+use httpmock::Regex;
+use rocket::http::{Header, Status};
+use rocket::local::asynchronous::Client;
+use rocket::tokio::time::{sleep, Duration};
+use serial_test::serial;
+use std::env;
+use std::time::Instant;
+
+use crate::integrations::common;
+
+const ENV_VARS: [&str; 4] = [
+    "METTA_KG_MORK_RETRY_DELAY_MS",
+    "METTA_KG_MORK_BREAKER_COOLDOWN_MS",
+    "METTA_KG_MORK_TIMEOUT_READ_MS",
+    "METTA_KG_MORK_TIMEOUT_UPLOAD_MS",
+];
+
+/// MORK answers slower than the timeouts, so every request fails
+fn setup(server: &MockServer) {
+    common::setup(&server.base_url());
+    env::set_var("METTA_KG_MORK_RETRY_DELAY_MS", "10");
+    env::set_var("METTA_KG_MORK_BREAKER_COOLDOWN_MS", "300");
+    env::set_var("METTA_KG_MORK_TIMEOUT_READ_MS", "100");
+    env::set_var("METTA_KG_MORK_TIMEOUT_UPLOAD_MS", "100");
+}
+
+fn teardown() {
+    for name in ENV_VARS {
+        env::remove_var(name);
+    }
+    common::teardown_database();
+}
+
+#[tokio::test]
+#[serial]
+async fn test_retries_are_bounded_by_the_timeout() {
+    if !common::is_database_running() {
+        eprintln!("Warning: Database not running, skipping test");
+        return;
+    }
+    let server = MockServer::start();
+    setup(&server);
+
+    let token = common::create_test_token("/test/", true, true);
+
+    let export = server.mock(|when, then| {
+        when.method(GET)
+            .path_matches(Regex::new(r"/export/.*").unwrap());
+        then.status(200).body("").delay(Duration::from_millis(500));
+    });
+    let upload = server.mock(|when, then| {
+        when.method(POST)
+            .path_matches(Regex::new(r"/upload/.*").unwrap());
+        then.status(200).body("").delay(Duration::from_millis(500));
+    });
+
+    let client = Client::tracked(rocket())
+        .await
+        .expect("valid rocket instance");
+
+    let started = Instant::now();
+    let response = client
+        .get("/spaces/test/data")
+        .header(Header::new("authorization", token.code.clone()))
+        .dispatch()
+        .await;
+
+    // the attempt used up the timeout of the read, which leaves no time for
+    // a retry
+    assert_eq!(response.status(), Status::ServiceUnavailable);
+    assert!(started.elapsed() < Duration::from_millis(400));
+    export.assert_hits(1);
+
+    let response = client
+        .post("/spaces/upload/test/data")
+        .header(Header::new("authorization", token.code.clone()))
+        .body("(a b)")
+        .dispatch()
+        .await;
+
+    assert_eq!(response.status(), Status::ServiceUnavailable);
+    upload.assert_hits(1);
+
+    teardown();
+}
+
+#[tokio::test]
+#[serial]
+async fn test_circuit_breaker() {
+    if !common::is_database_running() {
+        eprintln!("Warning: Database not running, skipping test");
+        return;
+    }
+    let server = MockServer::start();
+    setup(&server);
+
+    let token = common::create_test_token("/test/", true, true);
+
+    let mut slow = server.mock(|when, then| {
+        when.method(GET)
+            .path_matches(Regex::new(r"/export/.*").unwrap());
+        then.status(200).body("").delay(Duration::from_millis(500));
+    });
+
+    let client = Client::tracked(rocket())
+        .await
+        .expect("valid rocket instance");
+
+    let read = || async {
+        client
+            .get("/spaces/test/data")
+            .header(Header::new("authorization", token.code.clone()))
+            .dispatch()
+            .await
+            .status()
+    };
+
+    // the circuit opens after the fifth failure. Every read times out
+    // without a retry.
+    for _ in 0..5 {
+        assert_eq!(read().await, Status::ServiceUnavailable);
+    }
+    slow.assert_hits(5);
+
+    // while the circuit is open, requests fail without reaching MORK
+    assert_eq!(read().await, Status::ServiceUnavailable);
+    slow.assert_hits(5);
+
+    slow.delete();
+    server.mock(|when, then| {
+        when.method(GET)
+            .path_matches(Regex::new(r"/export/.*").unwrap());
+        then.status(200).body("(a b)");
+    });
+
+    // after the cooldown a request probes MORK and closes the circuit
+    sleep(Duration::from_millis(400)).await;
+    assert_eq!(read().await, Status::Ok);
+    assert_eq!(read().await, Status::Ok);
+
+    teardown();
+}
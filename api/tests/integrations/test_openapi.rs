@@ -0,0 +1,130 @@
+use api::rocket;
+use api::versioning::{unversioned, API_BASE};
+use regex::Regex;
+use rocket::http::Status;
+use rocket::local::asynchronous::Client;
+use rocket::serde::json::{from_str, Value};
+use serial_test::serial;
+use std::collections::BTreeSet;
+
+use crate::integrations::common;
+
+/// Routes of the docs UI and the CORS fairing, which are not part of the API
+const UNDOCUMENTED_URIS: [&str; 3] = ["/docs", "/openapi.json", "/cors"];
+
+/// `/spaces/<path..>?<background>` as the specification writes it,
+/// `/spaces/{path}`
+fn openapi_path(uri: &str) -> String {
+    let path = uri.split('?').next().unwrap();
+
+    Regex::new(r"<(\w+)(\.\.)?>")
+        .unwrap()
+        .replace_all(path, "{$1}")
+        .to_string()
+}
+
+#[tokio::test]
+#[serial]
+async fn test_openapi_matches_routes() {
+    if !common::is_database_running() {
+        eprintln!("Warning: Database not running, skipping test");
+        return;
+    }
+    common::setup("http://127.0.0.1:1");
+
+    let client = Client::tracked(rocket())
+        .await
+        .expect("valid rocket instance");
+
+    let response = client.get("/openapi.json").dispatch().await;
+    assert_eq!(response.status(), Status::Ok);
+
+    let spec: Value = from_str(&response.into_string().await.unwrap()).unwrap();
+
+    let versioned: BTreeSet<(String, String)> = client
+        .rocket()
+        .routes()
+        .filter(|route| route.uri.base() == API_BASE)
+        .map(|route| {
+            (
+                route.method.to_string(),
+                unversioned(route.uri.as_str()).to_string(),
+            )
+        })
+        .collect();
+
+    // the unversioned aliases are deprecated and left out of the spec
+    let (aliases, routes): (Vec<_>, Vec<_>) = client
+        .rocket()
+        .routes()
+        .filter(|route| {
+            !UNDOCUMENTED_URIS
+                .iter()
+                .any(|uri| route.uri.path().starts_with(uri))
+        })
+        .partition(|route| {
+            route.uri.base() == "/"
+                && versioned.contains(&(route.method.to_string(), route.uri.to_string()))
+        });
+    assert_eq!(
+        aliases.len(),
+        versioned.len(),
+        "every /v1 route has an alias"
+    );
+
+    let routes: BTreeSet<(String, String)> = routes
+        .into_iter()
+        .map(|route| {
+            (
+                route.method.as_str().to_lowercase(),
+                openapi_path(route.uri.as_str()),
+            )
+        })
+        .collect();
+
+    let documented: BTreeSet<(String, String)> = spec["paths"]
+        .as_object()
+        .unwrap()
+        .iter()
+        .flat_map(|(path, item)| {
+            item.as_object()
+                .unwrap()
+                .keys()
+                .filter(|key| ["get", "put", "post", "delete", "patch"].contains(&key.as_str()))
+                .map(move |method| (method.clone(), path.clone()))
+        })
+        .collect();
+
+    let undocumented: Vec<_> = routes.difference(&documented).collect();
+    assert!(undocumented.is_empty(), "not in the spec: {undocumented:?}");
+
+    let unrouted: Vec<_> = documented.difference(&routes).collect();
+    assert!(unrouted.is_empty(), "not routed: {unrouted:?}");
+
+    // every referenced schema is defined
+    let schemas = spec["components"]["schemas"].as_object().unwrap();
+    let text = spec.to_string();
+    for reference in Regex::new(r"#/components/schemas/(\w+)")
+        .unwrap()
+        .captures_iter(&text)
+    {
+        assert!(schemas.contains_key(&reference[1]), "{}", &reference[1]);
+    }
+
+    for schema in [
+        "Token",
+        "Mm2InputMulti",
+        "Mm2Input",
+        "ExploreInput",
+        "CSVParseDirection",
+        "ErrorBody",
+    ] {
+        assert!(schemas.contains_key(schema), "{schema}");
+    }
+
+    let response = client.get("/docs/").dispatch().await;
+    assert_eq!(response.status(), Status::Ok);
+    assert!(response.into_string().await.unwrap().contains("swagger"));
+
+    common::teardown_database();
+}
@@ -0,0 +1,360 @@
+use api::errors::ErrorBody;
+use api::model::Pipeline;
+use api::rocket;
+use api::routes::pipelines::{
+    PipelineDetails, PipelineInput, PipelineReport, PipelineRun, PipelineStepInput, StepStatus,
+};
+use httpmock::prelude::*;
+use rocket::http::{Header, Status};
+use rocket::local::asynchronous::Client;
+use rocket::serde::json::from_value;
+use serial_test::serial;
+use std::collections::HashMap;
+
+use crate::integrations::common;
+
+fn pipeline_input() -> PipelineInput {
+    PipelineInput {
+        name: "simplify".to_string(),
+        description: "Flattens the imported json".to_string(),
+        steps: vec![
+            PipelineStepInput {
+                patterns: vec![
+                    "(src (json $i ($k $v)))".to_string(),
+                    "(src (json $i ({{id_key}} $id)))".to_string(),
+                ],
+                templates: vec!["(simple (({{kind}} $id) $k $v))".to_string()],
+            },
+            PipelineStepInput {
+                patterns: vec!["(simple $x)".to_string()],
+                templates: vec!["(done $x)".to_string()],
+            },
+        ],
+    }
+}
+
+fn params(pairs: &[(&str, &str)]) -> PipelineRun {
+    PipelineRun {
+        params: pairs
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect::<HashMap<_, _>>(),
+    }
+}
+
+#[tokio::test]
+#[serial]
+async fn test_pipeline_versions() {
+    if !common::is_database_running() {
+        eprintln!("Warning: Database not running, skipping test");
+        return;
+    }
+    let server = MockServer::start();
+    common::setup(&server.base_url());
+
+    let token = common::create_test_token("/test/", true, true);
+    let reader = common::create_test_token("/test/", true, false);
+
+    let client = Client::tracked(rocket())
+        .await
+        .expect("valid rocket instance");
+
+    for expected_version in [1, 2] {
+        let response = client
+            .post("/pipelines")
+            .header(Header::new("authorization", token.code.clone()))
+            .json(&pipeline_input())
+            .dispatch()
+            .await;
+        assert_eq!(response.status(), Status::Ok);
+        let pipeline: Pipeline = response.into_json().await.expect("pipeline");
+        assert_eq!(pipeline.version, expected_version);
+    }
+
+    let response = client
+        .post("/pipelines")
+        .header(Header::new("authorization", reader.code.clone()))
+        .json(&pipeline_input())
+        .dispatch()
+        .await;
+    assert_eq!(response.status(), Status::Unauthorized);
+
+    let response = client
+        .get("/pipelines")
+        .header(Header::new("authorization", reader.code.clone()))
+        .dispatch()
+        .await;
+    let pipelines: Vec<Pipeline> = response.into_json().await.expect("pipelines");
+    assert_eq!(pipelines.len(), 1);
+    assert_eq!(pipelines[0].version, 2);
+
+    let response = client
+        .get("/pipelines/simplify?version=1")
+        .header(Header::new("authorization", reader.code.clone()))
+        .dispatch()
+        .await;
+    let details: PipelineDetails = response.into_json().await.expect("pipeline details");
+    assert_eq!(details.pipeline.version, 1);
+    assert_eq!(details.steps.len(), 2);
+
+    common::teardown_database();
+}
+
+#[tokio::test]
+#[serial]
+async fn test_pipeline_owner() {
+    if !common::is_database_running() {
+        eprintln!("Warning: Database not running, skipping test");
+        return;
+    }
+    let server = MockServer::start();
+    common::setup(&server.base_url());
+
+    let owner = common::create_test_token("/test/", true, true);
+    let other = common::create_test_token("/other/", true, true);
+    let neighbour = common::create_test_token("/test/", true, true);
+
+    let client = Client::tracked(rocket())
+        .await
+        .expect("valid rocket instance");
+
+    let response = client
+        .post("/pipelines")
+        .header(Header::new("authorization", owner.code.clone()))
+        .json(&pipeline_input())
+        .dispatch()
+        .await;
+    assert_eq!(response.status(), Status::Ok);
+    let pipeline: Pipeline = response.into_json().await.expect("pipeline");
+    assert_eq!(pipeline.namespace, "/test/");
+
+    // only the owner can add versions, even within its namespace
+    let response = client
+        .post("/pipelines")
+        .header(Header::new("authorization", neighbour.code.clone()))
+        .json(&pipeline_input())
+        .dispatch()
+        .await;
+    assert_eq!(response.status(), Status::Unauthorized);
+
+    // the pipeline is not visible outside of its namespace
+    let response = client
+        .get("/pipelines")
+        .header(Header::new("authorization", other.code.clone()))
+        .dispatch()
+        .await;
+    let pipelines: Vec<Pipeline> = response.into_json().await.expect("pipelines");
+    assert!(pipelines.is_empty());
+
+    let response = client
+        .get("/pipelines/simplify")
+        .header(Header::new("authorization", other.code.clone()))
+        .dispatch()
+        .await;
+    assert_eq!(response.status(), Status::NotFound);
+
+    let response = client
+        .post("/pipelines/simplify/run/other/space")
+        .header(Header::new("authorization", other.code.clone()))
+        .json(&params(&[("id_key", "id"), ("kind", "node")]))
+        .dispatch()
+        .await;
+    assert_eq!(response.status(), Status::NotFound);
+
+    // names are unique per namespace, so another namespace can use the same
+    let response = client
+        .post("/pipelines")
+        .header(Header::new("authorization", other.code.clone()))
+        .json(&pipeline_input())
+        .dispatch()
+        .await;
+    assert_eq!(response.status(), Status::Ok);
+    let other_pipeline: Pipeline = response.into_json().await.expect("pipeline");
+    assert_eq!(other_pipeline.namespace, "/other/");
+    assert_eq!(other_pipeline.version, 1);
+
+    let response = client
+        .get("/pipelines/simplify")
+        .header(Header::new("authorization", other.code.clone()))
+        .dispatch()
+        .await;
+    let details: PipelineDetails = response.into_json().await.expect("pipeline");
+    assert_eq!(details.pipeline.id, other_pipeline.id);
+
+    let response = client
+        .get("/pipelines/simplify")
+        .header(Header::new("authorization", neighbour.code.clone()))
+        .dispatch()
+        .await;
+    assert_eq!(response.status(), Status::Ok);
+
+    common::teardown_database();
+}
+
+#[tokio::test]
+#[serial]
+async fn test_pipeline_name_resolution() {
+    if !common::is_database_running() {
+        eprintln!("Warning: Database not running, skipping test");
+        return;
+    }
+    let server = MockServer::start();
+    common::setup(&server.base_url());
+
+    let parent = common::create_test_token("/test/", true, true);
+    let child = common::create_test_token("/test/sub/", true, true);
+
+    let client = Client::tracked(rocket())
+        .await
+        .expect("valid rocket instance");
+
+    let mut created = vec![];
+    for token in [&parent, &child] {
+        let response = client
+            .post("/pipelines")
+            .header(Header::new("authorization", token.code.clone()))
+            .json(&pipeline_input())
+            .dispatch()
+            .await;
+        assert_eq!(response.status(), Status::Ok);
+        let pipeline: Pipeline = response.into_json().await.expect("pipeline");
+        created.push(pipeline);
+
+        // until it has its own, the child resolves the name in the parent
+        let response = client
+            .get("/pipelines/simplify")
+            .header(Header::new("authorization", child.code.clone()))
+            .dispatch()
+            .await;
+        let details: PipelineDetails = response.into_json().await.expect("pipeline");
+        assert_eq!(details.pipeline.id, created.last().unwrap().id);
+    }
+
+    // the parent does not see the pipeline of the child
+    let response = client
+        .get("/pipelines")
+        .header(Header::new("authorization", parent.code.clone()))
+        .dispatch()
+        .await;
+    let pipelines: Vec<Pipeline> = response.into_json().await.expect("pipelines");
+    assert_eq!(pipelines.len(), 1);
+    assert_eq!(pipelines[0].id, created[0].id);
+
+    common::teardown_database();
+}
+
+#[tokio::test]
+#[serial]
+async fn test_pipeline_run() {
+    if !common::is_database_running() {
+        eprintln!("Warning: Database not running, skipping test");
+        return;
+    }
+    let server = MockServer::start();
+    common::setup(&server.base_url());
+
+    let token = common::create_test_token("/test/", true, true);
+
+    let first_step = server.mock(|when, then| {
+        when.method(POST)
+            .path("/transform")
+            .body_contains("comment_id")
+            .body_contains("comment");
+        then.status(200).body("Transform successful");
+    });
+    let second_step = server.mock(|when, then| {
+        when.method(POST).path("/transform").body_contains("done");
+        then.status(200).body("Transform successful");
+    });
+
+    let client = Client::tracked(rocket())
+        .await
+        .expect("valid rocket instance");
+
+    let response = client
+        .post("/pipelines")
+        .header(Header::new("authorization", token.code.clone()))
+        .json(&pipeline_input())
+        .dispatch()
+        .await;
+    assert_eq!(response.status(), Status::Ok);
+
+    let response = client
+        .post("/pipelines/simplify/run/test/comments")
+        .header(Header::new("authorization", token.code.clone()))
+        .json(&params(&[("id_key", "comment_id"), ("kind", "comment")]))
+        .dispatch()
+        .await;
+    assert_eq!(response.status(), Status::Ok);
+
+    let report: PipelineReport = response.into_json().await.expect("pipeline report");
+    assert_eq!(report.version, 1);
+    assert!(report
+        .steps
+        .iter()
+        .all(|step| step.status == StepStatus::Succeeded));
+    assert_eq!(
+        report.steps[0].templates,
+        vec!["(simple ((comment $id) $k $v))".to_string()]
+    );
+    first_step.assert();
+    second_step.assert();
+
+    common::teardown_database();
+}
+
+#[tokio::test]
+#[serial]
+async fn test_pipeline_run_invalid_step() {
+    if !common::is_database_running() {
+        eprintln!("Warning: Database not running, skipping test");
+        return;
+    }
+    let server = MockServer::start();
+    common::setup(&server.base_url());
+
+    let token = common::create_test_token("/test/", true, true);
+
+    let transform = server.mock(|when, then| {
+        when.method(POST).path("/transform");
+        then.status(200).body("Transform successful");
+    });
+
+    let client = Client::tracked(rocket())
+        .await
+        .expect("valid rocket instance");
+
+    let response = client
+        .post("/pipelines")
+        .header(Header::new("authorization", token.code.clone()))
+        .json(&pipeline_input())
+        .dispatch()
+        .await;
+    assert_eq!(response.status(), Status::Ok);
+
+    // the `kind` parameter is missing
+    let response = client
+        .post("/pipelines/simplify/run/test/comments")
+        .header(Header::new("authorization", token.code.clone()))
+        .json(&params(&[("id_key", "comment_id")]))
+        .dispatch()
+        .await;
+    assert_eq!(response.status(), Status::UnprocessableEntity);
+
+    // the parameter breaks the atom
+    let response = client
+        .post("/pipelines/simplify/run/test/comments")
+        .header(Header::new("authorization", token.code.clone()))
+        .json(&params(&[("id_key", "comment_id)"), ("kind", "comment")]))
+        .dispatch()
+        .await;
+    assert_eq!(response.status(), Status::UnprocessableEntity);
+
+    let error: ErrorBody = response.into_json().await.expect("error");
+    let report: PipelineReport = from_value(error.details.expect("pipeline report")).unwrap();
+    assert_eq!(report.steps[0].status, StepStatus::Invalid);
+    assert_eq!(report.steps[1].status, StepStatus::Skipped);
+    transform.assert_hits(0);
+
+    common::teardown_database();
+}
@@ -0,0 +1,144 @@
+use api::model::SavedQuery;
+use api::rocket;
+use api::routes::queries::SavedQueryInput;
+use httpmock::prelude::*;
+use httpmock::Regex;
+use rocket::http::{Header, Status};
+use rocket::local::asynchronous::Client;
+use serial_test::serial;
+
+use crate::integrations::common;
+
+fn query_input(namespace: &str, name: &str) -> SavedQueryInput {
+    SavedQueryInput {
+        namespace: namespace.to_string(),
+        name: name.to_string(),
+        patterns: vec!["(person $x)".to_string()],
+        templates: vec!["$x".to_string()],
+        description: "All persons".to_string(),
+    }
+}
+
+#[tokio::test]
+#[serial]
+async fn test_saved_query_crud() {
+    if !common::is_database_running() {
+        eprintln!("Warning: Database not running, skipping test");
+        return;
+    }
+    let server = MockServer::start();
+    common::setup(&server.base_url());
+
+    let token = common::create_test_token("/test/", true, true);
+
+    let client = Client::tracked(rocket())
+        .await
+        .expect("valid rocket instance");
+
+    let response = client
+        .post("/queries")
+        .header(Header::new("authorization", token.code.clone()))
+        .json(&query_input("/test/space/", "persons"))
+        .dispatch()
+        .await;
+    assert_eq!(response.status(), Status::Ok);
+    let query: SavedQuery = response.into_json().await.expect("saved query");
+    assert_eq!(query.token_id, Some(token.id));
+
+    // names are unique per space
+    let response = client
+        .post("/queries")
+        .header(Header::new("authorization", token.code.clone()))
+        .json(&query_input("/test/space/", "persons"))
+        .dispatch()
+        .await;
+    assert_eq!(response.status(), Status::Conflict);
+
+    let mut updated = query_input("/test/space/", "people");
+    updated.templates = vec!["(name $x)".to_string()];
+    let response = client
+        .post(format!("/queries/{}", query.id))
+        .header(Header::new("authorization", token.code.clone()))
+        .json(&updated)
+        .dispatch()
+        .await;
+    assert_eq!(response.status(), Status::Ok);
+
+    let response = client
+        .get("/queries/test")
+        .header(Header::new("authorization", token.code.clone()))
+        .dispatch()
+        .await;
+    let queries: Vec<SavedQuery> = response.into_json().await.expect("saved queries");
+    assert_eq!(queries.len(), 1);
+    assert_eq!(queries[0].name, "people");
+    assert_eq!(queries[0].templates, vec!["(name $x)".to_string()]);
+
+    let response = client
+        .delete(format!("/queries/{}", query.id))
+        .header(Header::new("authorization", token.code.clone()))
+        .dispatch()
+        .await;
+    assert_eq!(response.status(), Status::Ok);
+
+    common::teardown_database();
+}
+
+#[tokio::test]
+#[serial]
+async fn test_view_with_read_token() {
+    if !common::is_database_running() {
+        eprintln!("Warning: Database not running, skipping test");
+        return;
+    }
+    let server = MockServer::start();
+    common::setup(&server.base_url());
+
+    let writer = common::create_test_token("/test/", true, true);
+    let reader = common::create_test_token("/test/", true, false);
+
+    server.mock(|when, then| {
+        when.method(GET)
+            .path_matches(Regex::new(r"/export/.*").unwrap());
+        then.status(200).body("alice\nbob\n");
+    });
+
+    let client = Client::tracked(rocket())
+        .await
+        .expect("valid rocket instance");
+
+    let response = client
+        .post("/queries")
+        .header(Header::new("authorization", writer.code.clone()))
+        .json(&query_input("/test/space/", "persons"))
+        .dispatch()
+        .await;
+    assert_eq!(response.status(), Status::Ok);
+
+    // a read token can run views but not edit them
+    let response = client
+        .post("/queries")
+        .header(Header::new("authorization", reader.code.clone()))
+        .json(&query_input("/test/space/", "others"))
+        .dispatch()
+        .await;
+    assert_eq!(response.status(), Status::Unauthorized);
+
+    let response = client
+        .get("/spaces/_views/test/space/persons")
+        .header(Header::new("authorization", reader.code.clone()))
+        .dispatch()
+        .await;
+    assert_eq!(response.status(), Status::Ok);
+    let body = response.into_string().await.expect("response body");
+    assert_eq!(body, "\"alice\\nbob\\n\"");
+
+    let response = client
+        .get("/spaces/_views/test/space/missing")
+        .header(Header::new("authorization", reader.code.clone()))
+        .dispatch()
+        .await;
+    assert_eq!(response.status(), Status::NotFound);
+
+    common::teardown_database();
+}
@@ -0,0 +1,374 @@
+use api::db::establish_connection;
+use api::model::Token;
+use api::quotas::QuotaLimit;
+use api::rocket;
+use api::routes::quotas::{QuotaInput, QuotaReport};
+use api::routes::spaces::{Mm2CrossInput, Mm2InputMulti, RelocateInput, SourcePattern};
+use api::schema::tokens;
+use diesel::{ExpressionMethods, QueryDsl, RunQueryDsl};
+use httpmock::prelude::*;
+use httpmock::Regex;
+use rocket::http::{Header, Status};
+use rocket::local::asynchronous::Client;
+use serial_test::serial;
+use std::env;
+
+use crate::integrations::common;
+
+/// A token that may write, but not loosen quotas
+fn create_limited_token(namespace: &str) -> Token {
+    let token = common::create_test_token(namespace, true, true);
+
+    diesel::update(tokens::table.find(token.id))
+        .set(tokens::permission_share_share.eq(false))
+        .get_result(&mut establish_connection())
+        .expect("Failed to update test token")
+}
+
+async fn set_quota(client: &Client, code: &str, space: &str, input: &QuotaInput) -> Status {
+    client
+        .post(format!("/quotas/{space}"))
+        .header(Header::new("authorization", code.to_string()))
+        .json(input)
+        .dispatch()
+        .await
+        .status()
+}
+
+/// Makes every subtree look like it holds two atoms
+fn mock_count(server: &MockServer) {
+    server.mock(|when, then| {
+        when.method(GET)
+            .path_matches(Regex::new(r"/count/.*").unwrap());
+        then.status(200).body("2");
+    });
+}
+
+#[tokio::test]
+#[serial]
+async fn test_quota_inheritance() {
+    if !common::is_database_running() {
+        eprintln!("Warning: Database not running, skipping test");
+        return;
+    }
+    let server = MockServer::start();
+    common::setup(&server.base_url());
+    mock_count(&server);
+
+    let admin = common::create_test_token("/test/", true, true);
+    let limited = create_limited_token("/test/");
+
+    let client = Client::tracked(rocket())
+        .await
+        .expect("valid rocket instance");
+
+    let quota = QuotaInput {
+        max_atoms: Some(10),
+        max_upload_bytes: Some(100),
+        ..Default::default()
+    };
+    assert_eq!(
+        set_quota(&client, &admin.code, "test", &quota).await,
+        Status::Ok
+    );
+
+    let response = client
+        .get("/quotas/test/space")
+        .header(Header::new("authorization", limited.code.clone()))
+        .dispatch()
+        .await;
+    assert_eq!(response.status(), Status::Ok);
+    let report: QuotaReport = response.into_json().await.expect("quota report");
+    assert_eq!(report.atoms, 2);
+    assert_eq!(
+        report.quota.max_atoms,
+        Some(QuotaLimit {
+            limit: 10,
+            namespace: "/test/".to_string()
+        })
+    );
+    assert_eq!(report.quota.max_import_bytes, None);
+
+    // lowering is allowed, raising is not
+    let lower = QuotaInput {
+        max_atoms: Some(5),
+        ..Default::default()
+    };
+    assert_eq!(
+        set_quota(&client, &limited.code, "test/space", &lower).await,
+        Status::Ok
+    );
+
+    let higher = QuotaInput {
+        max_atoms: Some(20),
+        ..Default::default()
+    };
+    assert_eq!(
+        set_quota(&client, &limited.code, "test/space", &higher).await,
+        Status::Unauthorized
+    );
+
+    // removing the lower limit raises it back to the inherited one
+    let response = client
+        .delete("/quotas/test/space")
+        .header(Header::new("authorization", limited.code.clone()))
+        .dispatch()
+        .await;
+    assert_eq!(response.status(), Status::Unauthorized);
+
+    let response = client
+        .delete("/quotas/test/space")
+        .header(Header::new("authorization", admin.code.clone()))
+        .dispatch()
+        .await;
+    assert_eq!(response.status(), Status::Ok);
+
+    common::teardown_database();
+}
+
+#[tokio::test]
+#[serial]
+async fn test_upload_quota() {
+    if !common::is_database_running() {
+        eprintln!("Warning: Database not running, skipping test");
+        return;
+    }
+    let server = MockServer::start();
+    common::setup(&server.base_url());
+    mock_count(&server);
+
+    let token = common::create_test_token("/test/", true, true);
+
+    let upload = server.mock(|when, then| {
+        when.method(POST)
+            .path_matches(Regex::new(r"/upload/.*").unwrap());
+        then.status(200).body("Upload successful");
+    });
+
+    let client = Client::tracked(rocket())
+        .await
+        .expect("valid rocket instance");
+
+    let quota = QuotaInput {
+        max_atoms: Some(3),
+        max_upload_bytes: Some(10),
+        ..Default::default()
+    };
+    assert_eq!(
+        set_quota(&client, &token.code, "test", &quota).await,
+        Status::Ok
+    );
+
+    for (data, expected) in [
+        ("(x) (y)", Status::PayloadTooLarge),
+        ("(a long atom)", Status::PayloadTooLarge),
+        ("(x)", Status::Ok),
+    ] {
+        let response = client
+            .post("/spaces/upload/test/space")
+            .header(Header::new("authorization", token.code.clone()))
+            .body(data)
+            .dispatch()
+            .await;
+        assert_eq!(response.status(), expected, "uploading {data}");
+    }
+
+    upload.assert_hits(1);
+
+    common::teardown_database();
+}
+
+#[tokio::test]
+#[serial]
+async fn test_transform_quota() {
+    if !common::is_database_running() {
+        eprintln!("Warning: Database not running, skipping test");
+        return;
+    }
+    let server = MockServer::start();
+    common::setup(&server.base_url());
+    mock_count(&server);
+
+    let token = common::create_test_token("/test/", true, true);
+
+    let transform = server.mock(|when, then| {
+        when.method(POST).path("/transform");
+        then.status(200).body("Transform successful");
+    });
+
+    let client = Client::tracked(rocket())
+        .await
+        .expect("valid rocket instance");
+
+    let quota = QuotaInput {
+        max_atoms: Some(2),
+        ..Default::default()
+    };
+    assert_eq!(
+        set_quota(&client, &token.code, "test/space", &quota).await,
+        Status::Ok
+    );
+
+    let response = client
+        .post("/spaces/transform/test/space")
+        .header(Header::new("authorization", token.code.clone()))
+        .json(&Mm2InputMulti {
+            patterns: vec!["$x".to_string()],
+            templates: vec!["($x)".to_string()],
+        })
+        .dispatch()
+        .await;
+    assert_eq!(response.status(), Status::PayloadTooLarge);
+    transform.assert_hits(0);
+
+    // the quota of a space does not apply to its siblings
+    let response = client
+        .post("/spaces/transform/test/other")
+        .header(Header::new("authorization", token.code.clone()))
+        .json(&Mm2InputMulti {
+            patterns: vec!["$x".to_string()],
+            templates: vec!["($x)".to_string()],
+        })
+        .dispatch()
+        .await;
+    assert_eq!(response.status(), Status::Ok);
+    transform.assert_hits(1);
+
+    common::teardown_database();
+}
+
+#[tokio::test]
+#[serial]
+async fn test_transform_above_quota_after_write() {
+    if !common::is_database_running() {
+        eprintln!("Warning: Database not running, skipping test");
+        return;
+    }
+    // no MORK is running
+    common::setup("http://127.0.0.1:1");
+    env::set_var("METTA_KG_SPACE_BACKEND", "memory");
+
+    let token = common::create_test_token("/test/", true, true);
+
+    let client = Client::tracked(rocket())
+        .await
+        .expect("valid rocket instance");
+
+    let quota = QuotaInput {
+        max_atoms: Some(2),
+        ..Default::default()
+    };
+    assert_eq!(
+        set_quota(&client, &token.code, "test/space", &quota).await,
+        Status::Ok
+    );
+
+    let response = client
+        .post("/spaces/upload/test/space")
+        .header(Header::new("authorization", token.code.clone()))
+        .body("(person alice)")
+        .dispatch()
+        .await;
+    assert_eq!(response.status(), Status::Ok);
+
+    // below the limit beforehand, the transformation ends above it
+    let response = client
+        .post("/spaces/transform/test/space")
+        .header(Header::new("authorization", token.code.clone()))
+        .json(&Mm2InputMulti {
+            patterns: vec!["(person $x)".to_string()],
+            templates: vec!["(name $x)".to_string(), "(known $x)".to_string()],
+        })
+        .dispatch()
+        .await;
+    env::remove_var("METTA_KG_SPACE_BACKEND");
+    assert_eq!(response.status(), Status::PayloadTooLarge);
+
+    // the overage is reported
+    let response = client
+        .get("/quotas/test/space")
+        .header(Header::new("authorization", token.code.clone()))
+        .dispatch()
+        .await;
+    let report: QuotaReport = response.into_json().await.expect("report");
+    assert_eq!(report.atoms, 3);
+
+    common::teardown_database();
+}
+
+#[tokio::test]
+#[serial]
+async fn test_subtree_quota() {
+    if !common::is_database_running() {
+        eprintln!("Warning: Database not running, skipping test");
+        return;
+    }
+    // the atoms are counted by the memory backend
+    common::setup("http://127.0.0.1:1");
+    env::set_var("METTA_KG_SPACE_BACKEND", "memory");
+
+    let token = common::create_test_token("/test/", true, true);
+    let auth = || Header::new("authorization", token.code.clone());
+
+    let client = Client::tracked(rocket())
+        .await
+        .expect("valid rocket instance");
+
+    let quota = QuotaInput {
+        max_atoms: Some(3),
+        ..Default::default()
+    };
+    assert_eq!(
+        set_quota(&client, &token.code, "test", &quota).await,
+        Status::Ok
+    );
+
+    // the limit of the space applies to its subspaces together
+    for (space, data, expected) in [
+        ("test/raw", "(x) (y)", Status::Ok),
+        ("test/more", "(x) (y)", Status::PayloadTooLarge),
+        ("test/more", "(x)", Status::Ok),
+    ] {
+        let response = client
+            .post(format!("/spaces/upload/{space}"))
+            .header(auth())
+            .body(data)
+            .dispatch()
+            .await;
+        assert_eq!(response.status(), expected, "uploading {data} to {space}");
+    }
+
+    let response = client.get("/quotas/test").header(auth()).dispatch().await;
+    let report: QuotaReport = response.into_json().await.expect("quota report");
+    assert_eq!(report.atoms, 3);
+
+    let response = client
+        .post("/spaces/cross-transform")
+        .header(auth())
+        .json(&Mm2CrossInput {
+            patterns: vec![SourcePattern {
+                namespace: "/test/raw/".to_string(),
+                pattern: "$x".to_string(),
+            }],
+            target: "/test/derived/".to_string(),
+            templates: vec!["(copy $x)".to_string()],
+        })
+        .dispatch()
+        .await;
+    assert_eq!(response.status(), Status::PayloadTooLarge);
+
+    let response = client
+        .post("/spaces/copy")
+        .header(auth())
+        .json(&RelocateInput {
+            source: "/test/raw/".to_string(),
+            target: "/test/copied/".to_string(),
+            retarget_tokens: false,
+        })
+        .dispatch()
+        .await;
+    assert_eq!(response.status(), Status::PayloadTooLarge);
+
+    env::remove_var("METTA_KG_SPACE_BACKEND");
+    common::teardown_database();
+}
@@ -0,0 +1,126 @@
+use api::db::establish_connection;
+use api::model::Token;
+use api::rocket;
+use api::schema::tokens;
+use diesel::{ExpressionMethods, QueryDsl, RunQueryDsl};
+use httpmock::prelude::*;
+use rocket::http::{Header, Status};
+use rocket::local::asynchronous::Client;
+use serial_test::serial;
+
+use crate::integrations::common;
+
+/// A token limited to `rate_limit` reads per minute
+fn create_limited_token(namespace: &str, rate_limit: i32) -> Token {
+    let token = common::create_test_token(namespace, true, true);
+
+    diesel::update(tokens::table.find(token.id))
+        .set(tokens::rate_limit_read.eq(rate_limit))
+        .get_result(&mut establish_connection())
+        .expect("Failed to update test token")
+}
+
+#[tokio::test]
+#[serial]
+async fn test_rate_limit() {
+    if !common::is_database_running() {
+        eprintln!("Warning: Database not running, skipping test");
+        return;
+    }
+    let server = MockServer::start();
+    common::setup(&server.base_url());
+
+    let token = create_limited_token("/test/", 2);
+    let other = common::create_test_token("/test/", true, true);
+
+    let client = Client::tracked(rocket())
+        .await
+        .expect("valid rocket instance");
+
+    for _ in 0..2 {
+        let response = client
+            .get("/token")
+            .header(Header::new("authorization", token.code.clone()))
+            .dispatch()
+            .await;
+        assert_eq!(response.status(), Status::Ok);
+    }
+
+    let response = client
+        .get("/token")
+        .header(Header::new("authorization", token.code.clone()))
+        .dispatch()
+        .await;
+    assert_eq!(response.status(), Status::TooManyRequests);
+    let retry_after: u64 = response
+        .headers()
+        .get_one("Retry-After")
+        .expect("Retry-After header")
+        .parse()
+        .expect("seconds to wait");
+    assert!(retry_after > 0);
+
+    // writes have limits of their own, none for this token
+    for _ in 0..3 {
+        let response = client
+            .delete("/tokens/0")
+            .header(Header::new("authorization", token.code.clone()))
+            .dispatch()
+            .await;
+        assert_ne!(response.status(), Status::TooManyRequests);
+    }
+
+    // and so are other tokens
+    let response = client
+        .get("/token")
+        .header(Header::new("authorization", other.code.clone()))
+        .dispatch()
+        .await;
+    assert_eq!(response.status(), Status::Ok);
+
+    common::teardown_database();
+}
+
+#[tokio::test]
+#[serial]
+async fn test_rate_limit_inherited() {
+    if !common::is_database_running() {
+        eprintln!("Warning: Database not running, skipping test");
+        return;
+    }
+    let server = MockServer::start();
+    common::setup(&server.base_url());
+
+    let parent = create_limited_token("/test/", 10);
+
+    let client = Client::tracked(rocket())
+        .await
+        .expect("valid rocket instance");
+
+    let mut new_token = parent.clone();
+    new_token.description = "child".to_string();
+    new_token.rate_limit_read = Some(20);
+
+    let response = client
+        .post("/tokens")
+        .header(Header::new("authorization", parent.code.clone()))
+        .json(&new_token)
+        .dispatch()
+        .await;
+    assert_eq!(response.status(), Status::BadRequest);
+
+    new_token.rate_limit_read = None;
+    let response = client
+        .post("/tokens")
+        .header(Header::new("authorization", parent.code.clone()))
+        .json(&new_token)
+        .dispatch()
+        .await;
+    assert_eq!(response.status(), Status::Ok);
+    let child: Token = response.into_json().await.expect("token");
+    assert_eq!(child.rate_limit_read, Some(10));
+    assert_eq!(child.rate_limit_write, None);
+    assert_eq!(child.concurrency_limit_read, None);
+
+    common::teardown_database();
+}
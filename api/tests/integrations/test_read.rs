@@ -241,3 +241,45 @@ async fn test_namespace_mismatch() {
 
     common::teardown_database();
 }
+
+#[tokio::test]
+#[serial]
+async fn test_read_spaces_named_like_routes() {
+    if !common::is_database_running() {
+        eprintln!("Warning: Database not running, skipping test");
+        return;
+    }
+    let server = MockServer::start();
+    common::setup(&server.base_url());
+
+    let token = common::create_test_token("/", true, false);
+
+    server.mock(|when, then| {
+        when.method(GET)
+            .path_matches(Regex::new(r"/export/.*").unwrap());
+        then.status(200).body("(a 1)\n");
+    });
+
+    let client = Client::tracked(rocket())
+        .await
+        .expect("valid rocket instance");
+
+    // the other routes under `/spaces/` do not hide these spaces
+    for path in [
+        "/spaces/events/sub",
+        "/spaces/views/sub/name",
+        "/spaces/diff",
+    ] {
+        let response = client
+            .get(path)
+            .header(Header::new("authorization", token.code.clone()))
+            .dispatch()
+            .await;
+
+        assert_eq!(response.status(), Status::Ok);
+        let data: String = response.into_json().await.expect("atoms");
+        assert_eq!(data, "(a 1)\n");
+    }
+
+    common::teardown_database();
+}
@@ -0,0 +1,209 @@
+use api::model::{SavedQuery, Schedule, ScheduleRun};
+use api::rocket;
+use api::routes::pipelines::{PipelineInput, PipelineStepInput};
+use api::routes::queries::SavedQueryInput;
+use api::routes::schedules::ScheduleInput;
+use httpmock::prelude::*;
+use httpmock::Regex;
+use rocket::http::{Header, Status};
+use rocket::local::asynchronous::Client;
+use rocket::tokio::time::{sleep, Duration};
+use serial_test::serial;
+use std::collections::HashMap;
+
+use crate::integrations::common;
+
+async fn create_pipeline(client: &Client, code: &str) {
+    let input = PipelineInput {
+        name: "derive".to_string(),
+        description: String::new(),
+        steps: vec![PipelineStepInput {
+            patterns: vec!["(src $x)".to_string()],
+            templates: vec!["({{target}} $x)".to_string()],
+        }],
+    };
+
+    let response = client
+        .post("/pipelines")
+        .header(Header::new("authorization", code.to_string()))
+        .json(&input)
+        .dispatch()
+        .await;
+    assert_eq!(response.status(), Status::Ok);
+}
+
+#[tokio::test]
+#[serial]
+async fn test_schedule_cron() {
+    if !common::is_database_running() {
+        eprintln!("Warning: Database not running, skipping test");
+        return;
+    }
+    let server = MockServer::start();
+    common::setup(&server.base_url());
+
+    let token = common::create_test_token("/test/", true, true);
+
+    let transform = server.mock(|when, then| {
+        when.method(POST)
+            .path("/transform")
+            .body_contains("derived");
+        then.status(200).body("Transform successful");
+    });
+
+    let client = Client::tracked(rocket())
+        .await
+        .expect("valid rocket instance");
+
+    create_pipeline(&client, &token.code).await;
+
+    let mut input = ScheduleInput {
+        name: "nightly".to_string(),
+        namespace: "/test/space/".to_string(),
+        pipeline: Some("derive".to_string()),
+        params: HashMap::from([("target".to_string(), "derived".to_string())]),
+        cron: Some("not a cron expression".to_string()),
+        ..Default::default()
+    };
+
+    let response = client
+        .post("/schedules")
+        .header(Header::new("authorization", token.code.clone()))
+        .json(&input)
+        .dispatch()
+        .await;
+    assert_eq!(response.status(), Status::BadRequest);
+
+    input.cron = Some("0 0 3 * * *".to_string());
+    let response = client
+        .post("/schedules")
+        .header(Header::new("authorization", token.code.clone()))
+        .json(&input)
+        .dispatch()
+        .await;
+    assert_eq!(response.status(), Status::Ok);
+    let schedule: Schedule = response.into_json().await.expect("schedule");
+    assert!(schedule.enabled);
+    // the latest version when the schedule was created
+    assert_eq!(schedule.pipeline_version, Some(1));
+
+    let response = client
+        .post(format!("/schedules/{}/disable", schedule.id))
+        .header(Header::new("authorization", token.code.clone()))
+        .dispatch()
+        .await;
+    let disabled: Schedule = response.into_json().await.expect("schedule");
+    assert!(!disabled.enabled);
+
+    // a disabled schedule can still be run by hand
+    let response = client
+        .post(format!("/schedules/{}/run", schedule.id))
+        .header(Header::new("authorization", token.code.clone()))
+        .dispatch()
+        .await;
+    assert_eq!(response.status(), Status::Ok);
+    let run: ScheduleRun = response.into_json().await.expect("schedule run");
+    assert_eq!(run.trigger, "manual");
+    assert_eq!(run.status, "succeeded");
+    transform.assert();
+
+    let response = client
+        .get("/schedules")
+        .header(Header::new("authorization", token.code.clone()))
+        .dispatch()
+        .await;
+    let schedules: Vec<Schedule> = response.into_json().await.expect("schedules");
+    assert_eq!(schedules.len(), 1);
+    assert!(schedules[0].last_run_timestamp.is_some());
+    assert_eq!(schedules[0].last_error, None);
+
+    common::teardown_database();
+}
+
+#[tokio::test]
+#[serial]
+async fn test_schedule_watch() {
+    if !common::is_database_running() {
+        eprintln!("Warning: Database not running, skipping test");
+        return;
+    }
+    let server = MockServer::start();
+    common::setup(&server.base_url());
+
+    let token = common::create_test_token("/test/", true, true);
+
+    server.mock(|when, then| {
+        when.method(POST)
+            .path_matches(Regex::new(r"/upload/.*").unwrap());
+        then.status(200).body("Upload successful");
+    });
+    let transform = server.mock(|when, then| {
+        when.method(POST).path("/transform");
+        then.status(200).body("Transform successful");
+    });
+
+    let client = Client::tracked(rocket())
+        .await
+        .expect("valid rocket instance");
+
+    let response = client
+        .post("/queries")
+        .header(Header::new("authorization", token.code.clone()))
+        .json(&SavedQueryInput {
+            namespace: "/test/derived/".to_string(),
+            name: "copy".to_string(),
+            patterns: vec!["(src $x)".to_string()],
+            templates: vec!["(copy $x)".to_string()],
+            description: String::new(),
+        })
+        .dispatch()
+        .await;
+    let query: SavedQuery = response.into_json().await.expect("saved query");
+
+    let response = client
+        .post("/schedules")
+        .header(Header::new("authorization", token.code.clone()))
+        .json(&ScheduleInput {
+            name: "refresh".to_string(),
+            namespace: "/test/derived/".to_string(),
+            saved_query_id: Some(query.id),
+            watch: Some("/test/raw/".to_string()),
+            ..Default::default()
+        })
+        .dispatch()
+        .await;
+    assert_eq!(response.status(), Status::Ok);
+    let schedule: Schedule = response.into_json().await.expect("schedule");
+
+    // uploads elsewhere do not trigger the schedule
+    for space in ["other", "raw/users"] {
+        let response = client
+            .post(format!("/spaces/upload/test/{space}"))
+            .header(Header::new("authorization", token.code.clone()))
+            .body("(src atom)")
+            .dispatch()
+            .await;
+        assert_eq!(response.status(), Status::Ok);
+    }
+
+    let mut runs: Vec<ScheduleRun> = vec![];
+    for _ in 0..50 {
+        let response = client
+            .get(format!("/schedules/{}/runs", schedule.id))
+            .header(Header::new("authorization", token.code.clone()))
+            .dispatch()
+            .await;
+        runs = response.into_json().await.expect("schedule runs");
+        if runs.iter().any(|run| run.finish_timestamp.is_some()) {
+            break;
+        }
+        sleep(Duration::from_millis(100)).await;
+    }
+
+    assert_eq!(runs.len(), 1);
+    assert_eq!(runs[0].trigger, "watch");
+    assert_eq!(runs[0].status, "succeeded");
+    transform.assert();
+
+    common::teardown_database();
+}
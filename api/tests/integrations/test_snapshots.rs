@@ -0,0 +1,241 @@
+use api::model::Snapshot;
+use api::rocket;
+use httpmock::prelude::*;
+use httpmock::Regex;
+use rocket::http::{Header, Status};
+use rocket::local::asynchronous::Client;
+use serial_test::serial;
+use std::env;
+use std::time::Duration;
+
+use crate::integrations::common;
+
+#[tokio::test]
+#[serial]
+async fn test_snapshot_create_and_list() {
+    if !common::is_database_running() {
+        eprintln!("Warning: Database not running, skipping test");
+        return;
+    }
+    let server = MockServer::start();
+    common::setup(&server.base_url());
+
+    let token = common::create_test_token("/test/", true, true);
+
+    let mock = server.mock(|when, then| {
+        when.method(POST)
+            .path("/transform")
+            .body_contains("(, (test (space $x)))")
+            .body_contains("(_snapshots (s");
+        then.status(200).body("Transform successful");
+    });
+
+    let client = Client::tracked(rocket())
+        .await
+        .expect("valid rocket instance");
+
+    let response = client
+        .post("/spaces/snapshot/test/space?name=before-cleanup")
+        .header(Header::new("authorization", token.code.clone()))
+        .dispatch()
+        .await;
+
+    assert_eq!(response.status(), Status::Ok);
+    let snapshot: Snapshot = response.into_json().await.expect("snapshot");
+    assert_eq!(snapshot.name, "before-cleanup");
+    assert_eq!(snapshot.namespace, "/test/space/");
+    mock.assert();
+
+    // names are unique per space
+    let response = client
+        .post("/spaces/snapshot/test/space?name=before-cleanup")
+        .header(Header::new("authorization", token.code.clone()))
+        .dispatch()
+        .await;
+    assert_eq!(response.status(), Status::Conflict);
+
+    let response = client
+        .get("/snapshots/test")
+        .header(Header::new("authorization", token.code.clone()))
+        .dispatch()
+        .await;
+    assert_eq!(response.status(), Status::Ok);
+    let snapshots: Vec<Snapshot> = response.into_json().await.expect("snapshots");
+    assert_eq!(snapshots.len(), 1);
+    assert_eq!(snapshots[0].id, snapshot.id);
+
+    common::teardown_database();
+}
+
+#[tokio::test]
+#[serial]
+async fn test_snapshot_restore() {
+    if !common::is_database_running() {
+        eprintln!("Warning: Database not running, skipping test");
+        return;
+    }
+    let server = MockServer::start();
+    common::setup(&server.base_url());
+
+    let token = common::create_test_token("/test/", true, true);
+
+    server.mock(|when, then| {
+        when.method(POST).path("/transform");
+        then.status(200).body("Transform successful");
+    });
+    let clear_mock = server.mock(|when, then| {
+        when.method(GET)
+            .path_matches(Regex::new(r"/clear/.*").unwrap());
+        then.status(200).body("Clear successful");
+    });
+
+    let client = Client::tracked(rocket())
+        .await
+        .expect("valid rocket instance");
+
+    let response = client
+        .post("/spaces/snapshot/test/space?name=v1")
+        .header(Header::new("authorization", token.code.clone()))
+        .dispatch()
+        .await;
+    let snapshot: Snapshot = response.into_json().await.expect("snapshot");
+
+    let response = client
+        .post(format!("/snapshots/{}/restore", snapshot.id))
+        .header(Header::new("authorization", token.code.clone()))
+        .dispatch()
+        .await;
+
+    assert_eq!(response.status(), Status::Ok);
+    // the live space is cleared once the snapshot was staged, then the staged
+    // copy is removed
+    clear_mock.assert_hits(2);
+
+    common::teardown_database();
+}
+
+#[tokio::test]
+#[serial]
+async fn test_snapshot_restore_keeps_space_when_staging_fails() {
+    if !common::is_database_running() {
+        eprintln!("Warning: Database not running, skipping test");
+        return;
+    }
+    let server = MockServer::start();
+    common::setup(&server.base_url());
+
+    let token = common::create_test_token("/test/", true, true);
+
+    let mut snapshot_mock = server.mock(|when, then| {
+        when.method(POST).path("/transform");
+        then.status(200).body("Transform successful");
+    });
+    let clear_mock = server.mock(|when, then| {
+        when.method(GET)
+            .path_matches(Regex::new(r"/clear/.*").unwrap());
+        then.status(200).body("Clear successful");
+    });
+
+    let client = Client::tracked(rocket())
+        .await
+        .expect("valid rocket instance");
+
+    let response = client
+        .post("/spaces/snapshot/test/space?name=v1")
+        .header(Header::new("authorization", token.code.clone()))
+        .dispatch()
+        .await;
+    let snapshot: Snapshot = response.into_json().await.expect("snapshot");
+
+    // the snapshot can not be copied next to the space
+    snapshot_mock.delete();
+    server.mock(|when, then| {
+        when.method(POST).path("/transform");
+        then.delay(Duration::from_secs(1));
+    });
+    env::set_var("METTA_KG_MORK_TIMEOUT_COPY_MS", "100");
+
+    let client = Client::tracked(rocket())
+        .await
+        .expect("valid rocket instance");
+
+    let response = client
+        .post(format!("/snapshots/{}/restore", snapshot.id))
+        .header(Header::new("authorization", token.code.clone()))
+        .dispatch()
+        .await;
+
+    env::remove_var("METTA_KG_MORK_TIMEOUT_COPY_MS");
+
+    assert_eq!(response.status(), Status::ServiceUnavailable);
+    // only the staged copy is removed, the live space is left as it was
+    clear_mock.assert_hits(1);
+
+    common::teardown_database();
+}
+
+#[tokio::test]
+#[serial]
+async fn test_snapshot_not_visible_to_other_namespace() {
+    if !common::is_database_running() {
+        eprintln!("Warning: Database not running, skipping test");
+        return;
+    }
+    let server = MockServer::start();
+    common::setup(&server.base_url());
+
+    let token1 = common::create_test_token("/ns1/", true, true);
+    let token2 = common::create_test_token("/ns2/", true, true);
+
+    server.mock(|when, then| {
+        when.method(POST).path("/transform");
+        then.status(200).body("Transform successful");
+    });
+
+    let client = Client::tracked(rocket())
+        .await
+        .expect("valid rocket instance");
+
+    let response = client
+        .post("/spaces/snapshot/ns1/space?name=v1")
+        .header(Header::new("authorization", token1.code.clone()))
+        .dispatch()
+        .await;
+    let snapshot: Snapshot = response.into_json().await.expect("snapshot");
+
+    let response = client
+        .post(format!("/snapshots/{}/restore", snapshot.id))
+        .header(Header::new("authorization", token2.code.clone()))
+        .dispatch()
+        .await;
+    assert_eq!(response.status(), Status::NotFound);
+
+    common::teardown_database();
+}
+
+#[tokio::test]
+#[serial]
+async fn test_snapshot_namespace_is_read_only() {
+    if !common::is_database_running() {
+        eprintln!("Warning: Database not running, skipping test");
+        return;
+    }
+    let server = MockServer::start();
+    common::setup(&server.base_url());
+
+    let token = common::create_test_token("/", true, true);
+
+    let client = Client::tracked(rocket())
+        .await
+        .expect("valid rocket instance");
+
+    let response = client
+        .post("/spaces/clear/_snapshots/s1?expr=$x")
+        .header(Header::new("authorization", token.code.clone()))
+        .dispatch()
+        .await;
+
+    assert_eq!(response.status(), Status::Unauthorized);
+
+    common::teardown_database();
+}
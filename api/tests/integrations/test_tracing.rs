@@ -0,0 +1,80 @@
+use api::rocket;
+use api::telemetry::REQUEST_ID_HEADER;
+use httpmock::prelude::*;
+use httpmock::Regex;
+use rocket::http::{Header, Status};
+use rocket::local::asynchronous::Client;
+use serial_test::serial;
+
+use crate::integrations::common;
+
+#[tokio::test]
+#[serial]
+async fn test_request_id_is_forwarded() {
+    if !common::is_database_running() {
+        eprintln!("Warning: Database not running, skipping test");
+        return;
+    }
+    let server = MockServer::start();
+    common::setup(&server.base_url());
+
+    let token = common::create_test_token("/test/", true, true);
+
+    let export = server.mock(|when, then| {
+        when.method(GET)
+            .path_matches(Regex::new(r"/export/.*").unwrap())
+            .header(REQUEST_ID_HEADER, "inherited-id-1");
+        then.status(200).body("(a b)");
+    });
+
+    let client = Client::tracked(rocket())
+        .await
+        .expect("valid rocket instance");
+
+    let response = client
+        .get("/spaces/test/data")
+        .header(Header::new("authorization", token.code.clone()))
+        .header(Header::new(REQUEST_ID_HEADER, "inherited-id-1"))
+        .dispatch()
+        .await;
+
+    assert_eq!(response.status(), Status::Ok);
+    assert_eq!(
+        response.headers().get_one(REQUEST_ID_HEADER),
+        Some("inherited-id-1")
+    );
+    export.assert_hits(1);
+
+    common::teardown_database();
+}
+
+#[tokio::test]
+#[serial]
+async fn test_request_id_is_generated() {
+    if !common::is_database_running() {
+        eprintln!("Warning: Database not running, skipping test");
+        return;
+    }
+    let server = MockServer::start();
+    common::setup(&server.base_url());
+
+    let client = Client::tracked(rocket())
+        .await
+        .expect("valid rocket instance");
+
+    let response = client.get("/healthz").dispatch().await;
+    let generated = response.headers().get_one(REQUEST_ID_HEADER).unwrap();
+    assert_eq!(generated.len(), 36);
+
+    // ids that are not safe to log are replaced
+    let response = client
+        .get("/healthz")
+        .header(Header::new(REQUEST_ID_HEADER, "not a\nsafe id"))
+        .dispatch()
+        .await;
+    let replaced = response.headers().get_one(REQUEST_ID_HEADER).unwrap();
+    assert_ne!(replaced, "not a\nsafe id");
+    assert_eq!(replaced.len(), 36);
+
+    common::teardown_database();
+}
@@ -5,7 +5,7 @@ use rocket::local::asynchronous::Client;
 use serial_test::serial;
 
 use crate::integrations::common;
-use api::routes::spaces::Mm2InputMulti;
+use api::routes::spaces::{Mm2CrossInput, Mm2InputMulti, SourcePattern};
 
 #[tokio::test]
 #[serial]
@@ -206,3 +206,131 @@ async fn test_namespace_mismatch() {
 
     common::teardown_database();
 }
+
+#[tokio::test]
+#[serial]
+async fn test_cross_transform_success() {
+    if !common::is_database_running() {
+        eprintln!("Warning: Database not running, skipping test");
+        return;
+    }
+    let server = MockServer::start();
+    common::setup(&server.base_url());
+
+    let token = common::create_test_token("/test/", true, true);
+
+    // patterns are wrapped with their source, templates with the target
+    let mock = server.mock(|when, then| {
+        when.method(POST)
+            .path("/transform")
+            .body_contains("(test (raw (rawa727d4f9-836a-4e4c-9480 (a $x))))")
+            .body_contains("(test (other (othera727d4f9-836a-4e4c-9480 (b $x))))")
+            .body_contains("(test (curated (curateda727d4f9-836a-4e4c-9480 (c $x))))");
+        then.status(200).body("Transform successful");
+    });
+
+    let client = Client::tracked(rocket())
+        .await
+        .expect("valid rocket instance");
+
+    let mm2_input = Mm2CrossInput {
+        patterns: vec![
+            SourcePattern {
+                namespace: "/test/raw/".to_string(),
+                pattern: "(a $x)".to_string(),
+            },
+            SourcePattern {
+                namespace: "/test/other/".to_string(),
+                pattern: "(b $x)".to_string(),
+            },
+        ],
+        target: "/test/curated/".to_string(),
+        templates: vec!["(c $x)".to_string()],
+    };
+
+    let response = client
+        .post("/spaces/cross-transform")
+        .header(Header::new("authorization", token.code.clone()))
+        .json(&mm2_input)
+        .dispatch()
+        .await;
+
+    assert_eq!(response.status(), Status::Ok);
+    mock.assert();
+
+    common::teardown_database();
+}
+
+#[tokio::test]
+#[serial]
+async fn test_cross_transform_unauthorized_source() {
+    if !common::is_database_running() {
+        eprintln!("Warning: Database not running, skipping test");
+        return;
+    }
+    let server = MockServer::start();
+    common::setup(&server.base_url());
+
+    let token = common::create_test_token("/test/", true, true);
+
+    let client = Client::tracked(rocket())
+        .await
+        .expect("valid rocket instance");
+
+    let mm2_input = Mm2CrossInput {
+        patterns: vec![SourcePattern {
+            namespace: "/other/raw/".to_string(),
+            pattern: "$x".to_string(),
+        }],
+        target: "/test/curated/".to_string(),
+        templates: vec!["$x".to_string()],
+    };
+
+    let response = client
+        .post("/spaces/cross-transform")
+        .header(Header::new("authorization", token.code.clone()))
+        .json(&mm2_input)
+        .dispatch()
+        .await;
+
+    assert_eq!(response.status(), Status::Unauthorized);
+
+    common::teardown_database();
+}
+
+#[tokio::test]
+#[serial]
+async fn test_cross_transform_invalid_namespace() {
+    if !common::is_database_running() {
+        eprintln!("Warning: Database not running, skipping test");
+        return;
+    }
+    let server = MockServer::start();
+    common::setup(&server.base_url());
+
+    let token = common::create_test_token("/test/", true, true);
+
+    let client = Client::tracked(rocket())
+        .await
+        .expect("valid rocket instance");
+
+    let mm2_input = Mm2CrossInput {
+        patterns: vec![SourcePattern {
+            namespace: "/test/raw/".to_string(),
+            pattern: "$x".to_string(),
+        }],
+        target: "/test/(cu rated)/".to_string(),
+        templates: vec!["$x".to_string()],
+    };
+
+    let response = client
+        .post("/spaces/cross-transform")
+        .header(Header::new("authorization", token.code.clone()))
+        .json(&mm2_input)
+        .dispatch()
+        .await;
+
+    assert_eq!(response.status(), Status::BadRequest);
+
+    common::teardown_database();
+}
@@ -0,0 +1,210 @@
+use api::model::{Webhook, WebhookDelivery};
+use api::rocket;
+use api::routes::webhooks::WebhookInput;
+use api::webhooks::{verify, SIGNATURE_HEADER};
+use httpmock::prelude::*;
+use httpmock::Regex;
+use rocket::http::{Header, Status};
+use rocket::local::asynchronous::Client;
+use rocket::tokio::time::{sleep, Duration};
+use serial_test::serial;
+use std::env;
+
+use crate::integrations::common;
+
+const SECRET: &str = "webhook-test-secret";
+
+fn is_signed(request: &HttpMockRequest) -> bool {
+    let body = String::from_utf8(request.body.clone().unwrap_or_default()).unwrap();
+
+    request.headers.iter().flatten().any(|(name, value)| {
+        name.eq_ignore_ascii_case(SIGNATURE_HEADER)
+            && value
+                .strip_prefix("sha256=")
+                .is_some_and(|signature| verify(SECRET, &body, signature))
+    })
+}
+
+fn setup(server: &MockServer) {
+    common::setup(&server.base_url());
+    env::set_var("METTA_KG_SECRET", SECRET);
+    env::set_var("METTA_KG_WEBHOOK_RETRY_DELAY_MS", "10");
+    // the mock server listens on the loopback address
+    env::set_var("METTA_KG_WEBHOOK_ALLOWED_HOSTS", "[\"127.0.0.1\"]");
+
+    server.mock(|when, then| {
+        when.method(POST)
+            .path_matches(Regex::new(r"/upload/.*").unwrap());
+        then.status(200).body("Upload successful");
+    });
+}
+
+async fn register(client: &Client, code: &str, namespace: &str, url: String) -> Webhook {
+    let response = client
+        .post("/webhooks")
+        .header(Header::new("authorization", code.to_string()))
+        .json(&WebhookInput {
+            namespace: namespace.to_string(),
+            url,
+        })
+        .dispatch()
+        .await;
+    assert_eq!(response.status(), Status::Ok);
+
+    response.into_json().await.expect("webhook")
+}
+
+async fn upload(client: &Client, code: &str, space: &str) {
+    let response = client
+        .post(format!("/spaces/upload/{space}"))
+        .header(Header::new("authorization", code.to_string()))
+        .body("(test atom)")
+        .dispatch()
+        .await;
+    assert_eq!(response.status(), Status::Ok);
+}
+
+/// Polls the delivery log until no delivery is pending
+async fn settled_deliveries(client: &Client, code: &str, webhook_id: i32) -> Vec<WebhookDelivery> {
+    let mut deliveries: Vec<WebhookDelivery> = vec![];
+
+    for _ in 0..100 {
+        let response = client
+            .get(format!("/webhooks/{webhook_id}/deliveries"))
+            .header(Header::new("authorization", code.to_string()))
+            .dispatch()
+            .await;
+        deliveries = response.into_json().await.expect("deliveries");
+        if !deliveries.is_empty() && deliveries.iter().all(|d| d.status != "pending") {
+            break;
+        }
+        sleep(Duration::from_millis(50)).await;
+    }
+
+    deliveries
+}
+
+#[tokio::test]
+#[serial]
+async fn test_webhook_delivery() {
+    if !common::is_database_running() {
+        eprintln!("Warning: Database not running, skipping test");
+        return;
+    }
+    let server = MockServer::start();
+    setup(&server);
+
+    let token = common::create_test_token("/test/", true, true);
+    let reader = common::create_test_token("/test/", true, false);
+
+    let hook = server.mock(|when, then| {
+        when.method(POST).path("/hook").matches(is_signed);
+        then.status(200);
+    });
+
+    let client = Client::tracked(rocket())
+        .await
+        .expect("valid rocket instance");
+
+    let response = client
+        .post("/webhooks")
+        .header(Header::new("authorization", reader.code.clone()))
+        .json(&WebhookInput {
+            namespace: "/test/".to_string(),
+            url: server.url("/hook"),
+        })
+        .dispatch()
+        .await;
+    assert_eq!(response.status(), Status::Unauthorized);
+
+    let webhook = register(&client, &token.code, "/test/watched/", server.url("/hook")).await;
+
+    upload(&client, &token.code, "test/other").await;
+    upload(&client, &token.code, "test/watched/space").await;
+
+    let deliveries = settled_deliveries(&client, &token.code, webhook.id).await;
+    assert_eq!(deliveries.len(), 1);
+    assert_eq!(deliveries[0].operation, "upload");
+    assert_eq!(deliveries[0].namespace, "/test/watched/space");
+    assert_eq!(deliveries[0].status, "succeeded");
+    assert_eq!(deliveries[0].attempts, 1);
+    hook.assert();
+
+    common::teardown_database();
+}
+
+#[tokio::test]
+#[serial]
+async fn test_webhook_retries() {
+    if !common::is_database_running() {
+        eprintln!("Warning: Database not running, skipping test");
+        return;
+    }
+    let server = MockServer::start();
+    setup(&server);
+
+    let token = common::create_test_token("/test/", true, true);
+
+    let hook = server.mock(|when, then| {
+        when.method(POST).path("/hook");
+        then.status(503);
+    });
+
+    let client = Client::tracked(rocket())
+        .await
+        .expect("valid rocket instance");
+
+    let webhook = register(&client, &token.code, "/test/", server.url("/hook")).await;
+
+    upload(&client, &token.code, "test/space").await;
+
+    let deliveries = settled_deliveries(&client, &token.code, webhook.id).await;
+    assert_eq!(deliveries.len(), 1);
+    assert_eq!(deliveries[0].status, "failed");
+    assert_eq!(deliveries[0].attempts, 5);
+    assert_eq!(deliveries[0].response_status, Some(503));
+    hook.assert_hits(5);
+
+    common::teardown_database();
+}
+
+#[tokio::test]
+#[serial]
+async fn test_webhook_private_urls() {
+    if !common::is_database_running() {
+        eprintln!("Warning: Database not running, skipping test");
+        return;
+    }
+    let server = MockServer::start();
+    setup(&server);
+    env::remove_var("METTA_KG_WEBHOOK_ALLOWED_HOSTS");
+
+    let token = common::create_test_token("/test/", true, true);
+
+    let client = Client::tracked(rocket())
+        .await
+        .expect("valid rocket instance");
+
+    for url in [
+        server.url("/hook"),
+        "http://localhost/hook".to_string(),
+        "http://10.0.0.1/hook".to_string(),
+        "http://169.254.169.254/latest/meta-data".to_string(),
+        "http://[::1]/hook".to_string(),
+        "http://[::ffff:192.168.0.1]/hook".to_string(),
+        "file:///etc/passwd".to_string(),
+    ] {
+        let response = client
+            .post("/webhooks")
+            .header(Header::new("authorization", token.code.clone()))
+            .json(&WebhookInput {
+                namespace: "/test/".to_string(),
+                url: url.clone(),
+            })
+            .dispatch()
+            .await;
+        assert_eq!(response.status(), Status::BadRequest, "{url}");
+    }
+
+    common::teardown_database();
+}
@@ -0,0 +1,66 @@
+import { API_URL } from "./api";
+import { rootToken } from "./state";
+import { SpaceEvent } from "./types";
+
+/**
+ * Streams the events of the operations on the `path` space and its subspaces
+ * to `onEvent`. `EventSource` can not send the token, so the server-sent
+ * events are read with `fetch`. Returns a function that closes the stream.
+ */
+export const subscribeToEvents = (
+  path: string,
+  onEvent: (event: SpaceEvent) => void
+): (() => void) => {
+  const auth = rootToken();
+  if (!auth) return () => {};
+
+  const controller = new AbortController();
+  const url = new URL(`/spaces/_events${path}`, API_URL);
+
+  const read = async () => {
+    const response = await fetch(url, {
+      headers: { Authorization: auth },
+      signal: controller.signal,
+    });
+    if (!response.ok || !response.body) return;
+
+    const reader = response.body.pipeThrough(new TextDecoderStream()).getReader();
+    let buffer = "";
+
+    for (;;) {
+      const { value, done } = await reader.read();
+      if (done) return;
+
+      // events are separated by a blank line
+      buffer += value;
+      const messages = buffer.split("\n\n");
+      buffer = messages.pop() ?? "";
+
+      for (const message of messages) {
+        const data = message
+          .split("\n")
+          .filter((line) => line.startsWith("data:"))
+          .map((line) => line.slice(5).trimStart())
+          .join("\n");
+        if (data) onEvent(JSON.parse(data) as SpaceEvent);
+      }
+    }
+  };
+
+  // the stream ends with an error once it is aborted
+  read().catch(() => {});
+
+  return () => controller.abort();
+};
+
+/** A short description of an event, e.g. `transform finished: 3 atoms` */
+export const describeEvent = (event: SpaceEvent) => {
+  const details = [
+    event.message,
+    event.count != null ? `${event.count} atoms` : null,
+  ].filter(Boolean);
+
+  return details.length > 0
+    ? `${event.operation} ${event.kind}: ${details.join(", ")}`
+    : `${event.operation} ${event.kind}`;
+};
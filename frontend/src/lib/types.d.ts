@@ -16,6 +16,12 @@ export interface Token {
   permission_share_read: boolean;
   permission_share_write: boolean;
   parent: number;
+  rate_limit_read?: number | null;
+  rate_limit_write?: number | null;
+  rate_limit_translate?: number | null;
+  concurrency_limit_read?: number | null;
+  concurrency_limit_write?: number | null;
+  concurrency_limit_translate?: number | null;
 }
 
 export interface ExploreDetail {
@@ -27,3 +33,13 @@ export interface Mm2Input {
   pattern: string[] | string;
   template: string[] | string;
 }
+
+export interface SpaceEvent {
+  kind: "started" | "progress" | "finished" | "failed";
+  operation: string;
+  namespace: string;
+  token_id: number;
+  job_id?: number | null;
+  count?: number | null;
+  message?: string | null;
+}
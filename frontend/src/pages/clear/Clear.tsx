@@ -4,7 +4,13 @@ import { CommandCard } from "~/components/common/CommandCard";
 import MettaEditor from "~/components/common/MettaEditor";
 import Loader2 from "lucide-solid/icons/loader-2";
 import { formatedNamespace } from "~/lib/state";
-import { expression, setExpression, isLoading, handleClear } from "./lib";
+import {
+  expression,
+  setExpression,
+  isLoading,
+  handleClear,
+  progress,
+} from "./lib";
 
 const ClearPage: Component = () => {
   return (
@@ -35,7 +41,7 @@ const ClearPage: Component = () => {
               </Show>
             </Button>
             <p class="text-sm text-muted-foreground">
-              This will permanently delete all matching data.
+              {progress() ?? "This will permanently delete all matching data."}
             </p>
           </div>
         </div>
@@ -2,9 +2,12 @@ import { createSignal } from "solid-js";
 import { showToast } from "~/components/ui/Toast";
 import { clearSpace } from "~/lib/api";
 import { refreshSpace } from "../load/lib";
+import { describeEvent, subscribeToEvents } from "~/lib/events";
 
 export const [expression, setExpression] = createSignal("$x \n \n \n");
 export const [isLoading, setIsLoading] = createSignal(false);
+/** the latest event of the running clear */
+export const [progress, setProgress] = createSignal<string | null>(null);
 
 export const handleClear = async (spacePath: string) => {
   if (!expression().trim()) {
@@ -17,6 +20,10 @@ export const handleClear = async (spacePath: string) => {
   }
 
   setIsLoading(true);
+  setProgress(null);
+  const closeEvents = subscribeToEvents(spacePath, (event) => {
+    if (event.operation === "clear") setProgress(describeEvent(event));
+  });
 
   try {
     const success = await clearSpace(spacePath);
@@ -43,6 +50,7 @@ export const handleClear = async (spacePath: string) => {
       variant: "destructive",
     });
   } finally {
+    closeEvents();
     setIsLoading(false);
   }
 };
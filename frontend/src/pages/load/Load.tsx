@@ -1,4 +1,4 @@
-import { Show } from "solid-js";
+import { createEffect, createSignal, onCleanup, Show } from "solid-js";
 import MettaEditor from "~/components/common/MettaEditor";
 import ZoomControls from "./components/ZoomControls";
 import MinimizeControls from "./components/MinimizeControls";
@@ -6,6 +6,8 @@ import D3TreeGraph from "./components/D3SpaceGraph";
 import Plus from "lucide-solid/icons/plus";
 import Minus from "lucide-solid/icons/minus";
 import { initNodesFromApiResponse } from "~/lib/space";
+import { describeEvent, subscribeToEvents } from "~/lib/events";
+import { formatedNamespace } from "~/lib/state";
 import {
   mettaText,
   handleTextChange,
@@ -19,12 +21,24 @@ import {
   handleCollapseToRoot,
   setupGraphApi,
   handleToggleCard,
+  refreshSpace,
 } from "./lib";
 
 import "../../styles/variables.css";
 import "../../styles/components.css";
 
 const LoadPage = () => {
+  const [lastEvent, setLastEvent] = createSignal<string | null>(null);
+
+  // show the writes to the space as they happen, and reload it after each
+  createEffect(() => {
+    const closeEvents = subscribeToEvents(formatedNamespace(), (event) => {
+      setLastEvent(describeEvent(event));
+      if (event.kind === "finished") refreshSpace();
+    });
+    onCleanup(closeEvents);
+  });
+
   return (
     <div class="relative h-full w-full bg-background">
       {/* Pattern Editor Card */}
@@ -79,6 +93,12 @@ const LoadPage = () => {
       <div class="absolute top-2.5 right-[74px] z-10">
         <MinimizeControls onToggleCards={handleToggleCard} />
       </div>
+      {/* Latest Event */}
+      <Show when={lastEvent()}>
+        <div class="absolute top-2.5 left-2.5 z-10 rounded border border-neutral-700 bg-neutral-900 px-3 py-1 text-sm text-neutral-300">
+          {lastEvent()}
+        </div>
+      </Show>
       {/* D3 Tree Canvas */}
       <div class="absolute inset-0 w-full h-full flex" style="z-index: 0;">
         <Show
@@ -2,7 +2,13 @@ import { Component, createSignal, Show, onCleanup } from "solid-js";
 import MettaEditor from "../../components/common/MettaEditor";
 import { CommandCard } from "~/components/common/CommandCard";
 import { formatedNamespace } from "~/lib/state";
-import { isLoading, isPolling, executeTransform, stopPolling } from "./lib";
+import {
+  isLoading,
+  isPolling,
+  executeTransform,
+  stopPolling,
+  progress,
+} from "./lib";
 
 const TransformPage: Component = () => {
   const [sExpr, setSExpr] = createSignal(`(transform 
@@ -66,6 +72,9 @@ const TransformPage: Component = () => {
             Transforming...
           </Show>
         </button>
+        <Show when={progress()}>
+          <p class="mt-2 text-sm text-muted-foreground">{progress()}</p>
+        </Show>
       </CommandCard>
     </div>
   );
@@ -4,18 +4,45 @@ import { Mm2Input } from "~/lib/types";
 import { showToast } from "~/components/ui/Toast";
 import { parseTransformExpression } from "~/lib/utils";
 import { refreshSpace } from "../load/lib";
+import { describeEvent, subscribeToEvents } from "~/lib/events";
 
 export const [isLoading, setIsLoading] = createSignal(false);
 export const [isPolling, setIsPolling] = createSignal(false);
+/** the latest event of the running transformation */
+export const [progress, setProgress] = createSignal<string | null>(null);
 
 let pollingIntervalId: NodeJS.Timeout | null = null;
+let closeEvents: (() => void) | null = null;
 
 export const stopPolling = () => {
   if (pollingIntervalId) clearInterval(pollingIntervalId);
   pollingIntervalId = null;
+  closeEvents?.();
+  closeEvents = null;
   setIsPolling(false);
 };
 
+/** Shows the progress of the transformations of `spacePath` as it is reported */
+const watchEvents = (spacePath: string) => {
+  setProgress(null);
+  closeEvents = subscribeToEvents(spacePath, (event) => {
+    if (event.operation !== "transform") return;
+    setProgress(describeEvent(event));
+
+    if (event.kind === "finished" && isPolling()) {
+      stopPolling();
+      showToast({
+        title: "Transform Completed",
+        description: describeEvent(event),
+      });
+      refreshSpace();
+    } else if (event.kind === "failed") {
+      // the failure is reported by the response to the request
+      stopPolling();
+    }
+  });
+};
+
 export const startPolling = (spacePath: string) => {
   setIsPolling(true);
   pollingIntervalId = setInterval(async () => {
@@ -52,6 +79,7 @@ export const executeTransform = async (sExpr: string, spacePath: string) => {
 
   setIsLoading(true);
   stopPolling();
+  watchEvents(spacePath);
 
   try {
     if (!(await isPathClear(spacePath))) {
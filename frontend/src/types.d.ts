@@ -10,6 +10,12 @@ interface Token {
   permission_share_write: boolean;
   permission_share_share: boolean;
   parent: number | null;
+  rate_limit_read?: number | null;
+  rate_limit_write?: number | null;
+  rate_limit_translate?: number | null;
+  concurrency_limit_read?: number | null;
+  concurrency_limit_write?: number | null;
+  concurrency_limit_translate?: number | null;
 }
 
 export enum EditorMode {